@@ -0,0 +1,224 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam, Generics, Index, Meta,
+    NestedMeta,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Repr {
+    List,
+    ProperList,
+    Curry,
+}
+
+fn get_repr(input: &DeriveInput) -> Repr {
+    for attr in &input.attrs {
+        if !attr.path.is_ident("clvm") {
+            continue;
+        }
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                    if path.is_ident("list") {
+                        return Repr::List;
+                    } else if path.is_ident("proper_list") {
+                        return Repr::ProperList;
+                    } else if path.is_ident("curry") {
+                        return Repr::Curry;
+                    } else {
+                        panic!("Unknown clvm representation, expected list, proper_list or curry");
+                    }
+                }
+            }
+        }
+    }
+    Repr::ProperList
+}
+
+struct FieldInfo {
+    accessors: Vec<TokenStream2>,
+    bindings: Vec<syn::Ident>,
+    types: Vec<syn::Type>,
+    constructor: TokenStream2,
+}
+
+fn get_fields(input: &DeriveInput) -> FieldInfo {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => panic!("ToClvm and FromClvm can only be derived for structs"),
+    };
+    let mut accessors = Vec::new();
+    let mut bindings = Vec::new();
+    let mut types = Vec::new();
+    let constructor = match &data.fields {
+        Fields::Named(fields) => {
+            let mut names = Vec::new();
+            for field in &fields.named {
+                let ident = field.ident.clone().unwrap();
+                accessors.push(quote!(self.#ident));
+                bindings.push(ident.clone());
+                types.push(field.ty.clone());
+                names.push(ident);
+            }
+            quote!(Self { #(#names),* })
+        }
+        Fields::Unnamed(fields) => {
+            let mut names = Vec::new();
+            for (i, field) in fields.unnamed.iter().enumerate() {
+                let index = Index::from(i);
+                let ident = format_ident!("field_{}", i);
+                accessors.push(quote!(self.#index));
+                bindings.push(ident.clone());
+                types.push(field.ty.clone());
+                names.push(ident);
+            }
+            quote!(Self(#(#names),*))
+        }
+        Fields::Unit => quote!(Self),
+    };
+    FieldInfo {
+        accessors,
+        bindings,
+        types,
+        constructor,
+    }
+}
+
+fn add_trait_bounds(mut generics: Generics, bound: TokenStream2) -> Generics {
+    for param in &mut generics.params {
+        if let GenericParam::Type(type_param) = param {
+            type_param.bounds.push(parse_quote!(#bound));
+        }
+    }
+    generics
+}
+
+#[proc_macro_derive(ToClvm, attributes(clvm))]
+pub fn derive_to_clvm(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let repr = get_repr(&input);
+    let fields = get_fields(&input);
+    let name = &input.ident;
+    let crate_name = quote!(::druid_garden_chia_types);
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(#crate_name::clvm::clvm_traits::ToClvm),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let accessors = &fields.accessors;
+    let body = match repr {
+        Repr::ProperList => quote! {
+            let items: Vec<#crate_name::clvm::program::Program> = vec![
+                #(#crate_name::clvm::clvm_traits::ToClvm::to_clvm(&#accessors)?),*
+            ];
+            Ok(items.into())
+        },
+        Repr::Curry => quote! {
+            let items: Vec<#crate_name::clvm::program::Program> = vec![
+                #(#crate_name::clvm::clvm_traits::ToClvm::to_clvm(&#accessors)?),*
+            ];
+            Ok(#crate_name::clvm::clvm_traits::curried_args_to_clvm(items))
+        },
+        Repr::List => {
+            if accessors.is_empty() {
+                quote!(Ok(#crate_name::clvm::program::Program::null()))
+            } else {
+                let last = &accessors[accessors.len() - 1];
+                let rest: Vec<&TokenStream2> =
+                    accessors[..accessors.len() - 1].iter().rev().collect();
+                quote! {
+                    let mut rtn = #crate_name::clvm::clvm_traits::ToClvm::to_clvm(&#last)?;
+                    #(rtn = #crate_name::clvm::clvm_traits::ToClvm::to_clvm(&#rest)?.cons(&rtn);)*
+                    Ok(rtn)
+                }
+            }
+        }
+    };
+    let expanded = quote! {
+        impl #impl_generics #crate_name::clvm::clvm_traits::ToClvm for #name #ty_generics #where_clause {
+            fn to_clvm(&self) -> Result<#crate_name::clvm::program::Program, Box<dyn std::error::Error>> {
+                #body
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(FromClvm, attributes(clvm))]
+pub fn derive_from_clvm(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let repr = get_repr(&input);
+    let fields = get_fields(&input);
+    let name = &input.ident;
+    let crate_name = quote!(::druid_garden_chia_types);
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(#crate_name::clvm::clvm_traits::FromClvm),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let bindings = &fields.bindings;
+    let types = &fields.types;
+    let constructor = &fields.constructor;
+    let body = match repr {
+        Repr::ProperList => quote! {
+            let mut current = program.clone();
+            #(
+                let (first, rest) = match current.as_pair() {
+                    Some(pair) => pair,
+                    None => return Err(format!("Expected pair for field {}", stringify!(#bindings)).into()),
+                };
+                let #bindings = <#types as #crate_name::clvm::clvm_traits::FromClvm>::from_clvm(&first)?;
+                current = rest;
+            )*
+            #crate_name::clvm::clvm_traits::expect_nil(&current)?;
+            Ok(#constructor)
+        },
+        Repr::Curry => {
+            let count = bindings.len();
+            let indexes: Vec<Index> = (0..count).map(Index::from).collect();
+            quote! {
+                let args = #crate_name::clvm::clvm_traits::curried_args_from_clvm(program, #count)?;
+                #(
+                    let #bindings = <#types as #crate_name::clvm::clvm_traits::FromClvm>::from_clvm(&args[#indexes])?;
+                )*
+                Ok(#constructor)
+            }
+        }
+        Repr::List => {
+            if bindings.is_empty() {
+                quote! {
+                    #crate_name::clvm::clvm_traits::expect_nil(program)?;
+                    Ok(#constructor)
+                }
+            } else {
+                let last_binding = &bindings[bindings.len() - 1];
+                let last_type = &types[types.len() - 1];
+                let rest_bindings = &bindings[..bindings.len() - 1];
+                let rest_types = &types[..types.len() - 1];
+                quote! {
+                    let mut current = program.clone();
+                    #(
+                        let (first, rest) = match current.as_pair() {
+                            Some(pair) => pair,
+                            None => return Err(format!("Expected pair for field {}", stringify!(#rest_bindings)).into()),
+                        };
+                        let #rest_bindings = <#rest_types as #crate_name::clvm::clvm_traits::FromClvm>::from_clvm(&first)?;
+                        current = rest;
+                    )*
+                    let #last_binding = <#last_type as #crate_name::clvm::clvm_traits::FromClvm>::from_clvm(&current)?;
+                    Ok(#constructor)
+                }
+            }
+        }
+    };
+    let expanded = quote! {
+        impl #impl_generics #crate_name::clvm::clvm_traits::FromClvm for #name #ty_generics #where_clause {
+            fn from_clvm(program: &#crate_name::clvm::program::Program) -> Result<Self, Box<dyn std::error::Error>> {
+                #body
+            }
+        }
+    };
+    expanded.into()
+}
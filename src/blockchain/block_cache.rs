@@ -0,0 +1,45 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct BlockCache {
+    block_records: HashMap<Bytes32, BlockRecord>,
+    height_to_hash: HashMap<u32, Bytes32>,
+    sub_epoch_summaries: HashMap<u32, SubEpochSummary>,
+}
+impl BlockCache {
+    pub fn new() -> Self {
+        BlockCache::default()
+    }
+
+    //Records are expected to be added along a single chain, later records replace the height entry
+    pub fn add_block_record(&mut self, block_record: BlockRecord) {
+        self.height_to_hash
+            .insert(block_record.height, block_record.header_hash.clone());
+        if let Some(ses) = &block_record.sub_epoch_summary_included {
+            self.sub_epoch_summaries
+                .insert(block_record.height, ses.clone());
+        }
+        self.block_records
+            .insert(block_record.header_hash.clone(), block_record);
+    }
+}
+impl BlockchainInterface for BlockCache {
+    fn try_block_record(&self, header_hash: &Bytes32) -> Option<BlockRecord> {
+        self.block_records.get(header_hash).cloned()
+    }
+    fn height_to_hash(&self, height: u32) -> Option<Bytes32> {
+        self.height_to_hash.get(&height).cloned()
+    }
+    fn get_ses_heights(&self) -> Vec<u32> {
+        let mut heights: Vec<u32> = self.sub_epoch_summaries.keys().cloned().collect();
+        heights.sort_unstable();
+        heights
+    }
+    fn get_ses(&self, height: u32) -> Option<SubEpochSummary> {
+        self.sub_epoch_summaries.get(&height).cloned()
+    }
+}
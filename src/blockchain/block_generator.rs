@@ -0,0 +1,280 @@
+use crate::blockchain::coin::Coin;
+use crate::blockchain::coin_spend::CoinSpend;
+use crate::blockchain::condition_with_args::ConditionWithArgs;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::full_block::FullBlock;
+use crate::blockchain::npc::NPC;
+use crate::blockchain::npc_result::NPCResult;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::blockchain::transactions_info::TransactionsInfo;
+use crate::clvm::condition_utils::parse_sexp_to_conditions;
+use crate::clvm::program::Program;
+use crate::clvm::serialized_program::SerializedProgram;
+use crate::clvm::utils::hash_256;
+use crate::list;
+use clvmr::allocator::Allocator;
+use hex::encode;
+use lazy_static::lazy_static;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+pub const COST_PER_BYTE: u64 = 12000;
+
+lazy_static! {
+    //chialisp_deserialisation, passed to every generator so it can decompress its refs
+    pub static ref DESERIALIZE_MOD: SerializedProgram = SerializedProgram::from_hex("ff02ffff01ff05ffff02ff3effff04ff02ffff04ff05ff8080808080ffff04ffff01ffffff81ff7fff81df81bfffffff02ffff03ffff09ff0bffff01818080ffff01ff04ff80ffff04ff05ff808080ffff01ff02ffff03ffff0aff0bff1880ffff01ff02ff1affff04ff02ffff04ffff02ffff03ffff0aff0bff1c80ffff01ff02ffff03ffff0aff0bff1480ffff01ff0880ffff01ff04ffff0effff18ffff011fff0b80ffff0cff05ff80ffff01018080ffff04ffff0cff05ffff010180ff80808080ff0180ffff01ff04ffff18ffff013fff0b80ffff04ff05ff80808080ff0180ff80808080ffff01ff04ff0bffff04ff05ff80808080ff018080ff0180ff04ffff0cff15ff80ff0980ffff04ffff0cff15ff0980ff808080ffff04ffff04ff05ff1380ffff04ff2bff808080ffff02ff16ffff04ff02ffff04ff09ffff04ffff02ff3effff04ff02ffff04ff15ff80808080ff8080808080ff02ffff03ffff09ffff0cff05ff80ffff010180ff1080ffff01ff02ff2effff04ff02ffff04ffff02ff3effff04ff02ffff04ffff0cff05ffff010180ff80808080ff80808080ffff01ff02ff12ffff04ff02ffff04ffff0cff05ffff010180ffff04ffff0cff05ff80ffff010180ff808080808080ff0180ff018080".to_string());
+}
+
+pub trait GeneratorBlockCache {
+    fn get_generator_for_block_height(
+        &self,
+        height: u32,
+    ) -> Result<Option<SerializedProgram>, Box<dyn Error>>;
+}
+
+pub fn resolve_generator_refs(
+    cache: &dyn GeneratorBlockCache,
+    generator_ref_list: &[u32],
+) -> Result<Vec<SerializedProgram>, Box<dyn Error>> {
+    let mut rtn: Vec<SerializedProgram> = Vec::new();
+    for height in generator_ref_list {
+        match cache.get_generator_for_block_height(*height)? {
+            Some(generator) => rtn.push(generator),
+            None => return Err(format!("No generator found for block at height {}", height).into()),
+        }
+    }
+    Ok(rtn)
+}
+
+pub fn generator_root(generator: &Option<SerializedProgram>) -> Bytes32 {
+    match generator {
+        Some(generator) => Bytes32::new(hash_256(generator.to_bytes())),
+        None => Bytes32::new(vec![0u8; 32]),
+    }
+}
+
+pub fn generator_refs_root(generator_ref_list: &[u32]) -> Bytes32 {
+    if generator_ref_list.is_empty() {
+        return Bytes32::new(vec![1u8; 32]);
+    }
+    let mut to_hash: Vec<u8> = Vec::new();
+    for height in generator_ref_list {
+        to_hash.extend(height.to_be_bytes());
+    }
+    Bytes32::new(hash_256(to_hash))
+}
+
+pub fn validate_generator_roots(
+    constants: &Constants,
+    height: u32,
+    generator: &Option<SerializedProgram>,
+    generator_ref_list: &[u32],
+    transactions_info: &TransactionsInfo,
+) -> Result<(), Box<dyn Error>> {
+    if generator_root(generator) != transactions_info.generator_root {
+        return Err("Invalid transactions generator hash".into());
+    }
+    if !generator_ref_list.is_empty() && generator.is_none() {
+        return Err("Generator refs without a transactions generator".into());
+    }
+    if generator_refs_root(generator_ref_list) != transactions_info.generator_refs_root {
+        return Err("Invalid transactions generator refs root".into());
+    }
+    if generator_ref_list.len() > constants.max_generator_ref_list_size {
+        return Err("Too many generator refs".into());
+    }
+    if generator_ref_list
+        .iter()
+        .any(|ref_height| *ref_height >= height)
+    {
+        return Err("Generator refs point to a future block".into());
+    }
+    Ok(())
+}
+
+pub fn validate_block_generator_roots(
+    constants: &Constants,
+    block: &FullBlock,
+) -> Result<(), Box<dyn Error>> {
+    match &block.transactions_info {
+        Some(transactions_info) => validate_generator_roots(
+            constants,
+            block.reward_chain_block.height as u32,
+            &block.transactions_generator,
+            &block.transactions_generator_ref_list,
+            transactions_info,
+        ),
+        None => {
+            if block.transactions_generator.is_some()
+                || !block.transactions_generator_ref_list.is_empty()
+            {
+                Err("Transactions generator on a non transaction block".into())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn run_full_block_generator(
+    block: &FullBlock,
+    cache: &dyn GeneratorBlockCache,
+    max_cost: u64,
+    flags: u32,
+) -> Result<Option<(NPCResult, Vec<CoinSpend>)>, Box<dyn Error>> {
+    match &block.transactions_generator {
+        Some(generator) => {
+            let generator_refs =
+                resolve_generator_refs(cache, &block.transactions_generator_ref_list)?;
+            Ok(Some(run_block_generator(
+                generator,
+                &generator_refs,
+                max_cost,
+                flags,
+            )?))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn generator_args(generator_refs: &[SerializedProgram]) -> Result<Program, Box<dyn Error>> {
+    let refs: Vec<Program> = generator_refs
+        .iter()
+        .map(|generator_ref| Program::from(generator_ref.to_bytes()))
+        .collect();
+    Ok(list![DESERIALIZE_MOD.clone().to_program()?, refs])
+}
+
+pub fn generator_spends(
+    generator: &SerializedProgram,
+    generator_refs: &[SerializedProgram],
+    max_cost: u64,
+    flags: u32,
+) -> Result<(u64, Vec<CoinSpend>), Box<dyn Error>> {
+    let byte_cost = generator.to_bytes().len() as u64 * COST_PER_BYTE;
+    if byte_cost > max_cost {
+        return Err("Block generator exceeds max cost".into());
+    }
+    let mut allocator = Allocator::new();
+    let (cost, result) = generator.run_with_flags(
+        &mut allocator,
+        max_cost - byte_cost,
+        flags,
+        &generator_args(generator_refs)?,
+    )?;
+    let output = Program::from_allocator(Arc::new(Mutex::new(allocator)), result);
+    let mut spends: Vec<CoinSpend> = Vec::new();
+    for spend in output.first()?.iter() {
+        let puzzle = spend.at("rf")?;
+        spends.push(CoinSpend {
+            coin: Coin {
+                parent_coin_info: spend.first()?.as_bytes32()?,
+                puzzle_hash: puzzle.tree_hash(),
+                amount: spend.at("rrf")?.as_u64()?,
+            },
+            puzzle_reveal: puzzle.into(),
+            solution: spend.at("rrrf")?.into(),
+        });
+    }
+    Ok((cost, spends))
+}
+
+pub fn run_block_generator(
+    generator: &SerializedProgram,
+    generator_refs: &[SerializedProgram],
+    max_cost: u64,
+    flags: u32,
+) -> Result<(NPCResult, Vec<CoinSpend>), Box<dyn Error>> {
+    let byte_cost = generator.to_bytes().len() as u64 * COST_PER_BYTE;
+    let (mut clvm_cost, spends) = generator_spends(generator, generator_refs, max_cost, flags)?;
+    let mut npc_list: Vec<NPC> = Vec::new();
+    for spend in &spends {
+        let cost_left = max_cost
+            .checked_sub(byte_cost + clvm_cost)
+            .ok_or("Block generator exceeds max cost")?;
+        let mut allocator = Allocator::new();
+        let (cost, result) = spend.puzzle_reveal.run_with_flags(
+            &mut allocator,
+            cost_left,
+            flags,
+            &spend.solution.clone().to_program()?,
+        )?;
+        clvm_cost += cost;
+        let output: SerializedProgram =
+            Program::from_allocator(Arc::new(Mutex::new(allocator)), result).into();
+        npc_list.push(NPC {
+            coin_name: spend.coin.name(),
+            puzzle_hash: spend.coin.puzzle_hash.clone(),
+            conditions: group_conditions(parse_sexp_to_conditions(&output)?),
+        });
+    }
+    Ok((
+        NPCResult {
+            error: None,
+            clvm_cost,
+            npc_list,
+        },
+        spends,
+    ))
+}
+
+fn group_conditions(conditions: Vec<ConditionWithArgs>) -> Vec<(u8, Vec<(u8, String)>)> {
+    //Keeps opcodes in the order they were first created, each condition holds its serialized args
+    let mut rtn: Vec<(u8, Vec<(u8, String)>)> = Vec::new();
+    for condition in conditions {
+        let opcode = condition.opcode as u8;
+        let args: Vec<Program> = condition.vars.into_iter().map(Program::from).collect();
+        let entry = (opcode, encode(Program::from(args).serialized()));
+        match rtn
+            .iter_mut()
+            .find(|(group_opcode, _)| *group_opcode == opcode)
+        {
+            Some((_, group)) => group.push(entry),
+            None => rtn.push((opcode, vec![entry])),
+        }
+    }
+    rtn
+}
+
+pub fn get_puzzle_and_solution_for_coin(
+    generator: &SerializedProgram,
+    generator_refs: &[SerializedProgram],
+    max_cost: u64,
+    flags: u32,
+    coin: &Coin,
+) -> Result<Option<(SerializedProgram, SerializedProgram)>, Box<dyn Error>> {
+    let (_cost, spends) = generator_spends(generator, generator_refs, max_cost, flags)?;
+    Ok(spends
+        .into_iter()
+        .find(|spend| spend.coin == *coin)
+        .map(|spend| (spend.puzzle_reveal, spend.solution)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::MOD;
+    use crate::clvm::utils::INFINITE_COST;
+
+    #[test]
+    fn test_deserialize_mod_parses_programs() {
+        let mut allocator = Allocator::new();
+        let serialized: Program = MOD.to_bytes().into();
+        let (_cost, result) = DESERIALIZE_MOD
+            .run_with_cost(&mut allocator, INFINITE_COST, &list![serialized])
+            .unwrap();
+        let result = Program::from_allocator(Arc::new(Mutex::new(allocator)), result);
+        assert_eq!(result, MOD.clone().to_program().unwrap());
+    }
+
+    #[test]
+    fn test_generator_args_layout() {
+        let generator_ref = SerializedProgram::from_bytes(&MOD.to_bytes());
+        let args = generator_args(&[generator_ref]).unwrap().as_list().unwrap();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0], DESERIALIZE_MOD.clone().to_program().unwrap());
+        let refs = args[1].as_list().unwrap();
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].as_vec().unwrap(), MOD.to_bytes());
+    }
+}
@@ -0,0 +1,1601 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::challenge_chain_subslot::ChallengeChainSubSlot;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::difficulty_adjustment::can_finish_sub_and_full_epoch;
+use crate::blockchain::foliage_block_data::FoliageBlockData;
+use crate::blockchain::full_block_to_block_record::calculate_deficit;
+use crate::blockchain::header_block::HeaderBlock;
+use crate::blockchain::make_sub_epoch_summary::make_sub_epoch_summary;
+use crate::blockchain::pot_iterations::{
+    calculate_ip_iters, calculate_iterations_quality, calculate_sp_interval_iters,
+    calculate_sp_iters, is_overflow_block,
+};
+use crate::blockchain::proof_of_space::ProofOfSpace;
+use crate::blockchain::sized_bytes::{hex_to_bytes, Bytes32, SizedBytes};
+use crate::blockchain::validation_error::{ValidationError, ValidationErrorCode};
+use crate::blockchain::vdf::verify_vdf;
+use crate::blockchain::vdf_info::VdfInfo;
+use crate::blockchain::vdf_output::VdfOutput;
+use crate::blockchain::vdf_proof::VdfProof;
+use crate::clvm::bls_bindings::verify_signature;
+use crate::clvm::utils::hash_256;
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn invalid(error_code: ValidationErrorCode, error_message: &str) -> ValidationError {
+    ValidationError::new(error_code, error_message.to_string())
+}
+
+fn check<T>(
+    result: Result<T, Box<dyn Error>>,
+    error_code: ValidationErrorCode,
+) -> Result<T, ValidationError> {
+    result.map_err(|e| ValidationError::new(error_code, e.to_string()))
+}
+
+fn challenge_chain_hash(
+    challenge_chain: &ChallengeChainSubSlot,
+) -> Result<Bytes32, ValidationError> {
+    Ok(Bytes32::new(check(
+        challenge_chain.hash(),
+        ValidationErrorCode::InvalidBlockData,
+    )?))
+}
+
+fn verify_vdf_proof(
+    constants: &Constants,
+    input_el: &VdfOutput,
+    target_vdf_info: &VdfInfo,
+    vdf_info: &VdfInfo,
+    proof: &VdfProof,
+) -> bool {
+    //Compactified proofs always start from the default element and cover the full iterations
+    if proof.normalized_to_identity {
+        verify_vdf(
+            constants,
+            &vdf_info.challenge,
+            &VdfOutput::default_element(),
+            vdf_info,
+            proof,
+        )
+    } else {
+        verify_vdf(
+            constants,
+            &target_vdf_info.challenge,
+            input_el,
+            target_vdf_info,
+            proof,
+        )
+    }
+}
+
+fn last_block_before(
+    blocks: &impl BlockchainInterface,
+    prev_b: Option<&BlockRecord>,
+    slot_start_iters: u128,
+    total_iters: u128,
+) -> Result<Option<BlockRecord>, ValidationError> {
+    let mut curr = match prev_b {
+        Some(prev_b) => prev_b.clone(),
+        None => return Ok(None),
+    };
+    loop {
+        if curr.total_iters <= slot_start_iters {
+            return Ok(None);
+        }
+        if curr.total_iters < total_iters {
+            return Ok(Some(curr));
+        }
+        if curr.height == 0 {
+            return Ok(None);
+        }
+        curr = check(
+            blocks.block_record(&curr.prev_hash),
+            ValidationErrorCode::InvalidPrevBlockHash,
+        )?;
+    }
+}
+
+fn prev_challenge_slot_hashes(
+    blocks: &impl BlockchainInterface,
+    prev_b: &BlockRecord,
+    count: usize,
+) -> Result<Vec<Bytes32>, ValidationError> {
+    //Most recent challenge slot hash first
+    let mut reversed_hashes: Vec<Bytes32> = Vec::new();
+    let mut curr = prev_b.clone();
+    while reversed_hashes.len() < count {
+        if let Some(hashes) = &curr.finished_challenge_slot_hashes {
+            reversed_hashes.extend(hashes.iter().rev().cloned());
+        }
+        if curr.height == 0 {
+            break;
+        }
+        curr = check(
+            blocks.block_record(&curr.prev_hash),
+            ValidationErrorCode::InvalidPrevBlockHash,
+        )?;
+    }
+    if reversed_hashes.len() < count {
+        return Err(invalid(
+            ValidationErrorCode::InvalidPrevChallengeSlotHash,
+            "Not enough finished sub slots before block",
+        ));
+    }
+    Ok(reversed_hashes)
+}
+
+fn reward_slot_hash(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    prev_b: Option<&BlockRecord>,
+    slots_back: usize,
+) -> Result<Bytes32, ValidationError> {
+    //Reward chain hash ending the sub slot slots_back sub slots before the block's own sub slot
+    let mut reversed_hashes: Vec<Bytes32> = header_block
+        .finished_sub_slots
+        .iter()
+        .rev()
+        .map(|sub_slot| sub_slot.reward_chain.hash())
+        .collect();
+    let mut curr = prev_b.cloned();
+    while reversed_hashes.len() <= slots_back {
+        let block = match curr {
+            Some(block) => block,
+            None => break,
+        };
+        if let Some(hashes) = &block.finished_reward_slot_hashes {
+            reversed_hashes.extend(hashes.iter().rev().cloned());
+        }
+        curr = if block.height == 0 {
+            None
+        } else {
+            Some(check(
+                blocks.block_record(&block.prev_hash),
+                ValidationErrorCode::InvalidPrevBlockHash,
+            )?)
+        };
+    }
+    Ok(match reversed_hashes.get(slots_back) {
+        Some(hash) => hash.clone(),
+        None => constants.genesis_challenge.clone(),
+    })
+}
+
+fn expected_icc_sub_slot(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    prev_b: &BlockRecord,
+    index: usize,
+) -> Result<Option<(Bytes32, u64, u64, VdfOutput)>, ValidationError> {
+    //Challenge, committed iterations, proof iterations and proof input of the icc end of slot
+    if prev_b.deficit >= constants.min_blocks_per_challenge_block {
+        return Ok(None);
+    }
+    if index > 0 {
+        let prev_sub_slot = &header_block.finished_sub_slots[index - 1];
+        if prev_sub_slot.reward_chain.deficit >= constants.min_blocks_per_challenge_block {
+            return Ok(None);
+        }
+        let prev_icc = prev_sub_slot
+            .infused_challenge_chain
+            .as_ref()
+            .ok_or_else(|| {
+                invalid(
+                    ValidationErrorCode::InvalidIccEosVdf,
+                    "Previous sub slot is missing its infused challenge chain",
+                )
+            })?;
+        return Ok(Some((
+            prev_icc.hash(),
+            prev_b.sub_slot_iters,
+            prev_b.sub_slot_iters,
+            VdfOutput::default_element(),
+        )));
+    }
+    //The first sub slot continues the icc from the challenge block or the start of prev_b's sub slot
+    let mut curr = prev_b.clone();
+    while !curr.is_challenge_block(constants.min_blocks_per_challenge_block)
+        && !curr.first_in_sub_slot()
+    {
+        curr = check(
+            blocks.block_record(&curr.prev_hash),
+            ValidationErrorCode::InvalidPrevBlockHash,
+        )?;
+    }
+    let (icc_challenge, icc_iters_committed) =
+        if curr.is_challenge_block(constants.min_blocks_per_challenge_block) {
+            let curr_ip_iters = check(
+                curr.ip_iters(constants),
+                ValidationErrorCode::InvalidBlockData,
+            )?;
+            (
+                curr.challenge_block_info_hash.clone(),
+                prev_b.sub_slot_iters - curr_ip_iters,
+            )
+        } else {
+            let icc_challenge = curr
+                .finished_infused_challenge_slot_hashes
+                .as_ref()
+                .and_then(|hashes| hashes.last())
+                .ok_or_else(|| {
+                    invalid(
+                        ValidationErrorCode::InvalidIccEosVdf,
+                        "No infused challenge chain sub slot before block",
+                    )
+                })?;
+            (icc_challenge.clone(), prev_b.sub_slot_iters)
+        };
+    let prev_ip_iters = check(
+        prev_b.ip_iters(constants),
+        ValidationErrorCode::InvalidBlockData,
+    )?;
+    let icc_input = if prev_b.is_challenge_block(constants.min_blocks_per_challenge_block) {
+        VdfOutput::default_element()
+    } else {
+        prev_b.infused_challenge_vdf_output.clone().ok_or_else(|| {
+            invalid(
+                ValidationErrorCode::InvalidIccEosVdf,
+                "Previous block is missing its infused challenge chain output",
+            )
+        })?
+    };
+    Ok(Some((
+        icc_challenge,
+        icc_iters_committed,
+        prev_b.sub_slot_iters - prev_ip_iters,
+        icc_input,
+    )))
+}
+
+pub fn get_block_challenge(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    prev_b: Option<&BlockRecord>,
+    overflow: bool,
+) -> Result<Bytes32, ValidationError> {
+    //Overflow blocks take their challenge from the sub slot before the one they are infused in
+    match header_block.finished_sub_slots.last() {
+        Some(sub_slot) if overflow => Ok(sub_slot
+            .challenge_chain
+            .challenge_chain_end_of_slot_vdf
+            .challenge
+            .clone()),
+        Some(sub_slot) => challenge_chain_hash(&sub_slot.challenge_chain),
+        None => match prev_b {
+            Some(prev_b) => {
+                let count = if overflow { 2 } else { 1 };
+                Ok(prev_challenge_slot_hashes(blocks, prev_b, count)?[count - 1].clone())
+            }
+            None => Ok(constants.genesis_challenge.clone()),
+        },
+    }
+}
+
+//The first sub slot after the end of a sub epoch commits to its summary, and epochs also change the difficulty
+#[allow(clippy::too_many_arguments)]
+fn validate_epoch_boundary(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    challenge_chain: &ChallengeChainSubSlot,
+    prev_b: Option<&BlockRecord>,
+    expected_difficulty: u64,
+    expected_sub_slot_iters: u64,
+    check_sub_epoch_summary: bool,
+) -> Result<bool, ValidationError> {
+    let prev_b = match prev_b {
+        Some(prev_b) => prev_b,
+        None => {
+            if challenge_chain.subepoch_summary_hash.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidSubEpochSummaryHash,
+                    "Genesis block cannot include a sub epoch summary",
+                ));
+            }
+            if challenge_chain.new_difficulty.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidNewDifficulty,
+                    "Genesis block cannot change the difficulty",
+                ));
+            }
+            if challenge_chain.new_sub_slot_iters.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidNewSubSlotIters,
+                    "Genesis block cannot change the sub slot iters",
+                ));
+            }
+            return Ok(false);
+        }
+    };
+    let (can_finish_se, can_finish_epoch) = check(
+        can_finish_sub_and_full_epoch(
+            constants,
+            blocks,
+            prev_b.height,
+            &prev_b.prev_hash,
+            prev_b.deficit,
+            prev_b.sub_epoch_summary_included.is_some(),
+        ),
+        ValidationErrorCode::InvalidSubEpochSummary,
+    )?;
+
+    if can_finish_se && !check_sub_epoch_summary {
+        //Without the blocks to rebuild the summary only its presence can be checked
+        if challenge_chain.subepoch_summary_hash.is_none() {
+            return Err(invalid(
+                ValidationErrorCode::InvalidSubEpochSummaryHash,
+                "Missing sub epoch summary at a sub epoch boundary",
+            ));
+        }
+    } else if can_finish_se {
+        let prev_prev_b = check(
+            blocks.block_record(&prev_b.prev_hash),
+            ValidationErrorCode::InvalidSubEpochSummary,
+        )?;
+        let (new_difficulty, new_sub_slot_iters) = if can_finish_epoch {
+            (Some(expected_difficulty), Some(expected_sub_slot_iters))
+        } else {
+            (None, None)
+        };
+        let ses = check(
+            make_sub_epoch_summary(
+                constants,
+                blocks,
+                header_block.height(),
+                &prev_prev_b,
+                new_difficulty,
+                new_sub_slot_iters,
+            ),
+            ValidationErrorCode::InvalidSubEpochSummary,
+        )?;
+        if challenge_chain.subepoch_summary_hash != Some(ses.hash()) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidSubEpochSummary,
+                "Sub epoch summary hash does not match the expected summary",
+            ));
+        }
+    } else if challenge_chain.subepoch_summary_hash.is_some() {
+        return Err(invalid(
+            ValidationErrorCode::InvalidSubEpochSummaryHash,
+            "Sub epoch summary included outside of a sub epoch boundary",
+        ));
+    }
+
+    if can_finish_epoch {
+        if challenge_chain.new_difficulty != Some(expected_difficulty) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidNewDifficulty,
+                "New difficulty does not match the expected difficulty",
+            ));
+        }
+        if challenge_chain.new_sub_slot_iters != Some(expected_sub_slot_iters) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidNewSubSlotIters,
+                "New sub slot iters does not match the expected sub slot iters",
+            ));
+        }
+    } else {
+        if challenge_chain.new_difficulty.is_some() {
+            return Err(invalid(
+                ValidationErrorCode::InvalidNewDifficulty,
+                "Difficulty can only change at an epoch boundary",
+            ));
+        }
+        if challenge_chain.new_sub_slot_iters.is_some() {
+            return Err(invalid(
+                ValidationErrorCode::InvalidNewSubSlotIters,
+                "Sub slot iters can only change at an epoch boundary",
+            ));
+        }
+    }
+    Ok(can_finish_epoch)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn validate_finished_sub_slots(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    prev_b: Option<&BlockRecord>,
+    expected_difficulty: u64,
+    expected_sub_slot_iters: u64,
+    check_sub_epoch_summary: bool,
+    skip_vdf_is_valid: bool,
+) -> Result<bool, ValidationError> {
+    //Returns whether the first sub slot can finish an epoch
+    let mut can_finish_epoch = false;
+    if header_block.finished_sub_slots.is_empty() {
+        return Ok(can_finish_epoch);
+    }
+    let (mut prev_cc_hash, mut prev_rc_hash, mut eos_iters) = match prev_b {
+        Some(prev_b) => {
+            //The first sub slot ends the slot prev_b was infused in, so it uses the previous iters
+            let prev_ip_iters = check(
+                prev_b.ip_iters(constants),
+                ValidationErrorCode::InvalidBlockData,
+            )?;
+            (
+                prev_challenge_slot_hashes(blocks, prev_b, 1)?[0].clone(),
+                prev_b.reward_infusion_new_challenge.clone(),
+                (prev_b.sub_slot_iters, prev_b.sub_slot_iters - prev_ip_iters),
+            )
+        }
+        None => (
+            constants.genesis_challenge.clone(),
+            constants.genesis_challenge.clone(),
+            (expected_sub_slot_iters, expected_sub_slot_iters),
+        ),
+    };
+    for (index, sub_slot) in header_block.finished_sub_slots.iter().enumerate() {
+        let challenge_chain = &sub_slot.challenge_chain;
+        let cc_eos_vdf = &challenge_chain.challenge_chain_end_of_slot_vdf;
+        if cc_eos_vdf.challenge != prev_cc_hash {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPrevChallengeSlotHash,
+                "Challenge chain end of slot does not follow the previous sub slot",
+            ));
+        }
+        if cc_eos_vdf.number_of_iterations != eos_iters.0 {
+            return Err(invalid(
+                ValidationErrorCode::InvalidCcEosVdf,
+                "Invalid challenge chain end of slot iterations",
+            ));
+        }
+        let rc_eos_vdf = &sub_slot.reward_chain.end_of_slot_vdf;
+        if rc_eos_vdf.challenge != prev_rc_hash {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPrevRewardSlotHash,
+                "Reward chain end of slot does not follow the previous block",
+            ));
+        }
+        if rc_eos_vdf.number_of_iterations != eos_iters.1 {
+            return Err(invalid(
+                ValidationErrorCode::InvalidRcEosVdf,
+                "Invalid reward chain end of slot iterations",
+            ));
+        }
+        if !skip_vdf_is_valid {
+            //Only the first sub slot continues from prev_b, later ones start from the default element
+            let cc_input = match prev_b {
+                Some(prev_b) if index == 0 => prev_b.challenge_vdf_output.clone(),
+                _ => VdfOutput::default_element(),
+            };
+            let cc_target_vdf_info = VdfInfo {
+                challenge: cc_eos_vdf.challenge.clone(),
+                output: cc_eos_vdf.output.clone(),
+                number_of_iterations: eos_iters.1,
+            };
+            if !verify_vdf_proof(
+                constants,
+                &cc_input,
+                &cc_target_vdf_info,
+                cc_eos_vdf,
+                &sub_slot.proofs.challenge_chain_slot_proof,
+            ) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidCcEosVdf,
+                    "Invalid challenge chain end of slot proof",
+                ));
+            }
+            if !verify_vdf_proof(
+                constants,
+                &VdfOutput::default_element(),
+                rc_eos_vdf,
+                rc_eos_vdf,
+                &sub_slot.proofs.reward_chain_slot_proof,
+            ) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidRcEosVdf,
+                    "Invalid reward chain end of slot proof",
+                ));
+            }
+        }
+        let cc_hash = challenge_chain_hash(challenge_chain)?;
+        if sub_slot.reward_chain.challenge_chain_sub_slot_hash != cc_hash {
+            return Err(invalid(
+                ValidationErrorCode::InvalidChallengeSlotHashRc,
+                "Reward chain does not commit to the challenge chain sub slot",
+            ));
+        }
+
+        let expected_icc = match prev_b {
+            Some(prev_b) => expected_icc_sub_slot(constants, blocks, header_block, prev_b, index)?,
+            None => None,
+        };
+        match (&sub_slot.infused_challenge_chain, expected_icc) {
+            (
+                Some(infused_challenge_chain),
+                Some((icc_challenge, icc_iters_committed, icc_iters_proof, icc_input)),
+            ) => {
+                let icc_eos_vdf = &infused_challenge_chain.infused_challenge_chain_end_of_slot_vdf;
+                if icc_eos_vdf.challenge != icc_challenge
+                    || icc_eos_vdf.number_of_iterations != icc_iters_committed
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccEosVdf,
+                        "Invalid infused challenge chain end of slot VDF",
+                    ));
+                }
+                let icc_hash = infused_challenge_chain.hash();
+                if sub_slot
+                    .reward_chain
+                    .infused_challenge_chain_sub_slot_hash
+                    .as_ref()
+                    != Some(&icc_hash)
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccHashRc,
+                        "Reward chain does not commit to the infused challenge chain sub slot",
+                    ));
+                }
+                //The challenge chain only commits to the icc once a challenge block reset the deficit
+                let expected_cc_icc_hash =
+                    if sub_slot.reward_chain.deficit == constants.min_blocks_per_challenge_block {
+                        Some(&icc_hash)
+                    } else {
+                        None
+                    };
+                if challenge_chain
+                    .infused_challenge_chain_sub_slot_hash
+                    .as_ref()
+                    != expected_cc_icc_hash
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccHashCc,
+                        "Invalid infused challenge chain hash in challenge chain",
+                    ));
+                }
+                let icc_proof = sub_slot
+                    .proofs
+                    .infused_challenge_chain_slot_proof
+                    .as_ref()
+                    .ok_or_else(|| {
+                        invalid(
+                            ValidationErrorCode::InvalidIccEosVdf,
+                            "Missing infused challenge chain end of slot proof",
+                        )
+                    })?;
+                if !skip_vdf_is_valid {
+                    let icc_target_vdf_info = VdfInfo {
+                        challenge: icc_challenge,
+                        output: icc_eos_vdf.output.clone(),
+                        number_of_iterations: icc_iters_proof,
+                    };
+                    if !verify_vdf_proof(
+                        constants,
+                        &icc_input,
+                        &icc_target_vdf_info,
+                        icc_eos_vdf,
+                        icc_proof,
+                    ) {
+                        return Err(invalid(
+                            ValidationErrorCode::InvalidIccEosVdf,
+                            "Invalid infused challenge chain end of slot proof",
+                        ));
+                    }
+                }
+            }
+            (Some(_), None) => {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidIccEosVdf,
+                    "Sub slot should not include an infused challenge chain",
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidIccEosVdf,
+                    "Sub slot is missing its infused challenge chain",
+                ));
+            }
+            (None, None) => {
+                if sub_slot
+                    .reward_chain
+                    .infused_challenge_chain_sub_slot_hash
+                    .is_some()
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccHashRc,
+                        "Reward chain commits to a missing infused challenge chain",
+                    ));
+                }
+                if challenge_chain
+                    .infused_challenge_chain_sub_slot_hash
+                    .is_some()
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccHashCc,
+                        "Challenge chain commits to a missing infused challenge chain",
+                    ));
+                }
+                if sub_slot.proofs.infused_challenge_chain_slot_proof.is_some() {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidIccEosVdf,
+                        "Unexpected infused challenge chain end of slot proof",
+                    ));
+                }
+            }
+        }
+
+        //The deficit resets after a challenge block, otherwise it carries over the slot end
+        let expected_deficit = match prev_b {
+            Some(prev_b) if prev_b.deficit != 0 => prev_b.deficit,
+            _ => constants.min_blocks_per_challenge_block,
+        };
+        if sub_slot.reward_chain.deficit != expected_deficit {
+            return Err(invalid(
+                ValidationErrorCode::InvalidDeficit,
+                "Invalid reward chain deficit",
+            ));
+        }
+
+        if index > 0 {
+            if challenge_chain.new_difficulty.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidNewDifficulty,
+                    "Only the first sub slot can change the difficulty",
+                ));
+            }
+            if challenge_chain.new_sub_slot_iters.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidNewSubSlotIters,
+                    "Only the first sub slot can change the sub slot iters",
+                ));
+            }
+            if challenge_chain.subepoch_summary_hash.is_some() {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidSubEpochSummaryHash,
+                    "Only the first sub slot can include a sub epoch summary",
+                ));
+            }
+        }
+        if index == 0 {
+            can_finish_epoch = validate_epoch_boundary(
+                constants,
+                blocks,
+                header_block,
+                challenge_chain,
+                prev_b,
+                expected_difficulty,
+                expected_sub_slot_iters,
+                check_sub_epoch_summary,
+            )?;
+        }
+
+        prev_cc_hash = cc_hash;
+        prev_rc_hash = sub_slot.reward_chain.hash();
+        eos_iters = (expected_sub_slot_iters, expected_sub_slot_iters);
+    }
+    Ok(can_finish_epoch)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn validate_sub_slot_block_count(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    prev_b: &BlockRecord,
+) -> Result<(), ValidationError> {
+    //Counts the new block and every block since the start of prev_b's sub slot
+    let mut num_blocks: u32 = 2;
+    let mut curr = prev_b.clone();
+    while !curr.first_in_sub_slot() {
+        num_blocks += 1;
+        if num_blocks > constants.max_sub_slot_blocks {
+            return Err(invalid(
+                ValidationErrorCode::TooManyBlocks,
+                "Too many blocks in the sub slot",
+            ));
+        }
+        curr = check(
+            blocks.block_record(&curr.prev_hash),
+            ValidationErrorCode::InvalidPrevBlockHash,
+        )?;
+    }
+    Ok(())
+}
+
+fn reward_chain_ip_start(
+    constants: &Constants,
+    last_reward_slot_hash: Option<Bytes32>,
+    prev_b: Option<&BlockRecord>,
+    total_iters: u128,
+    ip_iters: u64,
+) -> Result<(Bytes32, u64), ValidationError> {
+    //Challenge and iterations of the rc and icc infusion VDFs, which restart with each sub slot
+    match (last_reward_slot_hash, prev_b) {
+        (Some(reward_slot_hash), _) => Ok((reward_slot_hash, ip_iters)),
+        (None, Some(prev_b)) => {
+            //Same sub slot as the previous block, so the VDF continues from its infusion
+            let ip_vdf_iters = total_iters.checked_sub(prev_b.total_iters).ok_or_else(|| {
+                invalid(
+                    ValidationErrorCode::InvalidTotalIters,
+                    "Block is infused before the previous block",
+                )
+            })?;
+            Ok((
+                prev_b.reward_infusion_new_challenge.clone(),
+                ip_vdf_iters as u64,
+            ))
+        }
+        (None, None) => Ok((constants.genesis_challenge.clone(), ip_iters)),
+    }
+}
+
+fn validate_pool_target(
+    constants: &Constants,
+    genesis: bool,
+    proof_of_space: &ProofOfSpace,
+    foliage_block_data: &FoliageBlockData,
+) -> Result<(), ValidationError> {
+    let pool_target = &foliage_block_data.pool_target;
+    //The genesis block pays the prefarm instead of a pool
+    if genesis {
+        if pool_target.puzzle_hash != constants.genesis_pre_farm_pool_puzzle_hash
+            || pool_target.max_height != 0
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPrefarm,
+                "Genesis pool target must be the prefarm puzzle hash",
+            ));
+        }
+        return Ok(());
+    }
+    match (
+        &proof_of_space.pool_public_key,
+        &foliage_block_data.pool_signature,
+    ) {
+        (Some(pool_public_key), Some(pool_signature)) => {
+            if !verify_signature(pool_public_key, &pool_target.to_bytes(), pool_signature) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidPoolSignature,
+                    "Invalid pool target signature",
+                ));
+            }
+        }
+        (Some(_), None) => {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPoolSignature,
+                "Missing pool target signature",
+            ));
+        }
+        (None, _) => {
+            if proof_of_space.pool_contract_puzzle_hash.as_ref() != Some(&pool_target.puzzle_hash) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidPoolTarget,
+                    "Pool target does not match the pool contract puzzle hash",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_transaction_block_expected(
+    blocks: &impl BlockchainInterface,
+    prev_b: Option<&BlockRecord>,
+    sp_total_iters: u128,
+) -> Result<bool, ValidationError> {
+    //The first block with a signage point after the last transaction block's infusion is one
+    let mut prev_transaction_block = match prev_b {
+        Some(prev_b) => prev_b.clone(),
+        None => return Ok(true),
+    };
+    while !prev_transaction_block.is_transaction_block() {
+        prev_transaction_block = check(
+            blocks.block_record(&prev_transaction_block.prev_hash),
+            ValidationErrorCode::InvalidPrevTransactionBlockHash,
+        )?;
+    }
+    Ok(sp_total_iters > prev_transaction_block.total_iters)
+}
+
+pub fn validate_header_block(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    header_block: &HeaderBlock,
+    check_filter: bool,
+    expected_difficulty: u64,
+    expected_sub_slot_iters: u64,
+    check_sub_epoch_summary: bool,
+    skip_vdf_is_valid: bool,
+) -> Result<u64, ValidationError> {
+    let height = header_block.height();
+    let reward_chain_block = &header_block.reward_chain_block;
+    let prev_b = if height == 0 {
+        if header_block.prev_header_hash() != constants.genesis_challenge {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPrevBlockHash,
+                "Genesis block must follow the genesis challenge",
+            ));
+        }
+        None
+    } else {
+        let prev_b = blocks
+            .try_block_record(&header_block.prev_header_hash())
+            .ok_or_else(|| {
+                invalid(
+                    ValidationErrorCode::InvalidPrevBlockHash,
+                    "Previous block not found",
+                )
+            })?;
+        if prev_b.height + 1 != height {
+            return Err(invalid(
+                ValidationErrorCode::InvalidHeight,
+                "Height does not follow the previous block",
+            ));
+        }
+        Some(prev_b)
+    };
+    if let (Some(prev_b), true) = (&prev_b, header_block.finished_sub_slots.is_empty()) {
+        validate_sub_slot_block_count(constants, blocks, prev_b)?;
+    }
+
+    let can_finish_epoch = validate_finished_sub_slots(
+        constants,
+        blocks,
+        header_block,
+        prev_b.as_ref(),
+        expected_difficulty,
+        expected_sub_slot_iters,
+        check_sub_epoch_summary,
+        skip_vdf_is_valid,
+    )?;
+
+    //Signage point
+    let signage_point_index = reward_chain_block.signage_point_index;
+    let overflow = check(
+        is_overflow_block(constants, signage_point_index),
+        ValidationErrorCode::InvalidSpIndex,
+    )?;
+    //Overflows are fine in the second sub slot of an epoch, just not the first
+    if overflow && can_finish_epoch && header_block.finished_sub_slots.len() < 2 {
+        return Err(invalid(
+            ValidationErrorCode::NoOverflowsInFirstSubSlotNewEpoch,
+            "Overflow blocks are not allowed in the first sub slot of an epoch",
+        ));
+    }
+    if signage_point_index == 0 {
+        if reward_chain_block.challenge_chain_sp_vdf.is_some()
+            || header_block.challenge_chain_sp_proof.is_some()
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidCcSpVdf,
+                "Signage point 0 has no challenge chain VDF",
+            ));
+        }
+        if reward_chain_block.reward_chain_sp_vdf.is_some()
+            || header_block.reward_chain_sp_proof.is_some()
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidRcSpVdf,
+                "Signage point 0 has no reward chain VDF",
+            ));
+        }
+    } else {
+        if reward_chain_block.challenge_chain_sp_vdf.is_none()
+            || header_block.challenge_chain_sp_proof.is_none()
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidCcSpVdf,
+                "Missing challenge chain signage point VDF",
+            ));
+        }
+        if reward_chain_block.reward_chain_sp_vdf.is_none()
+            || header_block.reward_chain_sp_proof.is_none()
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidRcSpVdf,
+                "Missing reward chain signage point VDF",
+            ));
+        }
+    }
+    let challenge =
+        get_block_challenge(constants, blocks, header_block, prev_b.as_ref(), overflow)?;
+    let cc_sp_hash = match &reward_chain_block.challenge_chain_sp_vdf {
+        Some(cc_sp_vdf) => cc_sp_vdf.output.hash(),
+        None => challenge.clone(),
+    };
+
+    //Proof of space and iterations
+    if reward_chain_block.pos_ss_cc_challenge_hash != challenge {
+        return Err(invalid(
+            ValidationErrorCode::InvalidPospaceChallenge,
+            "Proof of space challenge does not match the block challenge",
+        ));
+    }
+    let proof_of_space = &reward_chain_block.proof_of_space;
+    let quality_string = check(
+        proof_of_space.verify_and_get_quality_string(
+            &challenge,
+            &cc_sp_hash,
+            constants.min_plot_size,
+            constants.max_plot_size,
+        ),
+        ValidationErrorCode::InvalidPospace,
+    )?
+    .ok_or_else(|| {
+        invalid(
+            ValidationErrorCode::InvalidPospace,
+            "Invalid proof of space",
+        )
+    })?;
+    let required_iters = calculate_iterations_quality(
+        constants.difficulty_constant_factor,
+        &quality_string,
+        proof_of_space.size,
+        expected_difficulty,
+        &cc_sp_hash,
+    );
+    let sp_interval_iters = check(
+        calculate_sp_interval_iters(constants, expected_sub_slot_iters),
+        ValidationErrorCode::InvalidBlockData,
+    )?;
+    if required_iters >= sp_interval_iters {
+        return Err(invalid(
+            ValidationErrorCode::InvalidRequiredIters,
+            "Proof of space is not good enough for the signage point",
+        ));
+    }
+    let sp_iters = check(
+        calculate_sp_iters(constants, expected_sub_slot_iters, signage_point_index),
+        ValidationErrorCode::InvalidSpIndex,
+    )?;
+    let ip_iters = check(
+        calculate_ip_iters(
+            constants,
+            expected_sub_slot_iters,
+            signage_point_index,
+            required_iters,
+        ),
+        ValidationErrorCode::InvalidRequiredIters,
+    )?;
+    let num_finished_sub_slots = header_block.finished_sub_slots.len() as u128;
+    let expected_total_iters: u128 = match &prev_b {
+        Some(prev_b) => {
+            let prev_ip_iters = check(
+                prev_b.ip_iters(constants),
+                ValidationErrorCode::InvalidBlockData,
+            )? as u128;
+            let slot_start = prev_b.total_iters - prev_ip_iters;
+            if num_finished_sub_slots > 0 {
+                slot_start
+                    + prev_b.sub_slot_iters as u128
+                    + expected_sub_slot_iters as u128 * (num_finished_sub_slots - 1)
+            } else {
+                slot_start
+            }
+        }
+        None => expected_sub_slot_iters as u128 * num_finished_sub_slots,
+    } + ip_iters as u128;
+    if reward_chain_block.total_iters != expected_total_iters {
+        return Err(invalid(
+            ValidationErrorCode::InvalidTotalIters,
+            "Total iterations do not match the infusion point",
+        ));
+    }
+    let prev_weight = prev_b.as_ref().map_or(0, |prev_b| prev_b.weight);
+    if reward_chain_block.weight != prev_weight + expected_difficulty as u128 {
+        return Err(invalid(
+            ValidationErrorCode::InvalidWeight,
+            "Weight does not add the expected difficulty",
+        ));
+    }
+
+    //Signage point VDFs and signatures
+    if let Some(cc_sp_vdf) = &reward_chain_block.challenge_chain_sp_vdf {
+        if cc_sp_vdf.challenge != challenge || cc_sp_vdf.number_of_iterations != sp_iters {
+            return Err(invalid(
+                ValidationErrorCode::InvalidCcSpVdf,
+                "Invalid challenge chain signage point VDF",
+            ));
+        }
+    }
+    //Signage point VDFs continue from the last block infused before the signage point in its sub slot
+    let ip_slot_start = reward_chain_block.total_iters - ip_iters as u128;
+    let sp_slot_start = if overflow {
+        ip_slot_start - expected_sub_slot_iters as u128
+    } else {
+        ip_slot_start
+    };
+    let sp_total_iters = sp_slot_start + sp_iters as u128;
+    let sp_prev_block = last_block_before(blocks, prev_b.as_ref(), sp_slot_start, sp_total_iters)?;
+    if let Some(rc_sp_vdf) = &reward_chain_block.reward_chain_sp_vdf {
+        let (rc_sp_challenge, rc_sp_iters) = match &sp_prev_block {
+            Some(curr) => (
+                curr.reward_infusion_new_challenge.clone(),
+                (sp_total_iters - curr.total_iters) as u64,
+            ),
+            None => (
+                reward_slot_hash(
+                    constants,
+                    blocks,
+                    header_block,
+                    prev_b.as_ref(),
+                    overflow as usize,
+                )?,
+                sp_iters,
+            ),
+        };
+        if rc_sp_vdf.challenge != rc_sp_challenge || rc_sp_vdf.number_of_iterations != rc_sp_iters {
+            return Err(invalid(
+                ValidationErrorCode::InvalidRcSpVdf,
+                "Invalid reward chain signage point VDF",
+            ));
+        }
+    }
+    if !skip_vdf_is_valid {
+        if let (Some(cc_sp_vdf), Some(cc_sp_proof)) = (
+            &reward_chain_block.challenge_chain_sp_vdf,
+            &header_block.challenge_chain_sp_proof,
+        ) {
+            let (cc_input, cc_iters) = match &sp_prev_block {
+                Some(curr) => (
+                    curr.challenge_vdf_output.clone(),
+                    (sp_total_iters - curr.total_iters) as u64,
+                ),
+                None => (VdfOutput::default_element(), sp_iters),
+            };
+            let cc_target_vdf_info = VdfInfo {
+                challenge: cc_sp_vdf.challenge.clone(),
+                output: cc_sp_vdf.output.clone(),
+                number_of_iterations: cc_iters,
+            };
+            if !verify_vdf_proof(
+                constants,
+                &cc_input,
+                &cc_target_vdf_info,
+                cc_sp_vdf,
+                cc_sp_proof,
+            ) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidCcSpVdf,
+                    "Invalid challenge chain signage point proof",
+                ));
+            }
+        }
+        if let (Some(rc_sp_vdf), Some(rc_sp_proof)) = (
+            &reward_chain_block.reward_chain_sp_vdf,
+            &header_block.reward_chain_sp_proof,
+        ) {
+            if !verify_vdf_proof(
+                constants,
+                &VdfOutput::default_element(),
+                rc_sp_vdf,
+                rc_sp_vdf,
+                rc_sp_proof,
+            ) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidRcSpVdf,
+                    "Invalid reward chain signage point proof",
+                ));
+            }
+        }
+    }
+    let plot_public_key = &proof_of_space.plot_public_key;
+    if !verify_signature(
+        plot_public_key,
+        &cc_sp_hash.to_bytes(),
+        &reward_chain_block.challenge_chain_sp_signature,
+    ) {
+        return Err(invalid(
+            ValidationErrorCode::InvalidCcSignature,
+            "Invalid challenge chain signage point signature",
+        ));
+    }
+    let rc_sp_hash = match &reward_chain_block.reward_chain_sp_vdf {
+        Some(rc_sp_vdf) => rc_sp_vdf.output.hash(),
+        None => reward_slot_hash(constants, blocks, header_block, prev_b.as_ref(), 0)?,
+    };
+    if !verify_signature(
+        plot_public_key,
+        &rc_sp_hash.to_bytes(),
+        &reward_chain_block.reward_chain_sp_signature,
+    ) {
+        return Err(invalid(
+            ValidationErrorCode::InvalidRcSignature,
+            "Invalid reward chain signage point signature",
+        ));
+    }
+
+    //Infusion point VDFs
+    let ip_challenge = if overflow {
+        get_block_challenge(constants, blocks, header_block, prev_b.as_ref(), false)?
+    } else {
+        challenge.clone()
+    };
+    let (rc_ip_challenge, ip_vdf_iters) = reward_chain_ip_start(
+        constants,
+        header_block
+            .finished_sub_slots
+            .last()
+            .map(|sub_slot| sub_slot.reward_chain.hash()),
+        prev_b.as_ref(),
+        reward_chain_block.total_iters,
+        ip_iters,
+    )?;
+    let cc_ip_vdf = &reward_chain_block.challenge_chain_ip_vdf;
+    if cc_ip_vdf.challenge != ip_challenge || cc_ip_vdf.number_of_iterations != ip_iters {
+        return Err(invalid(
+            ValidationErrorCode::InvalidCcIpVdf,
+            "Invalid challenge chain infusion point VDF",
+        ));
+    }
+    let rc_ip_vdf = &reward_chain_block.reward_chain_ip_vdf;
+    if rc_ip_vdf.challenge != rc_ip_challenge || rc_ip_vdf.number_of_iterations != ip_vdf_iters {
+        return Err(invalid(
+            ValidationErrorCode::InvalidRcIpVdf,
+            "Invalid reward chain infusion point VDF",
+        ));
+    }
+    let deficit = check(
+        calculate_deficit(
+            constants,
+            height,
+            prev_b.as_ref(),
+            overflow,
+            header_block.finished_sub_slots.len(),
+        ),
+        ValidationErrorCode::InvalidDeficit,
+    )?;
+    //Only blocks after the challenge block of a sub slot are infused into the icc
+    let icc_expected = deficit < constants.min_blocks_per_challenge_block - 1;
+    if reward_chain_block.infused_challenge_chain_ip_vdf.is_some() != icc_expected
+        || header_block.infused_challenge_chain_ip_proof.is_some() != icc_expected
+    {
+        return Err(invalid(
+            ValidationErrorCode::InvalidIccVdf,
+            "Invalid infused challenge chain infusion point VDF",
+        ));
+    }
+    //A new sub slot restarts the icc from its icc end of slot, otherwise it continues from prev_b
+    let icc_ip_expected = match (&reward_chain_block.infused_challenge_chain_ip_vdf, &prev_b) {
+        (Some(_), Some(prev_b)) => Some(match header_block.finished_sub_slots.last() {
+            Some(sub_slot) => {
+                let icc = sub_slot.infused_challenge_chain.as_ref().ok_or_else(|| {
+                    invalid(
+                        ValidationErrorCode::InvalidIccVdf,
+                        "Last sub slot is missing its infused challenge chain",
+                    )
+                })?;
+                (icc.hash(), VdfOutput::default_element())
+            }
+            None => {
+                let min_blocks = constants.min_blocks_per_challenge_block;
+                let icc_input = if prev_b.is_challenge_block(min_blocks) {
+                    VdfOutput::default_element()
+                } else {
+                    prev_b.infused_challenge_vdf_output.clone().ok_or_else(|| {
+                        invalid(
+                            ValidationErrorCode::InvalidIccVdf,
+                            "Previous block is missing its infused challenge chain output",
+                        )
+                    })?
+                };
+                let mut curr = prev_b.clone();
+                while curr.finished_infused_challenge_slot_hashes.is_none()
+                    && !curr.is_challenge_block(min_blocks)
+                {
+                    curr = check(
+                        blocks.block_record(&curr.prev_hash),
+                        ValidationErrorCode::InvalidPrevBlockHash,
+                    )?;
+                }
+                let icc_challenge = if curr.is_challenge_block(min_blocks) {
+                    curr.challenge_block_info_hash.clone()
+                } else {
+                    curr.finished_infused_challenge_slot_hashes
+                        .as_ref()
+                        .and_then(|hashes| hashes.last())
+                        .cloned()
+                        .ok_or_else(|| {
+                            invalid(
+                                ValidationErrorCode::InvalidIccVdf,
+                                "No infused challenge chain sub slot before block",
+                            )
+                        })?
+                };
+                (icc_challenge, icc_input)
+            }
+        }),
+        (Some(_), None) => {
+            return Err(invalid(
+                ValidationErrorCode::InvalidIccVdf,
+                "Genesis block cannot have an infused challenge chain VDF",
+            ));
+        }
+        (None, _) => None,
+    };
+    if let (Some(icc_ip_vdf), Some((icc_challenge, _))) = (
+        &reward_chain_block.infused_challenge_chain_ip_vdf,
+        &icc_ip_expected,
+    ) {
+        if icc_ip_vdf.challenge != *icc_challenge || icc_ip_vdf.number_of_iterations != ip_vdf_iters
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidIccVdf,
+                "Invalid infused challenge chain infusion point VDF",
+            ));
+        }
+    }
+    if !skip_vdf_is_valid {
+        let same_slot_prev_b = match &prev_b {
+            Some(prev_b) if header_block.finished_sub_slots.is_empty() => Some(prev_b),
+            _ => None,
+        };
+        let cc_input = match same_slot_prev_b {
+            Some(prev_b) => prev_b.challenge_vdf_output.clone(),
+            None => VdfOutput::default_element(),
+        };
+        let cc_target_vdf_info = VdfInfo {
+            challenge: ip_challenge.clone(),
+            output: cc_ip_vdf.output.clone(),
+            number_of_iterations: ip_vdf_iters,
+        };
+        if !verify_vdf_proof(
+            constants,
+            &cc_input,
+            &cc_target_vdf_info,
+            cc_ip_vdf,
+            &header_block.challenge_chain_ip_proof,
+        ) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidCcIpVdf,
+                "Invalid challenge chain infusion point proof",
+            ));
+        }
+        if !verify_vdf_proof(
+            constants,
+            &VdfOutput::default_element(),
+            rc_ip_vdf,
+            rc_ip_vdf,
+            &header_block.reward_chain_ip_proof,
+        ) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidRcIpVdf,
+                "Invalid reward chain infusion point proof",
+            ));
+        }
+        if let (Some(icc_ip_vdf), Some(icc_ip_proof), Some((icc_challenge, icc_input))) = (
+            &reward_chain_block.infused_challenge_chain_ip_vdf,
+            &header_block.infused_challenge_chain_ip_proof,
+            &icc_ip_expected,
+        ) {
+            let icc_target_vdf_info = VdfInfo {
+                challenge: icc_challenge.clone(),
+                output: icc_ip_vdf.output.clone(),
+                number_of_iterations: ip_vdf_iters,
+            };
+            if !verify_vdf_proof(
+                constants,
+                icc_input,
+                &icc_target_vdf_info,
+                icc_ip_vdf,
+                icc_ip_proof,
+            ) {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidIccVdf,
+                    "Invalid infused challenge chain infusion point proof",
+                ));
+            }
+        }
+    }
+
+    //Foliage
+    let foliage = &header_block.foliage;
+    if foliage.reward_block_hash != reward_chain_block.hash() {
+        return Err(invalid(
+            ValidationErrorCode::InvalidRewardBlockHash,
+            "Foliage does not commit to the reward chain block",
+        ));
+    }
+    let foliage_block_data = &foliage.foliage_block_data;
+    if foliage_block_data.unfinished_reward_block_hash != reward_chain_block.get_unfinished().hash()
+    {
+        return Err(invalid(
+            ValidationErrorCode::InvalidUrsbHash,
+            "Foliage does not commit to the unfinished reward chain block",
+        ));
+    }
+    if !verify_signature(
+        plot_public_key,
+        &foliage_block_data.hash().to_bytes(),
+        &foliage.foliage_block_data_signature,
+    ) {
+        return Err(invalid(
+            ValidationErrorCode::InvalidPlotSignature,
+            "Invalid foliage block data signature",
+        ));
+    }
+    validate_pool_target(
+        constants,
+        prev_b.is_none(),
+        proof_of_space,
+        foliage_block_data,
+    )?;
+
+    //Transaction block
+    let is_transaction_block = reward_chain_block.is_transaction_block;
+    let expected_transaction_block =
+        is_transaction_block_expected(blocks, prev_b.as_ref(), sp_total_iters)?;
+    if is_transaction_block != expected_transaction_block {
+        return Err(invalid(
+            ValidationErrorCode::InvalidIsTransactionBlock,
+            "Block is not eligible to be a transaction block",
+        ));
+    }
+    if foliage.foliage_transaction_block_hash.is_some() != is_transaction_block
+        || foliage.foliage_transaction_block_signature.is_some() != is_transaction_block
+        || header_block.foliage_transaction_block.is_some() != is_transaction_block
+        || header_block.transactions_info.is_some() != is_transaction_block
+    {
+        return Err(invalid(
+            ValidationErrorCode::InvalidIsTransactionBlock,
+            "Transaction block fields do not match is_transaction_block",
+        ));
+    }
+    if let (
+        Some(foliage_transaction_block),
+        Some(foliage_transaction_block_hash),
+        Some(foliage_transaction_block_signature),
+        Some(transactions_info),
+    ) = (
+        &header_block.foliage_transaction_block,
+        &foliage.foliage_transaction_block_hash,
+        &foliage.foliage_transaction_block_signature,
+        &header_block.transactions_info,
+    ) {
+        if foliage_transaction_block.hash() != *foliage_transaction_block_hash {
+            return Err(invalid(
+                ValidationErrorCode::InvalidFoliageBlockHash,
+                "Foliage does not commit to the foliage transaction block",
+            ));
+        }
+        if !verify_signature(
+            plot_public_key,
+            &foliage_transaction_block_hash.to_bytes(),
+            foliage_transaction_block_signature,
+        ) {
+            return Err(invalid(
+                ValidationErrorCode::InvalidPlotSignature,
+                "Invalid foliage transaction block signature",
+            ));
+        }
+        match &prev_b {
+            Some(prev_b) => {
+                let mut prev_transaction_block = prev_b.clone();
+                while !prev_transaction_block.is_transaction_block() {
+                    prev_transaction_block = check(
+                        blocks.block_record(&prev_transaction_block.prev_hash),
+                        ValidationErrorCode::InvalidPrevTransactionBlockHash,
+                    )?;
+                }
+                if foliage_transaction_block.prev_transaction_block_hash
+                    != prev_transaction_block.header_hash
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidPrevTransactionBlockHash,
+                        "Invalid previous transaction block hash",
+                    ));
+                }
+                if Some(foliage_transaction_block.timestamp) <= prev_transaction_block.timestamp {
+                    return Err(invalid(
+                        ValidationErrorCode::TimestampTooFarInPast,
+                        "Timestamp is not after the previous transaction block",
+                    ));
+                }
+            }
+            None => {
+                if foliage_transaction_block.prev_transaction_block_hash
+                    != constants.genesis_challenge
+                {
+                    return Err(invalid(
+                        ValidationErrorCode::InvalidPrevTransactionBlockHash,
+                        "Genesis transaction block must follow the genesis challenge",
+                    ));
+                }
+            }
+        }
+        let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        };
+        if foliage_transaction_block.timestamp > now + constants.max_future_time {
+            return Err(invalid(
+                ValidationErrorCode::TimestampTooFarInFuture,
+                "Timestamp is too far in the future",
+            ));
+        }
+        if check(
+            transactions_info.hash(),
+            ValidationErrorCode::InvalidTransactionsInfoHash,
+        )? != foliage_transaction_block.transactions_info_hash
+        {
+            return Err(invalid(
+                ValidationErrorCode::InvalidTransactionsInfoHash,
+                "Foliage does not commit to the transactions info",
+            ));
+        }
+        if check_filter {
+            let filter = hex_to_bytes(&header_block.transactions_filter).map_err(|e| {
+                ValidationError::new(
+                    ValidationErrorCode::InvalidTransactionsFilterHash,
+                    e.to_string(),
+                )
+            })?;
+            if Bytes32::new(hash_256(filter)) != foliage_transaction_block.filter_hash {
+                return Err(invalid(
+                    ValidationErrorCode::InvalidTransactionsFilterHash,
+                    "Transactions filter does not match the filter hash",
+                ));
+            }
+        }
+    }
+    Ok(required_iters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::block_cache::BlockCache;
+    use crate::blockchain::constants::MAINNET;
+    use crate::blockchain::pool_target::PoolTarget;
+    use crate::blockchain::sized_bytes::{Bytes48, Bytes96, UnsizedBytes};
+
+    fn hash(height: u32) -> Bytes32 {
+        Bytes32::new(hash_256(height.to_be_bytes().to_vec()))
+    }
+
+    fn record(
+        height: u32,
+        total_iters: u128,
+        first_in_sub_slot: bool,
+        transaction: bool,
+    ) -> BlockRecord {
+        let mut record = BlockRecord::for_test(height);
+        record.total_iters = total_iters;
+        if first_in_sub_slot {
+            record.finished_challenge_slot_hashes = Some(vec![hash(height + 3000)]);
+        }
+        if transaction {
+            record.timestamp = Some(height as u64);
+        }
+        record
+    }
+
+    fn proof_of_space(
+        pool_public_key: Option<Bytes48>,
+        pool_contract_puzzle_hash: Option<Bytes32>,
+    ) -> ProofOfSpace {
+        ProofOfSpace {
+            challenge: hash(0),
+            pool_contract_puzzle_hash,
+            plot_public_key: Bytes48::new(vec![0u8; 48]),
+            pool_public_key,
+            proof: UnsizedBytes::new(Vec::new()),
+            size: 32,
+        }
+    }
+
+    fn foliage_block_data(
+        puzzle_hash: Bytes32,
+        pool_signature: Option<Bytes96>,
+    ) -> FoliageBlockData {
+        FoliageBlockData {
+            extension_data: hash(0),
+            farmer_reward_puzzle_hash: hash(0),
+            unfinished_reward_block_hash: hash(0),
+            pool_signature,
+            pool_target: PoolTarget {
+                max_height: 0,
+                puzzle_hash,
+            },
+        }
+    }
+
+    #[test]
+    fn test_too_many_blocks_in_sub_slot() {
+        let mut blocks = BlockCache::new();
+        for height in 0..128 {
+            blocks.add_block_record(record(height, height as u128, height == 0, false));
+        }
+        //127 blocks in the sub slot plus the new one is the limit
+        let prev_b = blocks.height_to_block_record(126).unwrap();
+        assert!(validate_sub_slot_block_count(&MAINNET, &blocks, &prev_b).is_ok());
+        let prev_b = blocks.height_to_block_record(127).unwrap();
+        assert_eq!(
+            validate_sub_slot_block_count(&MAINNET, &blocks, &prev_b)
+                .unwrap_err()
+                .error_code,
+            ValidationErrorCode::TooManyBlocks
+        );
+    }
+
+    #[test]
+    fn test_reward_chain_ip_start() {
+        let prev_b = record(10, 1000, false, true);
+        assert_eq!(
+            reward_chain_ip_start(&MAINNET, None, Some(&prev_b), 1500, 700).unwrap(),
+            (prev_b.reward_infusion_new_challenge.clone(), 500)
+        );
+        assert_eq!(
+            reward_chain_ip_start(&MAINNET, Some(hash(5)), Some(&prev_b), 1500, 700).unwrap(),
+            (hash(5), 700)
+        );
+        assert_eq!(
+            reward_chain_ip_start(&MAINNET, None, None, 700, 700).unwrap(),
+            (MAINNET.genesis_challenge.clone(), 700)
+        );
+        assert_eq!(
+            reward_chain_ip_start(&MAINNET, None, Some(&prev_b), 900, 700)
+                .unwrap_err()
+                .error_code,
+            ValidationErrorCode::InvalidTotalIters
+        );
+    }
+
+    #[test]
+    fn test_transaction_block_eligibility() {
+        let mut blocks = BlockCache::new();
+        blocks.add_block_record(record(0, 100, true, true));
+        blocks.add_block_record(record(1, 200, false, false));
+        let prev_b = blocks.height_to_block_record(1).unwrap();
+        assert!(is_transaction_block_expected(&blocks, Some(&prev_b), 150).unwrap());
+        assert!(!is_transaction_block_expected(&blocks, Some(&prev_b), 100).unwrap());
+        assert!(is_transaction_block_expected(&blocks, None, 0).unwrap());
+    }
+
+    #[test]
+    fn test_pool_target() {
+        let prefarm = MAINNET.genesis_pre_farm_pool_puzzle_hash.clone();
+        let pool_public_key = Some(Bytes48::new(vec![0u8; 48]));
+        let pospace = proof_of_space(pool_public_key.clone(), None);
+        //Genesis skips the pool signature and checks the prefarm instead
+        assert!(validate_pool_target(
+            &MAINNET,
+            true,
+            &pospace,
+            &foliage_block_data(prefarm.clone(), None)
+        )
+        .is_ok());
+        assert_eq!(
+            validate_pool_target(&MAINNET, true, &pospace, &foliage_block_data(hash(1), None))
+                .unwrap_err()
+                .error_code,
+            ValidationErrorCode::InvalidPrefarm
+        );
+        assert_eq!(
+            validate_pool_target(
+                &MAINNET,
+                false,
+                &pospace,
+                &foliage_block_data(prefarm, None)
+            )
+            .unwrap_err()
+            .error_code,
+            ValidationErrorCode::InvalidPoolSignature
+        );
+        let contract = proof_of_space(None, Some(hash(2)));
+        assert!(validate_pool_target(
+            &MAINNET,
+            false,
+            &contract,
+            &foliage_block_data(hash(2), None)
+        )
+        .is_ok());
+        assert_eq!(
+            validate_pool_target(
+                &MAINNET,
+                false,
+                &contract,
+                &foliage_block_data(hash(3), None)
+            )
+            .unwrap_err()
+            .error_code,
+            ValidationErrorCode::InvalidPoolTarget
+        );
+    }
+}
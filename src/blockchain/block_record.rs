@@ -1,10 +1,13 @@
 use crate::blockchain::coin::Coin;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::pot_iterations::{calculate_ip_iters, calculate_sp_iters};
 use crate::blockchain::sized_bytes::Bytes32;
 use crate::blockchain::sub_epoch_summary::SubEpochSummary;
 use crate::blockchain::vdf_output::VdfOutput;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct BlockRecord {
     pub header_hash: Bytes32,
     pub prev_hash: Bytes32,
@@ -32,3 +35,75 @@ pub struct BlockRecord {
     pub finished_reward_slot_hashes: Option<Vec<Bytes32>>,
     pub sub_epoch_summary_included: Option<SubEpochSummary>,
 }
+impl BlockRecord {
+    pub fn is_transaction_block(&self) -> bool {
+        self.timestamp.is_some()
+    }
+    pub fn first_in_sub_slot(&self) -> bool {
+        self.finished_challenge_slot_hashes.is_some()
+    }
+    pub fn is_challenge_block(&self, min_blocks_per_challenge_block: u8) -> bool {
+        self.deficit == min_blocks_per_challenge_block - 1
+    }
+    pub fn sp_iters(&self, constants: &Constants) -> Result<u64, Box<dyn Error>> {
+        calculate_sp_iters(constants, self.sub_slot_iters, self.signage_point_index)
+    }
+    pub fn ip_iters(&self, constants: &Constants) -> Result<u64, Box<dyn Error>> {
+        calculate_ip_iters(
+            constants,
+            self.sub_slot_iters,
+            self.signage_point_index,
+            self.required_iters,
+        )
+    }
+    pub fn sp_sub_slot_total_iters(&self, constants: &Constants) -> Result<u128, Box<dyn Error>> {
+        let ip_sub_slot_total_iters = self.ip_sub_slot_total_iters(constants)?;
+        if self.overflow {
+            Ok(ip_sub_slot_total_iters - self.sub_slot_iters as u128)
+        } else {
+            Ok(ip_sub_slot_total_iters)
+        }
+    }
+    pub fn ip_sub_slot_total_iters(&self, constants: &Constants) -> Result<u128, Box<dyn Error>> {
+        Ok(self.total_iters - self.ip_iters(constants)? as u128)
+    }
+    pub fn sp_total_iters(&self, constants: &Constants) -> Result<u128, Box<dyn Error>> {
+        Ok(self.sp_sub_slot_total_iters(constants)? + self.sp_iters(constants)? as u128)
+    }
+}
+
+#[cfg(test)]
+impl BlockRecord {
+    //Minimal record chained to the one at height - 1, tests adjust the fields they care about
+    pub(crate) fn for_test(height: u32) -> Self {
+        let hash =
+            |height: u32| Bytes32::new(crate::clvm::utils::hash_256(height.to_be_bytes().to_vec()));
+        BlockRecord {
+            header_hash: hash(height),
+            prev_hash: hash(height.wrapping_sub(1)),
+            height,
+            weight: height as u128,
+            total_iters: height as u128,
+            signage_point_index: 0,
+            challenge_vdf_output: VdfOutput::default_element(),
+            infused_challenge_vdf_output: None,
+            reward_infusion_new_challenge: hash(height + 1000),
+            challenge_block_info_hash: hash(height + 2000),
+            sub_slot_iters: 1 << 27,
+            pool_puzzle_hash: Bytes32::new(vec![0u8; 32]),
+            farmer_puzzle_hash: Bytes32::new(vec![0u8; 32]),
+            required_iters: 0,
+            deficit: 0,
+            overflow: false,
+            prev_transaction_block_height: 0,
+            timestamp: None,
+            prev_transaction_block_hash: None,
+            fees: None,
+            reward_claims_incorporated: None,
+            finished_challenge_slot_hashes: None,
+            finished_infused_challenge_slot_hashes: None,
+            finished_reward_slot_hashes: None,
+            sub_epoch_summary_included: None,
+        }
+    }
+}
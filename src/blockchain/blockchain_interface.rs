@@ -0,0 +1,30 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use std::error::Error;
+
+pub trait BlockchainInterface {
+    fn try_block_record(&self, header_hash: &Bytes32) -> Option<BlockRecord>;
+    fn height_to_hash(&self, height: u32) -> Option<Bytes32>;
+    fn get_ses_heights(&self) -> Vec<u32>;
+    fn get_ses(&self, height: u32) -> Option<SubEpochSummary>;
+
+    fn block_record(&self, header_hash: &Bytes32) -> Result<BlockRecord, Box<dyn Error>> {
+        match self.try_block_record(header_hash) {
+            Some(record) => Ok(record),
+            None => Err(format!("Block record not found: {}", header_hash).into()),
+        }
+    }
+    fn height_to_block_record(&self, height: u32) -> Result<BlockRecord, Box<dyn Error>> {
+        match self.height_to_hash(height) {
+            Some(header_hash) => self.block_record(&header_hash),
+            None => Err(format!("No block at height {}", height).into()),
+        }
+    }
+    fn contains_block(&self, header_hash: &Bytes32) -> bool {
+        self.try_block_record(header_hash).is_some()
+    }
+    fn contains_height(&self, height: u32) -> bool {
+        self.height_to_hash(height).is_some()
+    }
+}
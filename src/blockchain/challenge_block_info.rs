@@ -1,6 +1,7 @@
 use crate::blockchain::proof_of_space::ProofOfSpace;
-use crate::blockchain::sized_bytes::Bytes96;
+use crate::blockchain::sized_bytes::{Bytes32, Bytes96, SizedBytes};
 use crate::blockchain::vdf_info::VdfInfo;
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -10,3 +11,24 @@ pub struct ChallengeBlockInfo {
     pub challenge_chain_sp_signature: Bytes96,
     pub challenge_chain_ip_vdf: VdfInfo,
 }
+impl ChallengeBlockInfo {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(self.proof_of_space.to_bytes());
+        match &self.challenge_chain_sp_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.challenge_chain_sp_signature.to_bytes());
+        bytes.extend(self.challenge_chain_ip_vdf.to_bytes());
+        bytes
+    }
+}
@@ -0,0 +1,577 @@
+use crate::clvm::utils::hash_256;
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed, Zero};
+
+//Compressed form layout used by chiavdf (bqfc)
+pub const BQFC_FORM_SIZE: usize = 100;
+const BQFC_B_SIGN: u8 = 1 << 0;
+const BQFC_T_SIGN: u8 = 1 << 1;
+const BQFC_IS_1: u8 = 1 << 2;
+const BQFC_IS_GEN: u8 = 1 << 3;
+pub const B_BYTES: usize = 33;
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+fn floor_div(a: &BigInt, b: &BigInt) -> BigInt {
+    let quotient = a / b;
+    if (a % b).is_zero() || a.sign() == b.sign() {
+        quotient
+    } else {
+        quotient - 1
+    }
+}
+
+fn floor_mod(a: &BigInt, b: &BigInt) -> BigInt {
+    let remainder = a % b;
+    if !remainder.is_zero() && remainder.sign() != b.sign() {
+        remainder + b
+    } else {
+        remainder
+    }
+}
+
+//Returns (g, x, y) with a*x + b*y = g = gcd(a, b) and g >= 0
+fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_s, mut s) = (BigInt::one(), BigInt::zero());
+    let (mut old_t, mut t) = (BigInt::zero(), BigInt::one());
+    while !r.is_zero() {
+        let quotient = &old_r / &r;
+        let next_r = &old_r - &quotient * &r;
+        old_r = std::mem::replace(&mut r, next_r);
+        let next_s = &old_s - &quotient * &s;
+        old_s = std::mem::replace(&mut s, next_s);
+        let next_t = &old_t - &quotient * &t;
+        old_t = std::mem::replace(&mut t, next_t);
+    }
+    if old_r.is_negative() {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+fn mod_inverse(a: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+    let (g, x, _) = extended_gcd(&floor_mod(a, modulus), modulus);
+    if g.is_one() {
+        Some(floor_mod(&x, modulus))
+    } else {
+        None
+    }
+}
+
+pub fn is_probable_prime(n: &BigInt) -> bool {
+    if *n < BigInt::from(2) {
+        return false;
+    }
+    for prime in SMALL_PRIMES {
+        let prime = BigInt::from(prime);
+        if *n == prime {
+            return true;
+        }
+        if (n % &prime).is_zero() {
+            return false;
+        }
+    }
+    //Miller-Rabin with the small primes as witnesses
+    let n_minus_one: BigInt = n - 1;
+    let shift = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d: BigInt = &n_minus_one >> shift;
+    'witness: for prime in SMALL_PRIMES {
+        let mut x = BigInt::from(prime).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..shift {
+            x = x.modpow(&BigInt::from(2), n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+pub fn hash_prime(seed: &[u8], length: usize, bitmask: &[u64]) -> BigInt {
+    let mut sprout = seed.to_vec();
+    loop {
+        let mut blob: Vec<u8> = Vec::new();
+        while blob.len() * 8 < length {
+            //Same as chiavdf's HashPrime, which keeps incrementing bytes until one wraps to zero
+            for byte in sprout.iter_mut().rev() {
+                *byte = byte.wrapping_add(1);
+                if *byte == 0 {
+                    break;
+                }
+            }
+            let hash = hash_256(sprout.clone());
+            let take = hash.len().min(length / 8 - blob.len());
+            blob.extend(&hash[..take]);
+        }
+        let mut p = BigInt::from_bytes_be(Sign::Plus, &blob);
+        for bit in bitmask {
+            p.set_bit(*bit, true);
+        }
+        if is_probable_prime(&p) {
+            return p;
+        }
+    }
+}
+
+pub fn create_discriminant(seed: &[u8], length: usize) -> BigInt {
+    -hash_prime(seed, length, &[0, 1, 2, length as u64 - 1])
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassgroupForm {
+    pub a: BigInt,
+    pub b: BigInt,
+    pub c: BigInt,
+}
+impl ClassgroupForm {
+    pub fn from_abd(a: BigInt, b: BigInt, discriminant: &BigInt) -> Option<Self> {
+        if !a.is_positive() {
+            return None;
+        }
+        let numerator: BigInt = &b * &b - discriminant;
+        let denominator: BigInt = &a * 4;
+        if !(&numerator % &denominator).is_zero() {
+            return None;
+        }
+        let c = numerator / denominator;
+        Some(ClassgroupForm { a, b, c }.reduced())
+    }
+
+    pub fn identity(discriminant: &BigInt) -> Option<Self> {
+        ClassgroupForm::from_abd(BigInt::one(), BigInt::one(), discriminant)
+    }
+
+    pub fn generator(discriminant: &BigInt) -> Option<Self> {
+        ClassgroupForm::from_abd(BigInt::from(2), BigInt::one(), discriminant)
+    }
+
+    pub fn discriminant(&self) -> BigInt {
+        &self.b * &self.b - BigInt::from(4) * &self.a * &self.c
+    }
+
+    fn normalized(self) -> Self {
+        if -&self.a < self.b && self.b <= self.a {
+            return self;
+        }
+        let two_a: BigInt = &self.a * 2;
+        let r = floor_div(&(&self.a - &self.b), &two_a);
+        let b = &self.b + &r * &two_a;
+        let c = &self.a * &r * &r + &self.b * &r + &self.c;
+        ClassgroupForm { a: self.a, b, c }
+    }
+
+    pub fn reduced(self) -> Self {
+        let mut form = self.normalized();
+        while form.a > form.c || (form.a == form.c && form.b.is_negative()) {
+            form = ClassgroupForm {
+                a: form.c,
+                b: -form.b,
+                c: form.a,
+            }
+            .normalized();
+        }
+        form
+    }
+
+    pub fn compose(&self, other: &ClassgroupForm) -> Self {
+        //Cohen, A Course in Computational Algebraic Number Theory, Algorithm 5.4.7
+        let (f1, f2) = if self.a > other.a {
+            (other, self)
+        } else {
+            (self, other)
+        };
+        let s: BigInt = (&f1.b + &f2.b) / 2;
+        let n: BigInt = &f2.b - &s;
+        let (d, y1) = if (&f2.a % &f1.a).is_zero() {
+            (f1.a.clone(), BigInt::zero())
+        } else {
+            let (d, u, _) = extended_gcd(&f2.a, &f1.a);
+            (d, u)
+        };
+        let (d1, x2, y2) = if (&s % &d).is_zero() {
+            (d.clone(), BigInt::zero(), -BigInt::one())
+        } else {
+            let (d1, x2, y2) = extended_gcd(&s, &d);
+            (d1, x2, -y2)
+        };
+        let v1: BigInt = &f1.a / &d1;
+        let v2: BigInt = &f2.a / &d1;
+        let r = floor_mod(&(&y1 * &y2 * &n - &x2 * &f2.c), &v1);
+        let b3: BigInt = &f2.b + &v2 * &r * 2;
+        let a3: BigInt = &v1 * &v2;
+        let c3: BigInt = (&f2.c * &d1 + &r * (&f2.b + &v2 * &r)) / &v1;
+        ClassgroupForm {
+            a: a3,
+            b: b3,
+            c: c3,
+        }
+        .reduced()
+    }
+
+    pub fn square(&self) -> Self {
+        self.compose(self)
+    }
+
+    pub fn pow(&self, exponent: &BigInt) -> Self {
+        let mut result = ClassgroupForm {
+            a: BigInt::one(),
+            b: BigInt::one(),
+            c: (BigInt::one() - self.discriminant()) / 4,
+        };
+        for bit in (0..exponent.bits()).rev() {
+            result = result.square();
+            if exponent.bit(bit) {
+                result = result.compose(self);
+            }
+        }
+        result
+    }
+
+    fn compress(&self) -> (BigInt, BigInt, BigInt, BigInt) {
+        //Partial extended gcd of (a, |b|) until the remainder drops to sqrt(a)
+        let bound = self.a.sqrt();
+        let (mut r0, mut r1) = (self.a.clone(), self.b.abs());
+        let (mut t0, mut t1) = (BigInt::zero(), BigInt::one());
+        while r1 > bound {
+            let quotient = &r0 / &r1;
+            let next_r = &r0 - &quotient * &r1;
+            r0 = std::mem::replace(&mut r1, next_r);
+            let next_t = &t0 - &quotient * &t1;
+            t0 = std::mem::replace(&mut t1, next_t);
+        }
+        let (g, _, _) = extended_gcd(&self.a, &t1);
+        let a = &self.a / &g;
+        let t = &t1 / &g;
+        let b0 = self.b.abs() / &a;
+        (a, t, g, b0)
+    }
+
+    pub fn serialize(&self, discriminant_bits: usize) -> Option<Vec<u8>> {
+        let d_bits = (discriminant_bits + 31) & !31;
+        let mut bytes: Vec<u8> = vec![0u8; BQFC_FORM_SIZE];
+        if self.b.is_one() && self.a <= BigInt::from(2) {
+            bytes[0] = if self.a.is_one() {
+                BQFC_IS_1
+            } else {
+                BQFC_IS_GEN
+            };
+            return Some(bytes);
+        }
+        let (a, t, g, b0) = self.compress();
+        let g_size = ((g.bits() as usize + 7) / 8).max(1) - 1;
+        if g_size >= d_bits / 32 {
+            return None;
+        }
+        if self.b.is_negative() {
+            bytes[0] |= BQFC_B_SIGN;
+        }
+        if t.is_negative() {
+            bytes[0] |= BQFC_T_SIGN;
+        }
+        bytes[1] = g_size as u8;
+        let mut offset = 2;
+        for (value, size) in [
+            (&a, d_bits / 16 - g_size),
+            (&t, d_bits / 32 - g_size),
+            (&g, g_size + 1),
+            (&b0, g_size + 1),
+        ] {
+            let (_, value_bytes) = value.to_bytes_le();
+            if value_bytes.len() > size {
+                return None;
+            }
+            bytes[offset..offset + value_bytes.len()].copy_from_slice(&value_bytes);
+            offset += size;
+        }
+        Some(bytes)
+    }
+
+    pub fn deserialize(discriminant: &BigInt, bytes: &[u8]) -> Option<Self> {
+        //Like bqfc_verify_canon, only the encoding serialize would produce is accepted
+        let form = ClassgroupForm::deserialize_unchecked(discriminant, bytes)?;
+        if form.serialize(discriminant.bits() as usize)? != bytes {
+            return None;
+        }
+        Some(form)
+    }
+
+    fn deserialize_unchecked(discriminant: &BigInt, bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != BQFC_FORM_SIZE {
+            return None;
+        }
+        if bytes[0] & (BQFC_IS_1 | BQFC_IS_GEN) != 0 {
+            let a = if bytes[0] & BQFC_IS_GEN != 0 { 2 } else { 1 };
+            return ClassgroupForm::from_abd(BigInt::from(a), BigInt::one(), discriminant);
+        }
+        let d_bits = (discriminant.bits() as usize + 31) & !31;
+        let g_size = bytes[1] as usize;
+        if g_size >= d_bits / 32 {
+            return None;
+        }
+        let mut offset = 2;
+        let mut values: Vec<BigInt> = Vec::new();
+        for size in [
+            d_bits / 16 - g_size,
+            d_bits / 32 - g_size,
+            g_size + 1,
+            g_size + 1,
+        ] {
+            values.push(BigInt::from_bytes_le(
+                Sign::Plus,
+                &bytes[offset..offset + size],
+            ));
+            offset += size;
+        }
+        let (a_reduced, mut t, g, b0) = (&values[0], values[1].clone(), &values[2], &values[3]);
+        if bytes[0] & BQFC_T_SIGN != 0 {
+            t = -t;
+        }
+        if g.is_zero() || a_reduced.is_zero() {
+            return None;
+        }
+        if t.is_zero() {
+            return ClassgroupForm::from_abd(a_reduced.clone(), a_reduced.clone(), discriminant);
+        }
+        let a: BigInt = a_reduced * g;
+        let t_full: BigInt = &t * g;
+        let square = floor_mod(&(&t_full * &t_full * discriminant), &a);
+        let root = square.sqrt();
+        if &root * &root != square || !(&root % g).is_zero() {
+            return None;
+        }
+        let t_inverse = mod_inverse(&t, a_reduced)?;
+        let mut b = floor_mod(&(root / g * t_inverse), a_reduced) + a_reduced * b0;
+        if bytes[0] & BQFC_B_SIGN != 0 {
+            b = -b;
+        }
+        ClassgroupForm::from_abd(a, b, discriminant)
+    }
+}
+
+fn get_b(discriminant: &BigInt, x: &ClassgroupForm, y: &ClassgroupForm) -> Option<BigInt> {
+    let discriminant_bits = discriminant.bits() as usize;
+    let mut seed = x.serialize(discriminant_bits)?;
+    seed.extend(y.serialize(discriminant_bits)?);
+    Some(hash_prime(&seed, B_BYTES * 8, &[B_BYTES as u64 * 8 - 1]))
+}
+
+fn wesolowski_output(
+    x: &ClassgroupForm,
+    proof: &ClassgroupForm,
+    b: &BigInt,
+    iterations: u64,
+) -> ClassgroupForm {
+    let r = BigInt::from(2).modpow(&BigInt::from(iterations), b);
+    proof.pow(b).compose(&x.pow(&r))
+}
+
+pub fn verify_wesolowski(
+    discriminant: &BigInt,
+    x: &ClassgroupForm,
+    y: &ClassgroupForm,
+    proof: &ClassgroupForm,
+    iterations: u64,
+) -> bool {
+    match get_b(discriminant, x, y) {
+        Some(b) => wesolowski_output(x, proof, &b, iterations) == *y,
+        None => false,
+    }
+}
+
+pub fn verify_n_wesolowski(
+    discriminant: &BigInt,
+    x_s: &[u8],
+    proof_blob: &[u8],
+    iterations: u64,
+    depth: u8,
+) -> bool {
+    let segment_len = 8 + B_BYTES + BQFC_FORM_SIZE;
+    if proof_blob.len() != 2 * BQFC_FORM_SIZE + depth as usize * segment_len {
+        return false;
+    }
+    let mut x = match ClassgroupForm::deserialize(discriminant, x_s) {
+        Some(x) => x,
+        None => return false,
+    };
+    let mut iterations = iterations;
+    //Segments are stored after the output and proof, the last one starting from the input
+    for index in (0..depth as usize).rev() {
+        let segment = &proof_blob[2 * BQFC_FORM_SIZE + index * segment_len..][..segment_len];
+        let mut iters_bytes = [0u8; 8];
+        iters_bytes.copy_from_slice(&segment[..8]);
+        let segment_iters = u64::from_be_bytes(iters_bytes);
+        let b = BigInt::from_bytes_be(Sign::Plus, &segment[8..8 + B_BYTES]);
+        let proof = match ClassgroupForm::deserialize(discriminant, &segment[8 + B_BYTES..]) {
+            Some(proof) => proof,
+            None => return false,
+        };
+        if b.is_zero() || segment_iters > iterations {
+            return false;
+        }
+        let x_new = wesolowski_output(&x, &proof, &b, segment_iters);
+        if get_b(discriminant, &x, &x_new) != Some(b) {
+            return false;
+        }
+        x = x_new;
+        iterations -= segment_iters;
+    }
+    let y = ClassgroupForm::deserialize(discriminant, &proof_blob[..BQFC_FORM_SIZE]);
+    let proof = ClassgroupForm::deserialize(
+        discriminant,
+        &proof_blob[BQFC_FORM_SIZE..][..BQFC_FORM_SIZE],
+    );
+    match (y, proof) {
+        (Some(y), Some(proof)) => verify_wesolowski(discriminant, &x, &y, &proof, iterations),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ];
+
+    fn prove(
+        x: &ClassgroupForm,
+        discriminant: &BigInt,
+        iterations: u64,
+    ) -> (ClassgroupForm, ClassgroupForm) {
+        let y = x.pow(&(BigInt::one() << iterations));
+        let b = get_b(discriminant, x, &y).unwrap();
+        let proof = x.pow(&((BigInt::one() << iterations) / b));
+        (y, proof)
+    }
+
+    fn serialize(form: &ClassgroupForm, discriminant: &BigInt) -> Vec<u8> {
+        form.serialize(discriminant.bits() as usize).unwrap()
+    }
+
+    #[test]
+    fn test_hash_prime_sprout_increment() {
+        //Expected values follow chiavdf's HashPrime byte increment
+        let discriminant = create_discriminant(&SEED, 1024);
+        assert_eq!(
+            (-discriminant).to_str_radix(16),
+            "eb9c43030f6541d682b64d47319fafda796d93820de12171eff133281af35efcf47f6fcd18df9aa17cc9fcd15bb962a0a3170a45d11f505c99066ecfb7f394a650e0d8c19e4faaa6a37969f0e0f030f85cd55a5d3219faa569f919440be8195c8edf0994ce02ac600fd208dc58847ddfa3de2bb7a2b320313eed24ca2ab6de3f"
+        );
+        assert_eq!(
+            hash_prime(&[0u8; 32], 264, &[263]).to_str_radix(16),
+            "a8070d2b9c083cf1b68e79322080eddffd95d7e57b056e108b15f075d82c41e84f"
+        );
+    }
+
+    #[test]
+    fn test_default_element() {
+        let discriminant = create_discriminant(&SEED, 1024);
+        let mut default_element = vec![0u8; BQFC_FORM_SIZE];
+        default_element[0] = BQFC_IS_GEN;
+        let generator = ClassgroupForm::deserialize(&discriminant, &default_element).unwrap();
+        assert_eq!(generator, ClassgroupForm::generator(&discriminant).unwrap());
+        assert_eq!(serialize(&generator, &discriminant), default_element);
+    }
+
+    #[test]
+    fn test_rejects_non_canonical_forms() {
+        let discriminant = create_discriminant(&SEED, 1024);
+        //Picks a form with g = 1, so g and b0 are the last two bytes
+        let generator = ClassgroupForm::generator(&discriminant).unwrap();
+        let (form, bytes) = (2u32..)
+            .map(|exponent| {
+                let form = generator.pow(&BigInt::from(exponent));
+                let bytes = serialize(&form, &discriminant);
+                (form, bytes)
+            })
+            .find(|(_, bytes)| bytes[1] == 0 && bytes[BQFC_FORM_SIZE - 2] == 1)
+            .unwrap();
+        assert_eq!(
+            ClassgroupForm::deserialize(&discriminant, &bytes),
+            Some(form.clone())
+        );
+        //Adding 4 to b0 decodes to an equivalent form, but serialize never writes it
+        let mut non_canonical = bytes.clone();
+        non_canonical[BQFC_FORM_SIZE - 1] += 4;
+        assert_eq!(
+            ClassgroupForm::deserialize_unchecked(&discriminant, &non_canonical),
+            Some(form)
+        );
+        assert_eq!(
+            ClassgroupForm::deserialize(&discriminant, &non_canonical),
+            None
+        );
+        let mut with_generator_flag = bytes;
+        with_generator_flag[0] |= BQFC_IS_GEN;
+        assert_eq!(
+            ClassgroupForm::deserialize(&discriminant, &with_generator_flag),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_wesolowski_proofs() {
+        let discriminant = create_discriminant(&SEED, 1024);
+        let x = ClassgroupForm::generator(&discriminant).unwrap();
+        let x_s = serialize(&x, &discriminant);
+
+        //Witness type 0, a single proof from x to y
+        let (y, proof) = prove(&x, &discriminant, 300);
+        let mut proof_blob = serialize(&y, &discriminant);
+        proof_blob.extend(serialize(&proof, &discriminant));
+        assert!(verify_n_wesolowski(
+            &discriminant,
+            &x_s,
+            &proof_blob,
+            300,
+            0
+        ));
+        assert!(!verify_n_wesolowski(
+            &discriminant,
+            &x_s,
+            &proof_blob,
+            301,
+            0
+        ));
+
+        //Witness type 1, a 100 iteration segment from x followed by a proof for the rest
+        let (x_mid, segment_proof) = prove(&x, &discriminant, 100);
+        let (y, proof) = prove(&x_mid, &discriminant, 200);
+        let mut proof_blob = serialize(&y, &discriminant);
+        proof_blob.extend(serialize(&proof, &discriminant));
+        proof_blob.extend(100u64.to_be_bytes());
+        let (_, b_bytes) = get_b(&discriminant, &x, &x_mid).unwrap().to_bytes_be();
+        assert_eq!(b_bytes.len(), B_BYTES);
+        proof_blob.extend(b_bytes);
+        proof_blob.extend(serialize(&segment_proof, &discriminant));
+        assert!(verify_n_wesolowski(
+            &discriminant,
+            &x_s,
+            &proof_blob,
+            300,
+            1
+        ));
+        assert!(!verify_n_wesolowski(
+            &discriminant,
+            &x_s,
+            &proof_blob,
+            300,
+            0
+        ));
+        assert!(!verify_n_wesolowski(
+            &discriminant,
+            &x_s,
+            &proof_blob,
+            299,
+            1
+        ));
+    }
+}
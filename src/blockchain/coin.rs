@@ -14,6 +14,14 @@ impl Coin {
         self.hash().into()
     }
 
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.parent_coin_info.to_bytes());
+        bytes.extend(&self.puzzle_hash.to_bytes());
+        bytes.extend(self.amount.to_be_bytes());
+        bytes
+    }
+
     pub fn hash(&self) -> Vec<u8> {
         let mut to_hash: Vec<u8> = Vec::new();
         to_hash.extend(&self.parent_coin_info.to_bytes());
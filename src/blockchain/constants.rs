@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Constants {
     pub genesis_challenge: Bytes32,
+    pub genesis_pre_farm_pool_puzzle_hash: Bytes32,
     pub min_plot_size: u8,
     pub max_plot_size: u8,
     pub max_transaction_amount: u64,
@@ -12,23 +13,59 @@ pub struct Constants {
     pub pool_sub_slot_iters: u64,
     pub iters_limit: u64,
     pub protocol_version: i64,
+    pub num_sps_sub_slot: u32,
+    pub num_sp_intervals_extra: u8,
+    pub max_generator_ref_list_size: usize,
+    pub sub_slot_iters_starting: u64,
+    pub difficulty_starting: u64,
+    pub min_blocks_per_challenge_block: u8,
+    pub sub_epoch_blocks: u32,
+    pub epoch_blocks: u32,
+    pub max_sub_slot_blocks: u32,
+    pub max_future_time: u64,
+    pub discriminant_size_bits: usize,
+    pub max_vdf_witness_size: u8,
+    pub difficulty_change_max_factor: u64,
+    pub sub_slot_time_target: u64,
+    pub slot_blocks_target: u32,
+    pub significant_bits: u32,
 }
 
 lazy_static! {
     pub static ref MAINNET: Constants = Constants {
         genesis_challenge: "ccd5bb71183532bff220ba46c268991a3ff07eb358e8255a65c30a2dce0e5fbb"
             .into(),
+        genesis_pre_farm_pool_puzzle_hash:
+            "d23da14695a188ae5708dd152263c4db883eb27edeb936178d4d988b8f3ce5fc".into(),
         min_plot_size: 32,
         max_plot_size: 50,
         max_transaction_amount: 446250000000000,
-        difficulty_constant_factor: 2 ^ 67,
+        difficulty_constant_factor: 1 << 67,
         pool_sub_slot_iters: 37600000000,
         iters_limit: 37600000000 / 64,
         protocol_version: 1,
+        num_sps_sub_slot: 64,
+        num_sp_intervals_extra: 3,
+        max_generator_ref_list_size: 512,
+        sub_slot_iters_starting: 1 << 27,
+        difficulty_starting: 7,
+        min_blocks_per_challenge_block: 16,
+        sub_epoch_blocks: 384,
+        epoch_blocks: 4608,
+        max_sub_slot_blocks: 128,
+        max_future_time: 5 * 60,
+        discriminant_size_bits: 1024,
+        max_vdf_witness_size: 64,
+        difficulty_change_max_factor: 3,
+        sub_slot_time_target: 600,
+        slot_blocks_target: 32,
+        significant_bits: 8,
     };
     pub static ref TESTNET10: Constants = Constants {
         genesis_challenge: "ae83525ba8d1dd3f09b277de18ca3e43fc0af20d20c4b3e92ef2a48bd291ccb2"
             .into(),
+        genesis_pre_farm_pool_puzzle_hash:
+            "d23da14695a188ae5708dd152263c4db883eb27edeb936178d4d988b8f3ce5fc".into(),
         min_plot_size: 25,
         max_plot_size: 50,
         max_transaction_amount: 446250000000000,
@@ -36,5 +73,21 @@ lazy_static! {
         pool_sub_slot_iters: 37600000000,
         iters_limit: 37600000000 / 64,
         protocol_version: 1,
+        num_sps_sub_slot: 64,
+        num_sp_intervals_extra: 3,
+        max_generator_ref_list_size: 512,
+        sub_slot_iters_starting: 1 << 27,
+        difficulty_starting: 30,
+        min_blocks_per_challenge_block: 16,
+        sub_epoch_blocks: 384,
+        epoch_blocks: 768,
+        max_sub_slot_blocks: 128,
+        max_future_time: 5 * 60,
+        discriminant_size_bits: 1024,
+        max_vdf_witness_size: 64,
+        difficulty_change_max_factor: 3,
+        sub_slot_time_target: 600,
+        slot_blocks_target: 32,
+        significant_bits: 8,
     };
 }
@@ -0,0 +1,501 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::sized_bytes::Bytes32;
+use std::error::Error;
+
+fn bit_length(x: u64) -> u32 {
+    64 - x.leading_zeros()
+}
+
+pub fn count_significant_bits(x: u64) -> u32 {
+    if x == 0 {
+        return 0;
+    }
+    bit_length(x) - x.trailing_zeros()
+}
+
+pub fn truncate_to_significant_bits(x: u64, num_significant_bits: u32) -> u64 {
+    if num_significant_bits >= bit_length(x) {
+        return x;
+    }
+    let lower = bit_length(x) - num_significant_bits;
+    x & !((1u64 << lower) - 1)
+}
+
+fn get_blocks_at_height(
+    blocks: &impl BlockchainInterface,
+    prev_b: &BlockRecord,
+    target_height: u32,
+    max_num_blocks: u32,
+) -> Result<Vec<BlockRecord>, Box<dyn Error>> {
+    //Ancestors on the heaviest chain can be fetched by height directly
+    if blocks.height_to_hash(prev_b.height).as_ref() == Some(&prev_b.header_hash) {
+        let mut block_list: Vec<BlockRecord> = Vec::new();
+        for height in target_height..target_height + max_num_blocks {
+            block_list.push(blocks.height_to_block_record(height)?);
+        }
+        return Ok(block_list);
+    }
+    let mut curr_b = prev_b.clone();
+    let mut target_blocks: Vec<BlockRecord> = Vec::new();
+    while curr_b.height >= target_height {
+        if curr_b.height < target_height + max_num_blocks {
+            target_blocks.push(curr_b.clone());
+        }
+        if curr_b.height == 0 {
+            break;
+        }
+        curr_b = blocks.block_record(&curr_b.prev_hash)?;
+    }
+    target_blocks.reverse();
+    Ok(target_blocks)
+}
+
+fn fetched_block(
+    fetched_blocks: &[BlockRecord],
+    index: usize,
+) -> Result<&BlockRecord, Box<dyn Error>> {
+    fetched_blocks
+        .get(index)
+        .ok_or_else(|| "Not enough blocks to find the previous epoch".into())
+}
+
+fn get_second_to_last_transaction_block_in_previous_epoch(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    last_b: &BlockRecord,
+) -> Result<BlockRecord, Box<dyn Error>> {
+    let height_in_next_epoch = last_b.height
+        + 2 * constants.max_sub_slot_blocks
+        + constants.min_blocks_per_challenge_block as u32
+        + 5;
+    let height_epoch_surpass =
+        height_in_next_epoch - (height_in_next_epoch % constants.epoch_blocks);
+    let height_prev_epoch_surpass = height_epoch_surpass
+        .checked_sub(constants.epoch_blocks)
+        .ok_or("Block is before the end of the first epoch")?;
+    if height_in_next_epoch - height_epoch_surpass >= 5 * constants.max_sub_slot_blocks {
+        return Err("Block is too far past the epoch barrier".into());
+    }
+    //The genesis epoch is measured from the genesis block itself
+    if height_prev_epoch_surpass == 0 {
+        return get_blocks_at_height(blocks, last_b, 0, 1)?
+            .pop()
+            .ok_or_else(|| "Genesis block not found".into());
+    }
+
+    //The last block of the previous epoch is in this range, since the epoch can only start
+    //once the deficit allows a sub epoch summary to be included
+    let fetched_blocks = get_blocks_at_height(
+        blocks,
+        last_b,
+        height_prev_epoch_surpass - constants.max_sub_slot_blocks - 1,
+        3 * constants.max_sub_slot_blocks + constants.min_blocks_per_challenge_block as u32 + 3,
+    )?;
+    let mut fetched_index = constants.max_sub_slot_blocks as usize;
+    let mut curr_b = fetched_block(&fetched_blocks, fetched_index)?;
+    fetched_index += 1;
+    let mut next_b = fetched_block(&fetched_blocks, fetched_index)?;
+    fetched_index += 1;
+    if curr_b.height != height_prev_epoch_surpass - 1 || next_b.height != height_prev_epoch_surpass
+    {
+        return Err("Unexpected block heights around the previous epoch".into());
+    }
+
+    //The epoch ends at the block before the one including the sub epoch summary
+    while next_b.sub_epoch_summary_included.is_none() {
+        curr_b = next_b;
+        next_b = fetched_block(&fetched_blocks, fetched_index)?;
+        fetched_index += 1;
+    }
+
+    let mut curr_b = curr_b.clone();
+    let mut found_tx_block = if curr_b.is_transaction_block() { 1 } else { 0 };
+    while found_tx_block < 2 {
+        curr_b = blocks.block_record(&curr_b.prev_hash)?;
+        if curr_b.is_transaction_block() {
+            found_tx_block += 1;
+        }
+    }
+    Ok(curr_b)
+}
+
+fn last_transaction_block_before_sp(
+    blocks: &impl BlockchainInterface,
+    prev_header_hash: &Bytes32,
+    signage_point_total_iters: u128,
+) -> Result<BlockRecord, Box<dyn Error>> {
+    let mut last_block_curr = blocks.block_record(prev_header_hash)?;
+    while last_block_curr.total_iters > signage_point_total_iters
+        || !last_block_curr.is_transaction_block()
+    {
+        last_block_curr = blocks.block_record(&last_block_curr.prev_hash)?;
+    }
+    Ok(last_block_curr)
+}
+
+fn epoch_time(
+    last_block_curr: &BlockRecord,
+    last_block_prev: &BlockRecord,
+) -> Result<u64, Box<dyn Error>> {
+    match (last_block_curr.timestamp, last_block_prev.timestamp) {
+        (Some(curr), Some(prev)) if curr > prev => Ok(curr - prev),
+        (Some(_), Some(_)) => Err("Epoch timestamps are not increasing".into()),
+        _ => Err("Epoch boundary blocks must be transaction blocks".into()),
+    }
+}
+
+pub fn height_can_be_first_in_epoch(constants: &Constants, height: u32) -> bool {
+    (height - (height % constants.sub_epoch_blocks)) % constants.epoch_blocks == 0
+}
+
+//Returns whether the block at height is the last block of a sub epoch, and of an epoch
+pub fn can_finish_sub_and_full_epoch(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    height: u32,
+    prev_header_hash: &Bytes32,
+    deficit: u8,
+    block_at_height_included_ses: bool,
+) -> Result<(bool, bool), Box<dyn Error>> {
+    if height < constants.sub_epoch_blocks - 1 {
+        return Ok((false, false));
+    }
+    if deficit > 0 {
+        return Ok((false, false));
+    }
+    if block_at_height_included_ses {
+        return Ok((false, false));
+    }
+    //A sub epoch summary can only be included once per sub epoch
+    if (height + 1) % constants.sub_epoch_blocks > 1 {
+        let mut curr = blocks.block_record(prev_header_hash)?;
+        while curr.height % constants.sub_epoch_blocks > 0 {
+            if curr.sub_epoch_summary_included.is_some() {
+                return Ok((false, false));
+            }
+            curr = blocks.block_record(&curr.prev_hash)?;
+        }
+        if curr.sub_epoch_summary_included.is_some() {
+            return Ok((false, false));
+        }
+    }
+    Ok((true, height_can_be_first_in_epoch(constants, height + 1)))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn get_next_sub_slot_iters(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    prev_header_hash: &Bytes32,
+    height: u32,
+    curr_sub_slot_iters: u64,
+    deficit: u8,
+    block_at_height_included_ses: bool,
+    new_slot: bool,
+    signage_point_total_iters: u128,
+    skip_epoch_check: bool,
+) -> Result<u64, Box<dyn Error>> {
+    if height + 1 < constants.epoch_blocks {
+        return Ok(constants.sub_slot_iters_starting);
+    }
+    if !skip_epoch_check {
+        if !new_slot {
+            return Ok(curr_sub_slot_iters);
+        }
+        let (_, can_finish_epoch) = can_finish_sub_and_full_epoch(
+            constants,
+            blocks,
+            height,
+            prev_header_hash,
+            deficit,
+            block_at_height_included_ses,
+        )?;
+        if !can_finish_epoch {
+            return Ok(curr_sub_slot_iters);
+        }
+    }
+
+    let last_block_prev = get_second_to_last_transaction_block_in_previous_epoch(
+        constants,
+        blocks,
+        &blocks.block_record(prev_header_hash)?,
+    )?;
+    let last_block_curr =
+        last_transaction_block_before_sp(blocks, prev_header_hash, signage_point_total_iters)?;
+
+    //Iterations per second over the last epoch, scaled to the target slot time
+    let new_ssi_precise = (constants.sub_slot_time_target as u128
+        * (last_block_curr.total_iters - last_block_prev.total_iters)
+        / epoch_time(&last_block_curr, &last_block_prev)? as u128) as u64;
+
+    let max_ssi = constants.difficulty_change_max_factor * last_block_curr.sub_slot_iters;
+    let min_ssi = last_block_curr.sub_slot_iters / constants.difficulty_change_max_factor;
+    let new_ssi_precise = if new_ssi_precise >= last_block_curr.sub_slot_iters {
+        new_ssi_precise.min(max_ssi)
+    } else {
+        new_ssi_precise
+            .max(min_ssi)
+            .max(constants.num_sps_sub_slot as u64)
+    };
+    let new_ssi = truncate_to_significant_bits(new_ssi_precise, constants.significant_bits);
+    //Sub slot iters must divide evenly into signage points
+    Ok(new_ssi - new_ssi % constants.num_sps_sub_slot as u64)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn get_next_difficulty(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    prev_header_hash: &Bytes32,
+    height: u32,
+    current_difficulty: u64,
+    deficit: u8,
+    block_at_height_included_ses: bool,
+    new_slot: bool,
+    signage_point_total_iters: u128,
+    skip_epoch_check: bool,
+) -> Result<u64, Box<dyn Error>> {
+    //Still in the first epoch
+    let first_epoch_end = constants
+        .epoch_blocks
+        .saturating_sub(3 * constants.max_sub_slot_blocks);
+    if height + 1 < first_epoch_end {
+        return Ok(constants.difficulty_starting);
+    }
+    if !skip_epoch_check {
+        if !new_slot {
+            return Ok(current_difficulty);
+        }
+        let (_, can_finish_epoch) = can_finish_sub_and_full_epoch(
+            constants,
+            blocks,
+            height,
+            prev_header_hash,
+            deficit,
+            block_at_height_included_ses,
+        )?;
+        if !can_finish_epoch {
+            return Ok(current_difficulty);
+        }
+    }
+
+    let prev_b = blocks.block_record(prev_header_hash)?;
+    let last_block_prev =
+        get_second_to_last_transaction_block_in_previous_epoch(constants, blocks, &prev_b)?;
+    let last_block_curr =
+        last_transaction_block_before_sp(blocks, prev_header_hash, signage_point_total_iters)?;
+    let actual_epoch_time = epoch_time(&last_block_curr, &last_block_prev)?;
+    //The difficulty being replaced is prev_b's, which need not be a transaction block
+    let old_difficulty = (prev_b.weight - blocks.block_record(&prev_b.prev_hash)?.weight) as u64;
+
+    let new_difficulty_precise =
+        ((last_block_curr.weight - last_block_prev.weight) * constants.sub_slot_time_target as u128
+            / (constants.slot_blocks_target as u128 * actual_epoch_time as u128)) as u64;
+
+    //Only change by a max factor to prevent attacks, and never drop below 1
+    let max_diff = constants.difficulty_change_max_factor * old_difficulty;
+    let min_diff = old_difficulty / constants.difficulty_change_max_factor;
+    let new_difficulty_precise = if new_difficulty_precise >= old_difficulty {
+        new_difficulty_precise.min(max_diff)
+    } else {
+        new_difficulty_precise.max(min_diff).max(1)
+    };
+    Ok(truncate_to_significant_bits(
+        new_difficulty_precise,
+        constants.significant_bits,
+    ))
+}
+
+//Returns the sub slot iters and difficulty of the block after prev_b
+pub fn get_next_sub_slot_iters_and_difficulty(
+    constants: &Constants,
+    is_first_in_sub_slot: bool,
+    prev_b: Option<&BlockRecord>,
+    blocks: &impl BlockchainInterface,
+) -> Result<(u64, u64), Box<dyn Error>> {
+    let prev_b = match prev_b {
+        Some(prev_b) => prev_b,
+        None => {
+            return Ok((
+                constants.sub_slot_iters_starting,
+                constants.difficulty_starting,
+            ))
+        }
+    };
+    let prev_difficulty = if prev_b.height != 0 {
+        (prev_b.weight - blocks.block_record(&prev_b.prev_hash)?.weight) as u64
+    } else {
+        prev_b.weight as u64
+    };
+    if prev_b.sub_epoch_summary_included.is_some() {
+        return Ok((prev_b.sub_slot_iters, prev_difficulty));
+    }
+
+    let sp_total_iters = prev_b.sp_total_iters(constants)?;
+    let difficulty = get_next_difficulty(
+        constants,
+        blocks,
+        &prev_b.prev_hash,
+        prev_b.height,
+        prev_difficulty,
+        prev_b.deficit,
+        false,
+        is_first_in_sub_slot,
+        sp_total_iters,
+        false,
+    )?;
+    let sub_slot_iters = get_next_sub_slot_iters(
+        constants,
+        blocks,
+        &prev_b.prev_hash,
+        prev_b.height,
+        prev_b.sub_slot_iters,
+        prev_b.deficit,
+        false,
+        is_first_in_sub_slot,
+        sp_total_iters,
+        false,
+    )?;
+    Ok((sub_slot_iters, difficulty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::block_cache::BlockCache;
+    use crate::blockchain::constants::MAINNET;
+
+    fn constants() -> Constants {
+        let mut constants = MAINNET.clone();
+        constants.sub_epoch_blocks = 32;
+        constants.epoch_blocks = 64;
+        constants.max_sub_slot_blocks = 4;
+        constants.min_blocks_per_challenge_block = 2;
+        constants
+    }
+
+    //First epoch chain up to height 62, every block adds 100 weight except the last which adds 50
+    //and is not a transaction block
+    fn first_epoch() -> BlockCache {
+        let mut blocks = BlockCache::new();
+        for height in 0..63u32 {
+            let mut record = BlockRecord::for_test(height);
+            record.weight = 100 * (height as u128 + 1);
+            record.total_iters = 1000 * height as u128;
+            record.timestamp = Some(10 * height as u64);
+            record.sub_slot_iters = 1 << 15;
+            if height == 62 {
+                record.weight -= 50;
+                record.timestamp = None;
+            }
+            blocks.add_block_record(record);
+        }
+        blocks
+    }
+
+    #[test]
+    fn test_first_epoch_keeps_starting_values() {
+        let constants = constants();
+        let blocks = BlockCache::new();
+        let prev_header_hash = BlockRecord::for_test(61).header_hash;
+        assert_eq!(
+            get_next_sub_slot_iters(
+                &constants,
+                &blocks,
+                &prev_header_hash,
+                62,
+                1 << 15,
+                0,
+                false,
+                true,
+                0,
+                true
+            )
+            .unwrap(),
+            constants.sub_slot_iters_starting
+        );
+        assert_eq!(
+            get_next_difficulty(
+                &constants,
+                &blocks,
+                &prev_header_hash,
+                50,
+                50,
+                0,
+                false,
+                true,
+                0,
+                true
+            )
+            .unwrap(),
+            constants.difficulty_starting
+        );
+    }
+
+    #[test]
+    fn test_first_epoch_boundary() {
+        let constants = constants();
+        let blocks = first_epoch();
+        let prev_header_hash = BlockRecord::for_test(62).header_hash;
+        //Measured from genesis to block 61, the last transaction block before the signage point
+        assert_eq!(
+            get_next_sub_slot_iters(
+                &constants,
+                &blocks,
+                &prev_header_hash,
+                63,
+                1 << 15,
+                0,
+                false,
+                true,
+                u128::MAX,
+                true
+            )
+            .unwrap(),
+            59904
+        );
+        //The precise difficulty of 187 is capped at three times prev_b's difficulty of 50
+        assert_eq!(
+            get_next_difficulty(
+                &constants,
+                &blocks,
+                &prev_header_hash,
+                63,
+                50,
+                0,
+                false,
+                true,
+                u128::MAX,
+                true
+            )
+            .unwrap(),
+            150
+        );
+        //Without a new sub slot the epoch can not end
+        assert_eq!(
+            get_next_difficulty(
+                &constants,
+                &blocks,
+                &prev_header_hash,
+                63,
+                50,
+                0,
+                false,
+                false,
+                u128::MAX,
+                false
+            )
+            .unwrap(),
+            50
+        );
+    }
+
+    #[test]
+    fn test_significant_bits() {
+        assert_eq!(count_significant_bits(0b1011000), 4);
+        assert_eq!(truncate_to_significant_bits(60000, 8), 59904);
+        assert_eq!(truncate_to_significant_bits(150, 8), 150);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::blockchain::foliage_block_data::FoliageBlockData;
-use crate::blockchain::sized_bytes::{Bytes32, Bytes96};
+use crate::blockchain::sized_bytes::{Bytes32, Bytes96, SizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -11,3 +12,34 @@ pub struct Foliage {
     pub foliage_transaction_block_signature: Option<Bytes96>,
     pub foliage_block_data: FoliageBlockData,
 }
+impl Foliage {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.prev_block_hash.to_bytes());
+        bytes.extend(&self.reward_block_hash.to_bytes());
+        bytes.extend(self.foliage_block_data.to_bytes());
+        bytes.extend(&self.foliage_block_data_signature.to_bytes());
+        match &self.foliage_transaction_block_hash {
+            Some(block_hash) => {
+                bytes.push(1u8);
+                bytes.extend(&block_hash.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        match &self.foliage_transaction_block_signature {
+            Some(signature) => {
+                bytes.push(1u8);
+                bytes.extend(&signature.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes
+    }
+}
@@ -1,5 +1,6 @@
 use crate::blockchain::pool_target::PoolTarget;
-use crate::blockchain::sized_bytes::{Bytes32, Bytes96};
+use crate::blockchain::sized_bytes::{Bytes32, Bytes96, SizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -10,3 +11,25 @@ pub struct FoliageBlockData {
     pub pool_signature: Option<Bytes96>,
     pub pool_target: PoolTarget,
 }
+impl FoliageBlockData {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.unfinished_reward_block_hash.to_bytes());
+        bytes.extend(self.pool_target.to_bytes());
+        match &self.pool_signature {
+            Some(signature) => {
+                bytes.push(1u8);
+                bytes.extend(&signature.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.farmer_reward_puzzle_hash.to_bytes());
+        bytes.extend(&self.extension_data.to_bytes());
+        bytes
+    }
+}
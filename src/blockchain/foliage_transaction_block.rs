@@ -1,4 +1,5 @@
-use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -10,3 +11,18 @@ pub struct FoliageTransactionBlock {
     pub transactions_info_hash: Bytes32,
     pub timestamp: u64,
 }
+impl FoliageTransactionBlock {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.prev_transaction_block_hash.to_bytes());
+        bytes.extend(self.timestamp.to_be_bytes());
+        bytes.extend(&self.filter_hash.to_bytes());
+        bytes.extend(&self.additions_root.to_bytes());
+        bytes.extend(&self.removals_root.to_bytes());
+        bytes.extend(&self.transactions_info_hash.to_bytes());
+        bytes
+    }
+}
@@ -1,23 +1,45 @@
 use crate::blockchain::foliage::Foliage;
 use crate::blockchain::foliage_transaction_block::FoliageTransactionBlock;
 use crate::blockchain::reward_chain_block::RewardChainBlock;
+use crate::blockchain::sized_bytes::Bytes32;
 use crate::blockchain::subslot_bundle::SubSlotBundle;
 use crate::blockchain::transactions_info::TransactionsInfo;
 use crate::blockchain::vdf_proof::VdfProof;
+use crate::clvm::serialized_program::SerializedProgram;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FullBlock {
     pub challenge_chain_ip_proof: VdfProof,
-    pub challenge_chain_sp_proof: VdfProof,
+    pub challenge_chain_sp_proof: Option<VdfProof>,
     pub infused_challenge_chain_ip_proof: Option<VdfProof>,
     pub reward_chain_ip_proof: VdfProof,
     pub reward_chain_sp_proof: Option<VdfProof>,
     pub foliage: Foliage,
     pub foliage_transaction_block: Option<FoliageTransactionBlock>,
-    pub transactions_generator: Option<String>,
+    pub transactions_generator: Option<SerializedProgram>,
     pub transactions_generator_ref_list: Vec<u32>,
     pub finished_sub_slots: Vec<SubSlotBundle>,
     pub reward_chain_block: RewardChainBlock,
     pub transactions_info: Option<TransactionsInfo>,
 }
+impl FullBlock {
+    pub fn header_hash(&self) -> Bytes32 {
+        self.foliage.hash()
+    }
+    pub fn prev_header_hash(&self) -> Bytes32 {
+        self.foliage.prev_block_hash.clone()
+    }
+    pub fn height(&self) -> u32 {
+        self.reward_chain_block.height as u32
+    }
+    pub fn weight(&self) -> u128 {
+        self.reward_chain_block.weight
+    }
+    pub fn total_iters(&self) -> u128 {
+        self.reward_chain_block.total_iters
+    }
+    pub fn is_transaction_block(&self) -> bool {
+        self.foliage.foliage_transaction_block_hash.is_some()
+    }
+}
@@ -0,0 +1,271 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::challenge_block_info::ChallengeBlockInfo;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::difficulty_adjustment::get_next_sub_slot_iters_and_difficulty;
+use crate::blockchain::foliage::Foliage;
+use crate::blockchain::foliage_transaction_block::FoliageTransactionBlock;
+use crate::blockchain::full_block::FullBlock;
+use crate::blockchain::header_block::HeaderBlock;
+use crate::blockchain::make_sub_epoch_summary::make_sub_epoch_summary;
+use crate::blockchain::pot_iterations::is_overflow_block;
+use crate::blockchain::reward_chain_block::RewardChainBlock;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use crate::blockchain::subslot_bundle::SubSlotBundle;
+use crate::blockchain::transactions_info::TransactionsInfo;
+use std::error::Error;
+
+pub struct BlockParts<'a> {
+    pub header_hash: Bytes32,
+    pub foliage: &'a Foliage,
+    pub foliage_transaction_block: &'a Option<FoliageTransactionBlock>,
+    pub reward_chain_block: &'a RewardChainBlock,
+    pub finished_sub_slots: &'a Vec<SubSlotBundle>,
+    pub transactions_info: &'a Option<TransactionsInfo>,
+}
+impl<'a> From<&'a FullBlock> for BlockParts<'a> {
+    fn from(block: &'a FullBlock) -> Self {
+        BlockParts {
+            header_hash: block.header_hash(),
+            foliage: &block.foliage,
+            foliage_transaction_block: &block.foliage_transaction_block,
+            reward_chain_block: &block.reward_chain_block,
+            finished_sub_slots: &block.finished_sub_slots,
+            transactions_info: &block.transactions_info,
+        }
+    }
+}
+impl<'a> From<&'a HeaderBlock> for BlockParts<'a> {
+    fn from(block: &'a HeaderBlock) -> Self {
+        BlockParts {
+            header_hash: block.header_hash(),
+            foliage: &block.foliage,
+            foliage_transaction_block: &block.foliage_transaction_block,
+            reward_chain_block: &block.reward_chain_block,
+            finished_sub_slots: &block.finished_sub_slots,
+            transactions_info: &block.transactions_info,
+        }
+    }
+}
+impl<'a> BlockParts<'a> {
+    pub fn height(&self) -> u32 {
+        self.reward_chain_block.height as u32
+    }
+}
+
+pub fn calculate_deficit(
+    constants: &Constants,
+    height: u32,
+    prev_b: Option<&BlockRecord>,
+    overflow: bool,
+    num_finished_sub_slots: usize,
+) -> Result<u8, Box<dyn Error>> {
+    if height == 0 {
+        return Ok(constants.min_blocks_per_challenge_block - 1);
+    }
+    let prev_deficit = match prev_b {
+        Some(prev_b) => prev_b.deficit,
+        None => return Err("Previous block is required to calculate the deficit".into()),
+    };
+    if prev_deficit == constants.min_blocks_per_challenge_block {
+        //The previous block was an overflow block, the deficit only drops once we leave its sub slot
+        if overflow && num_finished_sub_slots == 0 {
+            Ok(prev_deficit)
+        } else {
+            Ok(prev_deficit - 1)
+        }
+    } else if prev_deficit == 0 {
+        if num_finished_sub_slots == 0 {
+            Ok(0)
+        } else if num_finished_sub_slots == 1 && overflow {
+            Ok(constants.min_blocks_per_challenge_block)
+        } else {
+            Ok(constants.min_blocks_per_challenge_block - 1)
+        }
+    } else {
+        Ok(prev_deficit - 1)
+    }
+}
+
+pub fn block_to_block_record(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    required_iters: u64,
+    full_block: &FullBlock,
+) -> Result<BlockRecord, Box<dyn Error>> {
+    block_parts_to_block_record(constants, blocks, required_iters, &full_block.into())
+}
+
+pub fn block_parts_to_block_record(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    required_iters: u64,
+    block: &BlockParts,
+) -> Result<BlockRecord, Box<dyn Error>> {
+    let height = block.height();
+    let prev_b = blocks.try_block_record(&block.foliage.prev_block_hash);
+    if height > 0 && prev_b.is_none() {
+        return Err(format!("Previous block not found for block at height {}", height).into());
+    }
+    let (sub_slot_iters, _) = get_next_sub_slot_iters_and_difficulty(
+        constants,
+        !block.finished_sub_slots.is_empty(),
+        prev_b.as_ref(),
+        blocks,
+    )?;
+    let overflow = is_overflow_block(constants, block.reward_chain_block.signage_point_index)?;
+    let deficit = calculate_deficit(
+        constants,
+        height,
+        prev_b.as_ref(),
+        overflow,
+        block.finished_sub_slots.len(),
+    )?;
+
+    let mut found_ses_hash: Option<Bytes32> = None;
+    for sub_slot in block.finished_sub_slots {
+        if let Some(ses_hash) = &sub_slot.challenge_chain.subepoch_summary_hash {
+            found_ses_hash = Some(ses_hash.clone());
+        }
+    }
+    let ses: Option<SubEpochSummary> = match found_ses_hash {
+        Some(ses_hash) => {
+            let prev_b = prev_b
+                .as_ref()
+                .ok_or("Sub epoch summary included without a previous block")?;
+            let first_sub_slot = &block.finished_sub_slots[0];
+            let ses = make_sub_epoch_summary(
+                constants,
+                blocks,
+                height,
+                &blocks.block_record(&prev_b.prev_hash)?,
+                first_sub_slot.challenge_chain.new_difficulty,
+                first_sub_slot.challenge_chain.new_sub_slot_iters,
+            )?;
+            if ses.hash() != ses_hash {
+                return Err("Invalid sub epoch summary".into());
+            }
+            Some(ses)
+        }
+        None => None,
+    };
+
+    let mut prev_transaction_block_height: u32 = 0;
+    let mut curr = prev_b.clone();
+    while let Some(record) = curr {
+        if record.is_transaction_block() {
+            prev_transaction_block_height = record.height;
+            break;
+        }
+        curr = blocks.try_block_record(&record.prev_hash);
+    }
+
+    header_block_to_sub_block_record(
+        constants,
+        required_iters,
+        block,
+        sub_slot_iters,
+        overflow,
+        deficit,
+        prev_transaction_block_height,
+        ses,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn header_block_to_sub_block_record(
+    constants: &Constants,
+    required_iters: u64,
+    block: &BlockParts,
+    sub_slot_iters: u64,
+    overflow: bool,
+    deficit: u8,
+    prev_transaction_block_height: u32,
+    ses: Option<SubEpochSummary>,
+) -> Result<BlockRecord, Box<dyn Error>> {
+    let reward_chain_block = block.reward_chain_block;
+    let cbi = ChallengeBlockInfo {
+        proof_of_space: reward_chain_block.proof_of_space.clone(),
+        challenge_chain_sp_vdf: reward_chain_block.challenge_chain_sp_vdf.clone(),
+        challenge_chain_sp_signature: reward_chain_block.challenge_chain_sp_signature.clone(),
+        challenge_chain_ip_vdf: reward_chain_block.challenge_chain_ip_vdf.clone(),
+    };
+    let (
+        finished_challenge_slot_hashes,
+        finished_infused_challenge_slot_hashes,
+        finished_reward_slot_hashes,
+    ) = if !block.finished_sub_slots.is_empty() {
+        let mut challenge_hashes: Vec<Bytes32> = Vec::new();
+        let mut infused_hashes: Vec<Bytes32> = Vec::new();
+        let mut reward_hashes: Vec<Bytes32> = Vec::new();
+        for sub_slot in block.finished_sub_slots {
+            challenge_hashes.push(Bytes32::new(sub_slot.challenge_chain.hash()?));
+            if let Some(infused) = &sub_slot.infused_challenge_chain {
+                infused_hashes.push(infused.hash());
+            }
+            reward_hashes.push(sub_slot.reward_chain.hash());
+        }
+        (
+            Some(challenge_hashes),
+            Some(infused_hashes),
+            Some(reward_hashes),
+        )
+    } else if block.height() == 0 {
+        (
+            Some(vec![constants.genesis_challenge.clone()]),
+            None,
+            Some(vec![constants.genesis_challenge.clone()]),
+        )
+    } else {
+        (None, None, None)
+    };
+    Ok(BlockRecord {
+        header_hash: block.header_hash.clone(),
+        prev_hash: block.foliage.prev_block_hash.clone(),
+        height: block.height(),
+        weight: reward_chain_block.weight,
+        total_iters: reward_chain_block.total_iters,
+        signage_point_index: reward_chain_block.signage_point_index,
+        challenge_vdf_output: reward_chain_block.challenge_chain_ip_vdf.output.clone(),
+        infused_challenge_vdf_output: reward_chain_block
+            .infused_challenge_chain_ip_vdf
+            .as_ref()
+            .map(|vdf| vdf.output.clone()),
+        reward_infusion_new_challenge: reward_chain_block.hash(),
+        challenge_block_info_hash: cbi.hash(),
+        sub_slot_iters,
+        pool_puzzle_hash: block
+            .foliage
+            .foliage_block_data
+            .pool_target
+            .puzzle_hash
+            .clone(),
+        farmer_puzzle_hash: block
+            .foliage
+            .foliage_block_data
+            .farmer_reward_puzzle_hash
+            .clone(),
+        required_iters,
+        deficit,
+        overflow,
+        prev_transaction_block_height,
+        timestamp: block
+            .foliage_transaction_block
+            .as_ref()
+            .map(|transaction_block| transaction_block.timestamp),
+        prev_transaction_block_hash: block
+            .foliage_transaction_block
+            .as_ref()
+            .map(|transaction_block| transaction_block.prev_transaction_block_hash.clone()),
+        fees: block.transactions_info.as_ref().map(|info| info.fees),
+        reward_claims_incorporated: block
+            .transactions_info
+            .as_ref()
+            .map(|info| info.reward_claims_incorporated.clone()),
+        finished_challenge_slot_hashes,
+        finished_infused_challenge_slot_hashes,
+        finished_reward_slot_hashes,
+        sub_epoch_summary_included: ses,
+    })
+}
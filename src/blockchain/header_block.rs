@@ -0,0 +1,70 @@
+use crate::blockchain::coin::Coin;
+use crate::blockchain::foliage::Foliage;
+use crate::blockchain::foliage_transaction_block::FoliageTransactionBlock;
+use crate::blockchain::full_block::FullBlock;
+use crate::blockchain::reward_chain_block::RewardChainBlock;
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::subslot_bundle::SubSlotBundle;
+use crate::blockchain::transactions_filter::TransactionsFilter;
+use crate::blockchain::transactions_info::TransactionsInfo;
+use crate::blockchain::vdf_proof::VdfProof;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HeaderBlock {
+    pub finished_sub_slots: Vec<SubSlotBundle>,
+    pub reward_chain_block: RewardChainBlock,
+    pub challenge_chain_sp_proof: Option<VdfProof>,
+    pub challenge_chain_ip_proof: VdfProof,
+    pub reward_chain_sp_proof: Option<VdfProof>,
+    pub reward_chain_ip_proof: VdfProof,
+    pub infused_challenge_chain_ip_proof: Option<VdfProof>,
+    pub foliage: Foliage,
+    pub foliage_transaction_block: Option<FoliageTransactionBlock>,
+    pub transactions_filter: String,
+    pub transactions_info: Option<TransactionsInfo>,
+}
+impl HeaderBlock {
+    pub fn from_full_block(block: FullBlock, additions: &[Coin], removals: &[Bytes32]) -> Self {
+        //Only transaction blocks carry a filter of their additions and removals
+        let transactions_filter = if block.is_transaction_block() {
+            hex::encode(TransactionsFilter::from_block(additions, removals).encoded())
+        } else {
+            String::new()
+        };
+        HeaderBlock {
+            finished_sub_slots: block.finished_sub_slots,
+            reward_chain_block: block.reward_chain_block,
+            challenge_chain_sp_proof: block.challenge_chain_sp_proof,
+            challenge_chain_ip_proof: block.challenge_chain_ip_proof,
+            reward_chain_sp_proof: block.reward_chain_sp_proof,
+            reward_chain_ip_proof: block.reward_chain_ip_proof,
+            infused_challenge_chain_ip_proof: block.infused_challenge_chain_ip_proof,
+            foliage: block.foliage,
+            foliage_transaction_block: block.foliage_transaction_block,
+            transactions_filter,
+            transactions_info: block.transactions_info,
+        }
+    }
+    pub fn header_hash(&self) -> Bytes32 {
+        self.foliage.hash()
+    }
+    pub fn prev_header_hash(&self) -> Bytes32 {
+        self.foliage.prev_block_hash.clone()
+    }
+    pub fn height(&self) -> u32 {
+        self.reward_chain_block.height as u32
+    }
+    pub fn weight(&self) -> u128 {
+        self.reward_chain_block.weight
+    }
+    pub fn total_iters(&self) -> u128 {
+        self.reward_chain_block.total_iters
+    }
+    pub fn is_transaction_block(&self) -> bool {
+        self.foliage.foliage_transaction_block_hash.is_some()
+    }
+    pub fn first_in_sub_slot(&self) -> bool {
+        !self.finished_sub_slots.is_empty()
+    }
+}
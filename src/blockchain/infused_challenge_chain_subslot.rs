@@ -1,7 +1,17 @@
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
 use crate::blockchain::vdf_info::VdfInfo;
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InfusedChallengeChainSubSlot {
     pub infused_challenge_chain_end_of_slot_vdf: VdfInfo,
 }
+impl InfusedChallengeChainSubSlot {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.infused_challenge_chain_end_of_slot_vdf.to_bytes()
+    }
+}
@@ -0,0 +1,283 @@
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::difficulty_adjustment::{
+    can_finish_sub_and_full_epoch, get_next_difficulty, get_next_sub_slot_iters,
+    get_next_sub_slot_iters_and_difficulty, height_can_be_first_in_epoch,
+};
+use crate::blockchain::full_block_to_block_record::{calculate_deficit, BlockParts};
+use crate::blockchain::pot_iterations::{
+    calculate_ip_iters, calculate_sp_iters, is_overflow_block,
+};
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use std::error::Error;
+
+pub fn make_sub_epoch_summary(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    blocks_included_height: u32,
+    prev_prev_block: &BlockRecord,
+    new_difficulty: Option<u64>,
+    new_sub_slot_iters: Option<u64>,
+) -> Result<SubEpochSummary, Box<dyn Error>> {
+    if prev_prev_block.height + 2 != blocks_included_height {
+        return Err("Sub epoch summary must be built from the block two blocks back".into());
+    }
+    //The first sub epoch has no previous summary to point to
+    if (blocks_included_height + constants.max_sub_slot_blocks) / constants.sub_epoch_blocks <= 1 {
+        return Ok(SubEpochSummary {
+            prev_subepoch_summary_hash: constants.genesis_challenge.clone(),
+            reward_chain_hash: constants.genesis_challenge.clone(),
+            num_blocks_overflow: 0,
+            new_difficulty: None,
+            new_sub_slot_iters: None,
+        });
+    }
+    let mut curr = prev_prev_block.clone();
+    while curr.sub_epoch_summary_included.is_none() {
+        curr = blocks.block_record(&curr.prev_hash)?;
+    }
+    let prev_ses = match &curr.sub_epoch_summary_included {
+        Some(ses) => ses.hash(),
+        None => return Err("Missing sub epoch summary".into()),
+    };
+    let reward_chain_hash = match &curr.finished_reward_slot_hashes {
+        Some(hashes) if !hashes.is_empty() => hashes[hashes.len() - 1].clone(),
+        _ => return Err("Sub epoch summary block has no finished reward slots".into()),
+    };
+    Ok(SubEpochSummary {
+        prev_subepoch_summary_hash: prev_ses,
+        reward_chain_hash,
+        num_blocks_overflow: (curr.height % constants.sub_epoch_blocks) as u8,
+        new_difficulty,
+        new_sub_slot_iters,
+    })
+}
+
+//Returns the sub epoch summary to include in the block after block, if that block should have one
+pub fn next_sub_epoch_summary(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    required_iters: u64,
+    block: &BlockParts,
+    can_finish_soon: bool,
+) -> Result<Option<SubEpochSummary>, Box<dyn Error>> {
+    //A summary was just included, so the next one is a full sub epoch away
+    if let Some(first_sub_slot) = block.finished_sub_slots.first() {
+        if first_sub_slot
+            .challenge_chain
+            .subepoch_summary_hash
+            .is_some()
+            || first_sub_slot.challenge_chain.new_difficulty.is_some()
+        {
+            return Ok(None);
+        }
+    }
+    let height = block.height();
+    let prev_header_hash = &block.foliage.prev_block_hash;
+    let prev_b = if height > 0 {
+        Some(blocks.block_record(prev_header_hash)?)
+    } else {
+        None
+    };
+    let sub_slot_iters = match &prev_b {
+        Some(prev_b) if prev_b.height > 0 => {
+            get_next_sub_slot_iters_and_difficulty(
+                constants,
+                !block.finished_sub_slots.is_empty(),
+                Some(prev_b),
+                blocks,
+            )?
+            .0
+        }
+        _ => constants.sub_slot_iters_starting,
+    };
+    let signage_point_index = block.reward_chain_block.signage_point_index;
+    let overflow = is_overflow_block(constants, signage_point_index)?;
+
+    let (deficit, can_finish_se, can_finish_epoch) = if can_finish_soon {
+        //Assume the deficit reaches zero soon and the summary lands two blocks from now
+        let can_finish_epoch = if height_can_be_first_in_epoch(constants, height + 2) {
+            let mut can_finish_epoch = true;
+            if (height + 2) % constants.sub_epoch_blocks > 1 {
+                if let Some(prev_b) = &prev_b {
+                    //An epoch already started in this sub epoch
+                    let mut curr = prev_b.clone();
+                    loop {
+                        if let Some(ses) = &curr.sub_epoch_summary_included {
+                            if ses.new_difficulty.is_some() {
+                                can_finish_epoch = false;
+                            }
+                        }
+                        if curr.height % constants.sub_epoch_blocks == 0 {
+                            break;
+                        }
+                        curr = blocks.block_record(&curr.prev_hash)?;
+                    }
+                }
+            }
+            can_finish_epoch
+        } else {
+            height_can_be_first_in_epoch(constants, height + constants.max_sub_slot_blocks + 2)
+        };
+        (0, true, can_finish_epoch)
+    } else {
+        let deficit = calculate_deficit(
+            constants,
+            height + 1,
+            prev_b.as_ref(),
+            overflow,
+            block.finished_sub_slots.len(),
+        )?;
+        let (can_finish_se, can_finish_epoch) = can_finish_sub_and_full_epoch(
+            constants,
+            blocks,
+            height,
+            prev_header_hash,
+            deficit,
+            false,
+        )?;
+        (deficit, can_finish_se, can_finish_epoch)
+    };
+    if !can_finish_se {
+        return Ok(None);
+    }
+    //The genesis block never ends a sub epoch
+    let prev_b = match prev_b {
+        Some(prev_b) => prev_b,
+        None => return Ok(None),
+    };
+
+    let mut next_difficulty: Option<u64> = None;
+    let mut next_sub_slot_iters: Option<u64> = None;
+    if can_finish_epoch {
+        let sp_iters = calculate_sp_iters(constants, sub_slot_iters, signage_point_index)?;
+        let ip_iters = calculate_ip_iters(
+            constants,
+            sub_slot_iters,
+            signage_point_index,
+            required_iters,
+        )?;
+        let mut sp_total_iters =
+            block.reward_chain_block.total_iters - ip_iters as u128 + sp_iters as u128;
+        if overflow {
+            sp_total_iters -= sub_slot_iters as u128;
+        }
+        next_difficulty = Some(get_next_difficulty(
+            constants,
+            blocks,
+            prev_header_hash,
+            height,
+            (block.reward_chain_block.weight - prev_b.weight) as u64,
+            deficit,
+            false,
+            true,
+            sp_total_iters,
+            true,
+        )?);
+        next_sub_slot_iters = Some(get_next_sub_slot_iters(
+            constants,
+            blocks,
+            prev_header_hash,
+            height,
+            sub_slot_iters,
+            deficit,
+            false,
+            true,
+            sp_total_iters,
+            true,
+        )?);
+    }
+    Ok(Some(make_sub_epoch_summary(
+        constants,
+        blocks,
+        height + 1,
+        &prev_b,
+        next_difficulty,
+        next_sub_slot_iters,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::block_cache::BlockCache;
+    use crate::blockchain::constants::MAINNET;
+    use crate::blockchain::foliage::Foliage;
+    use crate::blockchain::foliage_block_data::FoliageBlockData;
+    use crate::blockchain::pool_target::PoolTarget;
+    use crate::blockchain::proof_of_space::ProofOfSpace;
+    use crate::blockchain::reward_chain_block::RewardChainBlock;
+    use crate::blockchain::sized_bytes::{Bytes32, Bytes48, Bytes96, UnsizedBytes};
+    use crate::blockchain::vdf_info::VdfInfo;
+    use crate::blockchain::vdf_output::VdfOutput;
+
+    fn vdf_info() -> VdfInfo {
+        VdfInfo {
+            challenge: MAINNET.genesis_challenge.clone(),
+            output: VdfOutput::default_element(),
+            number_of_iterations: 0,
+        }
+    }
+
+    #[test]
+    fn test_no_summary_after_genesis() {
+        let zeros = Bytes32::new(vec![0u8; 32]);
+        let foliage = Foliage {
+            foliage_transaction_block_hash: None,
+            prev_block_hash: MAINNET.genesis_challenge.clone(),
+            reward_block_hash: zeros.clone(),
+            foliage_block_data_signature: Bytes96::new(vec![0u8; 96]),
+            foliage_transaction_block_signature: None,
+            foliage_block_data: FoliageBlockData {
+                extension_data: zeros.clone(),
+                farmer_reward_puzzle_hash: zeros.clone(),
+                unfinished_reward_block_hash: zeros.clone(),
+                pool_signature: None,
+                pool_target: PoolTarget {
+                    max_height: 0,
+                    puzzle_hash: MAINNET.genesis_pre_farm_pool_puzzle_hash.clone(),
+                },
+            },
+        };
+        let reward_chain_block = RewardChainBlock {
+            pos_ss_cc_challenge_hash: MAINNET.genesis_challenge.clone(),
+            challenge_chain_sp_signature: Bytes96::new(vec![0u8; 96]),
+            reward_chain_sp_signature: Bytes96::new(vec![0u8; 96]),
+            challenge_chain_sp_vdf: None,
+            infused_challenge_chain_ip_vdf: None,
+            challenge_chain_ip_vdf: vdf_info(),
+            reward_chain_ip_vdf: vdf_info(),
+            reward_chain_sp_vdf: None,
+            height: 0,
+            signage_point_index: 0,
+            total_iters: 1000,
+            weight: MAINNET.difficulty_starting as u128,
+            is_transaction_block: true,
+            proof_of_space: ProofOfSpace {
+                challenge: MAINNET.genesis_challenge.clone(),
+                pool_contract_puzzle_hash: None,
+                plot_public_key: Bytes48::new(vec![0u8; 48]),
+                pool_public_key: None,
+                proof: UnsizedBytes::new(Vec::new()),
+                size: 32,
+            },
+        };
+        let block = BlockParts {
+            header_hash: zeros,
+            foliage: &foliage,
+            foliage_transaction_block: &None,
+            reward_chain_block: &reward_chain_block,
+            finished_sub_slots: &Vec::new(),
+            transactions_info: &None,
+        };
+        let blocks = BlockCache::new();
+        for can_finish_soon in [false, true] {
+            assert!(
+                next_sub_epoch_summary(&MAINNET, &blocks, 1000, &block, can_finish_soon)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+}
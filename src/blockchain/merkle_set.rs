@@ -0,0 +1,420 @@
+use crate::blockchain::coin::Coin;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
+use std::collections::HashMap;
+use std::error::Error;
+
+const EMPTY: u8 = 0;
+const TERMINAL: u8 = 1;
+const MIDDLE: u8 = 2;
+const TRUNCATED: u8 = 3;
+const BLANK: [u8; 32] = [0; 32];
+
+fn hashdown(left_type: u8, right_type: u8, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut to_hash: Vec<u8> = vec![0u8; 30];
+    to_hash.push(left_type);
+    to_hash.push(right_type);
+    to_hash.extend(left);
+    to_hash.extend(right);
+    let mut rtn = [0u8; 32];
+    rtn.copy_from_slice(&hash_256(to_hash));
+    rtn
+}
+
+fn get_bit(bytes: &[u8; 32], position: usize) -> u8 {
+    (bytes[position / 8] >> (7 - (position % 8))) & 1
+}
+
+fn to_array(bytes: &Bytes32) -> Result<[u8; 32], Box<dyn Error>> {
+    let mut rtn = [0u8; 32];
+    if bytes.bytes.len() != 32 {
+        return Err(format!("Expected 32 bytes, found {}", bytes.bytes.len()).into());
+    }
+    rtn.copy_from_slice(&bytes.bytes);
+    Ok(rtn)
+}
+
+enum MerkleNode {
+    Empty,
+    Terminal([u8; 32]),
+    Middle {
+        children: Box<(MerkleNode, MerkleNode)>,
+        hash: [u8; 32],
+        double: bool,
+    },
+    Truncated([u8; 32]),
+}
+impl MerkleNode {
+    fn middle(left: MerkleNode, right: MerkleNode) -> Result<Self, Box<dyn Error>> {
+        let double = if left.is_empty() {
+            right.is_double()
+        } else if right.is_empty() {
+            left.is_double()
+        } else {
+            left.is_terminal() && right.is_terminal()
+        };
+        let hash = if left.is_empty() && right.is_double() {
+            right.get_hash()
+        } else if right.is_empty() && left.is_double() {
+            left.get_hash()
+        } else {
+            if left.is_empty() && (right.is_empty() || right.is_terminal()) {
+                return Err("Invalid merkle set node".into());
+            }
+            if right.is_empty() && left.is_terminal() {
+                return Err("Invalid merkle set node".into());
+            }
+            if let (MerkleNode::Terminal(left_hash), MerkleNode::Terminal(right_hash)) =
+                (&left, &right)
+            {
+                if left_hash >= right_hash {
+                    return Err("Invalid merkle set node ordering".into());
+                }
+            }
+            hashdown(
+                left.get_type(),
+                right.get_type(),
+                &left.get_hash(),
+                &right.get_hash(),
+            )
+        };
+        Ok(MerkleNode::Middle {
+            children: Box::new((left, right)),
+            hash,
+            double,
+        })
+    }
+
+    fn get_type(&self) -> u8 {
+        match self {
+            MerkleNode::Empty => EMPTY,
+            MerkleNode::Terminal(_) => TERMINAL,
+            MerkleNode::Middle { .. } | MerkleNode::Truncated(_) => MIDDLE,
+        }
+    }
+
+    fn get_hash(&self) -> [u8; 32] {
+        match self {
+            MerkleNode::Empty => BLANK,
+            MerkleNode::Terminal(hash) => *hash,
+            MerkleNode::Middle { hash, .. } => *hash,
+            MerkleNode::Truncated(hash) => *hash,
+        }
+    }
+
+    fn get_root(&self) -> [u8; 32] {
+        //A set with a single leaf hashes it with the terminal prefix, chia_rs does the same
+        match self {
+            MerkleNode::Terminal(hash) => {
+                let mut rtn = [0u8; 32];
+                rtn.copy_from_slice(&hash_256([&[TERMINAL], &hash[..]].concat()));
+                rtn
+            }
+            _ => self.get_hash(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, MerkleNode::Empty)
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(self, MerkleNode::Terminal(_))
+    }
+
+    fn is_double(&self) -> bool {
+        match self {
+            MerkleNode::Middle { double, .. } => *double,
+            _ => false,
+        }
+    }
+
+    fn is_included(
+        &self,
+        to_check: &[u8; 32],
+        depth: usize,
+        proof: &mut Vec<u8>,
+    ) -> Result<bool, Box<dyn Error>> {
+        match self {
+            MerkleNode::Empty => {
+                proof.push(EMPTY);
+                Ok(false)
+            }
+            MerkleNode::Terminal(hash) => {
+                proof.push(TERMINAL);
+                proof.extend(hash);
+                Ok(hash == to_check)
+            }
+            MerkleNode::Middle { children, .. } => {
+                if depth >= 256 {
+                    return Err("Merkle set proof too deep".into());
+                }
+                proof.push(MIDDLE);
+                let (left, right) = children.as_ref();
+                if get_bit(to_check, depth) == 0 {
+                    let rtn = left.is_included(to_check, depth + 1, proof)?;
+                    right.other_included(to_check, depth + 1, proof, !left.is_empty())?;
+                    Ok(rtn)
+                } else {
+                    left.other_included(to_check, depth + 1, proof, !right.is_empty())?;
+                    right.is_included(to_check, depth + 1, proof)
+                }
+            }
+            MerkleNode::Truncated(_) => Err("Can not check inclusion in a truncated node".into()),
+        }
+    }
+
+    fn other_included(
+        &self,
+        to_check: &[u8; 32],
+        depth: usize,
+        proof: &mut Vec<u8>,
+        collapse: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            MerkleNode::Empty => proof.push(EMPTY),
+            MerkleNode::Terminal(hash) => {
+                proof.push(TERMINAL);
+                proof.extend(hash);
+            }
+            MerkleNode::Middle { hash, .. } | MerkleNode::Truncated(hash) => {
+                if collapse || !self.is_double() {
+                    proof.push(TRUNCATED);
+                    proof.extend(hash);
+                } else {
+                    self.is_included(to_check, depth, proof)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn build_node(leaves: &[[u8; 32]], depth: usize) -> Result<MerkleNode, Box<dyn Error>> {
+    match leaves.len() {
+        0 => Ok(MerkleNode::Empty),
+        1 => Ok(MerkleNode::Terminal(leaves[0])),
+        _ => {
+            if depth >= 256 {
+                return Err("Duplicate leaves in merkle set".into());
+            }
+            //Leaves are sorted so everything with a 0 bit at this depth comes first
+            let split = leaves
+                .iter()
+                .position(|leaf| get_bit(leaf, depth) == 1)
+                .unwrap_or(leaves.len());
+            MerkleNode::middle(
+                build_node(&leaves[..split], depth + 1)?,
+                build_node(&leaves[split..], depth + 1)?,
+            )
+        }
+    }
+}
+
+fn deserialize_node(
+    proof: &[u8],
+    position: usize,
+    depth: usize,
+) -> Result<(MerkleNode, usize), Box<dyn Error>> {
+    if depth > 256 {
+        return Err("Merkle set proof too deep".into());
+    }
+    let node_type = *proof.get(position).ok_or("Merkle set proof too short")?;
+    let read_hash = |start: usize| -> Result<[u8; 32], Box<dyn Error>> {
+        let bytes = proof
+            .get(start..start + 32)
+            .ok_or("Merkle set proof too short")?;
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(bytes);
+        Ok(hash)
+    };
+    match node_type {
+        EMPTY => Ok((MerkleNode::Empty, position + 1)),
+        TERMINAL => Ok((
+            MerkleNode::Terminal(read_hash(position + 1)?),
+            position + 33,
+        )),
+        TRUNCATED => Ok((
+            MerkleNode::Truncated(read_hash(position + 1)?),
+            position + 33,
+        )),
+        MIDDLE => {
+            let (left, position) = deserialize_node(proof, position + 1, depth + 1)?;
+            let (right, position) = deserialize_node(proof, position, depth + 1)?;
+            Ok((MerkleNode::middle(left, right)?, position))
+        }
+        _ => Err(format!("Invalid merkle set proof node type {}", node_type).into()),
+    }
+}
+
+pub struct MerkleSet {
+    root: MerkleNode,
+}
+impl MerkleSet {
+    pub fn new(leaves: &[Bytes32]) -> Result<Self, Box<dyn Error>> {
+        let mut sorted: Vec<[u8; 32]> = Vec::new();
+        for leaf in leaves {
+            sorted.push(to_array(leaf)?);
+        }
+        sorted.sort();
+        sorted.dedup();
+        Ok(MerkleSet {
+            root: build_node(&sorted, 0)?,
+        })
+    }
+
+    pub fn get_root(&self) -> Bytes32 {
+        Bytes32::new(self.root.get_root().to_vec())
+    }
+
+    pub fn is_included(&self, leaf: &Bytes32) -> Result<(bool, Vec<u8>), Box<dyn Error>> {
+        let mut proof: Vec<u8> = Vec::new();
+        let included = self.root.is_included(&to_array(leaf)?, 0, &mut proof)?;
+        Ok((included, proof))
+    }
+
+    pub fn get_inclusion_proof(&self, leaf: &Bytes32) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self.is_included(leaf)? {
+            (true, proof) => Ok(proof),
+            (false, _) => Err(format!("{} is not in the merkle set", leaf).into()),
+        }
+    }
+
+    pub fn get_exclusion_proof(&self, leaf: &Bytes32) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self.is_included(leaf)? {
+            (false, proof) => Ok(proof),
+            (true, _) => Err(format!("{} is in the merkle set", leaf).into()),
+        }
+    }
+}
+
+fn confirm_proof(root: &Bytes32, leaf: &Bytes32, proof: &[u8], expected: bool) -> bool {
+    let check = || -> Result<bool, Box<dyn Error>> {
+        let (node, position) = deserialize_node(proof, 0, 0)?;
+        if position != proof.len() {
+            return Err("Trailing bytes in merkle set proof".into());
+        }
+        if node.get_root().to_vec() != root.bytes {
+            return Ok(false);
+        }
+        let mut junk: Vec<u8> = Vec::new();
+        Ok(node.is_included(&to_array(leaf)?, 0, &mut junk)? == expected)
+    };
+    check().unwrap_or(false)
+}
+
+pub fn confirm_included(root: &Bytes32, leaf: &Bytes32, proof: &[u8]) -> bool {
+    confirm_proof(root, leaf, proof, true)
+}
+
+pub fn confirm_not_included(root: &Bytes32, leaf: &Bytes32, proof: &[u8]) -> bool {
+    confirm_proof(root, leaf, proof, false)
+}
+
+pub fn hash_coin_ids(coin_ids: &[Bytes32]) -> Bytes32 {
+    if coin_ids.len() == 1 {
+        return Bytes32::new(hash_256(coin_ids[0].to_bytes()));
+    }
+    let mut sorted: Vec<Vec<u8>> = coin_ids.iter().map(|coin_id| coin_id.to_bytes()).collect();
+    sorted.sort_by(|a, b| b.cmp(a));
+    Bytes32::new(hash_256(sorted.concat()))
+}
+
+pub fn compute_additions_root(additions: &[Coin]) -> Result<Bytes32, Box<dyn Error>> {
+    //Each puzzle hash is added along with the hash of the ids of all coins created with it
+    let mut puzzle_hashes: Vec<Bytes32> = Vec::new();
+    let mut coin_ids_by_puzzle_hash: HashMap<Bytes32, Vec<Bytes32>> = HashMap::new();
+    for coin in additions {
+        match coin_ids_by_puzzle_hash.get_mut(&coin.puzzle_hash) {
+            Some(coin_ids) => coin_ids.push(coin.name()),
+            None => {
+                puzzle_hashes.push(coin.puzzle_hash.clone());
+                coin_ids_by_puzzle_hash.insert(coin.puzzle_hash.clone(), vec![coin.name()]);
+            }
+        }
+    }
+    let mut leaves: Vec<Bytes32> = Vec::new();
+    for puzzle_hash in puzzle_hashes {
+        leaves.push(hash_coin_ids(&coin_ids_by_puzzle_hash[&puzzle_hash]));
+        leaves.push(puzzle_hash);
+    }
+    Ok(MerkleSet::new(&leaves)?.get_root())
+}
+
+pub fn compute_removals_root(removals: &[Bytes32]) -> Result<Bytes32, Box<dyn Error>> {
+    Ok(MerkleSet::new(removals)?.get_root())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(first: u8, last: u8) -> Bytes32 {
+        let mut bytes = vec![0u8; 32];
+        bytes[0] = first;
+        bytes[31] = last;
+        Bytes32::new(bytes)
+    }
+
+    fn root_hex(leaves: &[Bytes32]) -> String {
+        hex::encode(MerkleSet::new(leaves).unwrap().get_root().to_bytes())
+    }
+
+    #[test]
+    fn test_known_roots() {
+        //sha256(0x00 * 30 + left_type + right_type + left + right) at every middle node
+        let a = leaf(0x00, 0x01);
+        let b = leaf(0x40, 0x00);
+        let c = leaf(0x80, 0x00);
+        let d = leaf(0x20, 0x00);
+        assert_eq!(root_hex(&[]), hex::encode(BLANK));
+        assert_eq!(
+            root_hex(&[c.clone(), a.clone()]),
+            "f7e450facb3c28a7571d721109d47c74a5d0d2e82839d947407008c1acd9b384"
+        );
+        assert_eq!(
+            root_hex(&[a.clone(), b, c]),
+            "76edfd311f427cf66d83f9e0dcab949a172ec4b48a5ff314f8a27912d7f3a4af"
+        );
+        //Both leaves share their first two bits so the single child middle nodes collapse
+        assert_eq!(
+            root_hex(&[a, d]),
+            "db9483b71f05e9c58b92a9da539b074744206d271bef34caf66bb1fdd4d758ea"
+        );
+    }
+
+    #[test]
+    fn test_single_leaf_root() {
+        let a = leaf(0x00, 0x01);
+        let set = MerkleSet::new(&[a.clone()]).unwrap();
+        let root = set.get_root();
+        assert_eq!(
+            hex::encode(root.to_bytes()),
+            "2e255099d6d6bee307c8e7075acc78f949897c5f67b53adf60724c814d7b90cb"
+        );
+        assert_eq!(compute_removals_root(&[a.clone()]).unwrap(), root);
+        let proof = set.get_inclusion_proof(&a).unwrap();
+        assert!(confirm_included(&root, &a, &proof));
+        assert!(!confirm_included(&a, &a, &proof));
+        let missing = leaf(0x80, 0x00);
+        let proof = set.get_exclusion_proof(&missing).unwrap();
+        assert!(confirm_not_included(&root, &missing, &proof));
+    }
+
+    #[test]
+    fn test_inclusion_and_exclusion_proofs() {
+        let leaves: Vec<Bytes32> = (0..20u8).map(|i| Bytes32::new(hash_256(vec![i]))).collect();
+        let set = MerkleSet::new(&leaves).unwrap();
+        let root = set.get_root();
+        for leaf in &leaves {
+            let proof = set.get_inclusion_proof(leaf).unwrap();
+            assert!(confirm_included(&root, leaf, &proof));
+            assert!(!confirm_not_included(&root, leaf, &proof));
+        }
+        let missing = Bytes32::new(hash_256(vec![0xFF]));
+        let proof = set.get_exclusion_proof(&missing).unwrap();
+        assert!(confirm_not_included(&root, &missing, &proof));
+        assert!(!confirm_included(&root, &missing, &proof));
+        assert!(!confirm_not_included(&leaves[0], &missing, &proof));
+    }
+}
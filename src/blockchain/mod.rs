@@ -1,25 +1,37 @@
 pub mod announcement;
+pub mod block_cache;
+pub mod block_generator;
+pub mod block_header_validation;
 pub mod block_record;
+pub mod blockchain_interface;
 pub mod blockchain_state;
 pub mod challenge_block_info;
 pub mod challenge_chain_subslot;
+pub mod classgroup;
 pub mod coin;
 pub mod coin_record;
 pub mod coin_spend;
 pub mod condition_opcode;
 pub mod condition_with_args;
 pub mod constants;
+pub mod difficulty_adjustment;
 pub mod foliage;
 pub mod foliage_block_data;
 pub mod foliage_transaction_block;
 pub mod full_block;
+pub mod full_block_to_block_record;
+pub mod header_block;
 pub mod infused_challenge_chain_subslot;
+pub mod make_sub_epoch_summary;
 pub mod mem_pool_item;
+pub mod merkle_set;
 pub mod network_info;
 pub mod npc;
 pub mod npc_result;
 pub mod pending_payment;
+pub mod plot_info;
 pub mod pool_target;
+pub mod pot_iterations;
 pub mod proof_of_space;
 pub mod reward_chain_block;
 pub mod reward_chain_block_unfinished;
@@ -34,10 +46,13 @@ pub mod subslot_proofs;
 pub mod sync;
 pub mod transaction_peer;
 pub mod transaction_record;
+pub mod transactions_filter;
 pub mod transactions_info;
 pub mod tx_status;
 pub mod unfinished_block;
 pub mod utils;
+pub mod validation_error;
+pub mod vdf;
 pub mod vdf_info;
 pub mod vdf_output;
 pub mod vdf_proof;
@@ -45,3 +60,5 @@ pub mod wallet_balance;
 pub mod wallet_info;
 pub mod wallet_sync;
 pub mod wallet_type;
+pub mod weight_proof;
+pub mod weight_proof_validation;
@@ -1,7 +1,7 @@
 use crate::blockchain::sized_bytes::Bytes32;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct PendingPayment {
     pub puzzle_hash: Bytes32,
     pub amount: u64,
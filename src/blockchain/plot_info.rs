@@ -0,0 +1,118 @@
+use crate::blockchain::proof_of_space::{
+    calculate_plot_id_public_key, calculate_plot_id_puzzle_hash, generate_plot_public_key,
+};
+use crate::blockchain::sized_bytes::{Bytes32, Bytes48};
+use crate::clvm::bls_bindings::{derive_child_sk, secret_key_to_public_key};
+use druid_garden_chiapos::chiapos::prover::DiskProver;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+
+const POOL_PUBLIC_KEY_MEMO_LENGTH: usize = 48 + 48 + 32;
+const POOL_CONTRACT_MEMO_LENGTH: usize = 32 + 48 + 32;
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PlotMemo {
+    pub pool_public_key: Option<Bytes48>,
+    pub pool_contract_puzzle_hash: Option<Bytes32>,
+    pub farmer_public_key: Bytes48,
+    pub local_master_secret_key: Bytes32,
+}
+impl PlotMemo {
+    pub fn from_bytes(memo: &[u8]) -> Result<Self, Box<dyn Error>> {
+        match memo.len() {
+            POOL_PUBLIC_KEY_MEMO_LENGTH => Ok(PlotMemo {
+                pool_public_key: Some(memo[0..48].to_vec().into()),
+                pool_contract_puzzle_hash: None,
+                farmer_public_key: memo[48..96].to_vec().into(),
+                local_master_secret_key: memo[96..128].to_vec().into(),
+            }),
+            POOL_CONTRACT_MEMO_LENGTH => Ok(PlotMemo {
+                pool_public_key: None,
+                pool_contract_puzzle_hash: Some(memo[0..32].to_vec().into()),
+                farmer_public_key: memo[32..80].to_vec().into(),
+                local_master_secret_key: memo[80..112].to_vec().into(),
+            }),
+            _ => Err(format!("Invalid Plot Memo Length: {}", memo.len()).into()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PlotHeader {
+    pub id: Bytes32,
+    pub k: u8,
+    pub format_description: String,
+    pub memo: PlotMemo,
+}
+impl PlotHeader {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let prover = DiskProver::new(path)?;
+        Ok(PlotHeader {
+            id: prover.get_id().to_vec().into(),
+            k: prover.get_size(),
+            format_description: prover.get_format().to_string(),
+            memo: PlotMemo::from_bytes(prover.get_memo())?,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PlotInfo {
+    pub header: PlotHeader,
+    pub local_secret_key: Bytes32,
+    pub local_public_key: Bytes48,
+    pub plot_public_key: Bytes48,
+}
+impl PlotInfo {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        PlotInfo::from_header(PlotHeader::from_file(path)?)
+    }
+
+    pub fn from_header(header: PlotHeader) -> Result<Self, Box<dyn Error>> {
+        let local_secret_key = master_sk_to_local_sk(&header.memo.local_master_secret_key)?;
+        let local_public_key = secret_key_to_public_key(&local_secret_key)?;
+        let plot_public_key = generate_plot_public_key(
+            &local_public_key,
+            &header.memo.farmer_public_key,
+            header.memo.pool_contract_puzzle_hash.is_some(),
+        )?;
+        let plot_id = match (
+            &header.memo.pool_public_key,
+            &header.memo.pool_contract_puzzle_hash,
+        ) {
+            (Some(pool_public_key), None) => {
+                calculate_plot_id_public_key(pool_public_key, &plot_public_key)
+            }
+            (None, Some(pool_contract_puzzle_hash)) => {
+                calculate_plot_id_puzzle_hash(pool_contract_puzzle_hash, &plot_public_key)
+            }
+            _ => return Err("Plot Memo must have exactly one pool key or puzzle hash".into()),
+        };
+        if plot_id != header.id {
+            return Err(format!(
+                "Plot Id Mismatch, header: {} calculated: {}",
+                header.id, plot_id
+            )
+            .into());
+        }
+        Ok(PlotInfo {
+            header,
+            local_secret_key,
+            local_public_key,
+            plot_public_key,
+        })
+    }
+
+    pub fn plot_id(&self) -> &Bytes32 {
+        &self.header.id
+    }
+}
+
+pub fn master_sk_to_local_sk(master: &Bytes32) -> Result<Bytes32, Box<dyn Error>> {
+    let mut sk = master.clone();
+    for index in [12381u32, 8444, 3, 0] {
+        sk = derive_child_sk(&sk, index)?;
+    }
+    Ok(sk)
+}
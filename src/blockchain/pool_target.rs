@@ -1,4 +1,4 @@
-use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -6,3 +6,11 @@ pub struct PoolTarget {
     pub max_height: u32,
     pub puzzle_hash: Bytes32,
 }
+impl PoolTarget {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.puzzle_hash.to_bytes());
+        bytes.extend(self.max_height.to_be_bytes());
+        bytes
+    }
+}
@@ -0,0 +1,91 @@
+use crate::blockchain::constants::Constants;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::error::Error;
+
+pub fn is_overflow_block(
+    constants: &Constants,
+    signage_point_index: u8,
+) -> Result<bool, Box<dyn Error>> {
+    if signage_point_index as u32 >= constants.num_sps_sub_slot {
+        return Err("SP index too high".into());
+    }
+    Ok(signage_point_index as u32
+        >= constants.num_sps_sub_slot - constants.num_sp_intervals_extra as u32)
+}
+
+pub fn calculate_sp_interval_iters(
+    constants: &Constants,
+    sub_slot_iters: u64,
+) -> Result<u64, Box<dyn Error>> {
+    if sub_slot_iters % constants.num_sps_sub_slot as u64 != 0 {
+        return Err("Sub Slot Iters not divisible by NUM_SPS_SUB_SLOT".into());
+    }
+    Ok(sub_slot_iters / constants.num_sps_sub_slot as u64)
+}
+
+pub fn calculate_sp_iters(
+    constants: &Constants,
+    sub_slot_iters: u64,
+    signage_point_index: u8,
+) -> Result<u64, Box<dyn Error>> {
+    if signage_point_index as u32 >= constants.num_sps_sub_slot {
+        return Err("SP index too high".into());
+    }
+    Ok(calculate_sp_interval_iters(constants, sub_slot_iters)? * signage_point_index as u64)
+}
+
+pub fn calculate_ip_iters(
+    constants: &Constants,
+    sub_slot_iters: u64,
+    signage_point_index: u8,
+    required_iters: u64,
+) -> Result<u64, Box<dyn Error>> {
+    let sp_iters = calculate_sp_iters(constants, sub_slot_iters, signage_point_index)?;
+    let sp_interval_iters = calculate_sp_interval_iters(constants, sub_slot_iters)?;
+    if sp_iters % sp_interval_iters != 0 || sp_iters >= sub_slot_iters {
+        return Err(format!(
+            "Invalid sp iters {} for this ssi {}",
+            sp_iters, sub_slot_iters
+        )
+        .into());
+    }
+    if required_iters >= sp_interval_iters || required_iters == 0 {
+        return Err(format!(
+            "Required iters {} is not below the sp interval iters {} {} or not >0.",
+            required_iters, sp_interval_iters, sub_slot_iters
+        )
+        .into());
+    }
+    Ok(
+        (sp_iters + constants.num_sp_intervals_extra as u64 * sp_interval_iters + required_iters)
+            % sub_slot_iters,
+    )
+}
+
+pub fn expected_plot_size(k: u8) -> BigUint {
+    BigUint::from(2u32 * k as u32 + 1) << (k as usize - 1)
+}
+
+pub fn calculate_iterations_quality(
+    difficulty_constant_factor: u128,
+    quality_string: &Bytes32,
+    size: u8,
+    difficulty: u64,
+    cc_sp_output_hash: &Bytes32,
+) -> u64 {
+    let mut to_hash: Vec<u8> = Vec::new();
+    to_hash.extend(quality_string.to_bytes());
+    to_hash.extend(cc_sp_output_hash.to_bytes());
+    let sp_quality_string = BigUint::from_bytes_be(hash_256(to_hash).as_slice());
+    let iters =
+        BigUint::from(difficulty) * BigUint::from(difficulty_constant_factor) * sp_quality_string
+            / ((BigUint::from(1u8) << 256) * expected_plot_size(size));
+    match iters.to_u64() {
+        Some(0) => 1,
+        Some(iters) => iters,
+        None => u64::MAX,
+    }
+}
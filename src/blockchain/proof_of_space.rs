@@ -1,4 +1,5 @@
 use crate::blockchain::sized_bytes::{Bytes32, Bytes48, SizedBytes, UnsizedBytes};
+use crate::clvm::bls_bindings::{aggregate_public_keys, key_gen, secret_key_to_public_key};
 use druid_garden_chiapos::chiapos::verifier::validate_proof;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -16,20 +17,18 @@ pub struct ProofOfSpace {
     pub size: u8,
 }
 impl ProofOfSpace {
-    fn get_plot_id(&self) -> Result<Option<Bytes32>, Box<dyn Error>> {
-        if self.pool_public_key.is_none() || self.pool_contract_puzzle_hash.is_none() {
-            if self.pool_public_key.is_none() && self.pool_contract_puzzle_hash.is_some() {
-                return Ok(Some(self.calculate_plot_id_puzzle_hash(
-                    &self.pool_contract_puzzle_hash.as_ref().unwrap(),
+    pub fn get_plot_id(&self) -> Result<Option<Bytes32>, Box<dyn Error>> {
+        match (&self.pool_public_key, &self.pool_contract_puzzle_hash) {
+            (Some(pool_public_key), None) => Ok(Some(
+                self.calculate_plot_id_public_key(pool_public_key, &self.plot_public_key)?,
+            )),
+            (None, Some(pool_contract_puzzle_hash)) => {
+                Ok(Some(self.calculate_plot_id_puzzle_hash(
+                    pool_contract_puzzle_hash,
                     &self.plot_public_key,
-                )?));
+                )?))
             }
-            return Ok(Some(self.calculate_plot_id_public_key(
-                &self.pool_public_key.as_ref().unwrap(),
-                &self.plot_public_key,
-            )?));
-        } else {
-            return Ok(None);
+            _ => Ok(None),
         }
     }
 
@@ -88,12 +87,10 @@ impl ProofOfSpace {
         pool_public_key: &Bytes48,
         plot_public_key: &Bytes48,
     ) -> Result<Bytes32, Box<dyn Error>> {
-        let mut to_hash: Vec<u8> = Vec::new();
-        to_hash.extend(pool_public_key.to_bytes());
-        to_hash.extend(plot_public_key.to_bytes());
-        let mut hasher: Sha256 = Sha256::new();
-        hasher.update(to_hash);
-        Ok(Bytes32::new(hasher.finalize().to_vec()))
+        Ok(calculate_plot_id_public_key(
+            pool_public_key,
+            plot_public_key,
+        ))
     }
 
     pub fn calculate_plot_id_puzzle_hash(
@@ -101,12 +98,10 @@ impl ProofOfSpace {
         pool_contract_puzzle_hash: &Bytes32,
         plot_public_key: &Bytes48,
     ) -> Result<Bytes32, Box<dyn Error>> {
-        let mut to_hash: Vec<u8> = Vec::new();
-        to_hash.extend(pool_contract_puzzle_hash.to_bytes());
-        to_hash.extend(plot_public_key.to_bytes());
-        let mut hasher: Sha256 = Sha256::new();
-        hasher.update(to_hash);
-        Ok(Bytes32::new(hasher.finalize().to_vec()))
+        Ok(calculate_plot_id_puzzle_hash(
+            pool_contract_puzzle_hash,
+            plot_public_key,
+        ))
     }
 
     pub fn passes_plot_filter(
@@ -121,7 +116,7 @@ impl ProofOfSpace {
             .calculate_plot_filter_input(plot_id, challenge_hash, signage_point)?
             .to_bytes()
         {
-            for i in 7..=0 {
+            for i in (0..8).rev() {
                 filter[index] = (b >> i & 1) == 1;
                 index += 1;
             }
@@ -161,32 +156,88 @@ impl ProofOfSpace {
         Ok(Bytes32::new(hasher.finalize().to_vec()))
     }
 
-    pub fn hash(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        let mut to_hash: Vec<u8> = Vec::new();
-        to_hash.extend(&self.challenge.clone().to_bytes());
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.challenge.to_bytes());
         match &self.pool_public_key {
             Some(public_key) => {
-                to_hash.push(1u8);
-                to_hash.extend(public_key.to_bytes());
+                bytes.push(1u8);
+                bytes.extend(public_key.to_bytes());
             }
             None => {
-                to_hash.push(0u8);
+                bytes.push(0u8);
             }
         }
         match &self.pool_contract_puzzle_hash {
             Some(contract_hash) => {
-                to_hash.push(1u8);
-                to_hash.extend(contract_hash.to_bytes());
+                bytes.push(1u8);
+                bytes.extend(contract_hash.to_bytes());
             }
             None => {
-                to_hash.push(0u8);
+                bytes.push(0u8);
             }
         }
-        to_hash.extend(&self.plot_public_key.to_bytes());
-        to_hash.push(self.size);
-        to_hash.extend(&self.proof.to_bytes());
+        bytes.extend(&self.plot_public_key.to_bytes());
+        bytes.push(self.size);
+        bytes.extend((self.proof.bytes.len() as u32).to_be_bytes());
+        bytes.extend(&self.proof.to_bytes());
+        bytes
+    }
+
+    pub fn hash(&self) -> Result<Vec<u8>, Box<dyn Error>> {
         let mut hasher: Sha256 = Sha256::new();
-        hasher.update(to_hash);
+        hasher.update(self.to_bytes());
         Ok(hasher.finalize().to_vec())
     }
 }
+
+pub fn calculate_plot_id_public_key(
+    pool_public_key: &Bytes48,
+    plot_public_key: &Bytes48,
+) -> Bytes32 {
+    let mut to_hash: Vec<u8> = Vec::new();
+    to_hash.extend(pool_public_key.to_bytes());
+    to_hash.extend(plot_public_key.to_bytes());
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(to_hash);
+    Bytes32::new(hasher.finalize().to_vec())
+}
+
+pub fn calculate_plot_id_puzzle_hash(
+    pool_contract_puzzle_hash: &Bytes32,
+    plot_public_key: &Bytes48,
+) -> Bytes32 {
+    let mut to_hash: Vec<u8> = Vec::new();
+    to_hash.extend(pool_contract_puzzle_hash.to_bytes());
+    to_hash.extend(plot_public_key.to_bytes());
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(to_hash);
+    Bytes32::new(hasher.finalize().to_vec())
+}
+
+pub fn generate_taproot_sk(
+    local_pk: &Bytes48,
+    farmer_pk: &Bytes48,
+) -> Result<Bytes32, Box<dyn Error>> {
+    let combined = aggregate_public_keys(&vec![local_pk, farmer_pk])?;
+    let mut taproot_message: Vec<u8> = Vec::new();
+    taproot_message.extend(combined.to_bytes());
+    taproot_message.extend(local_pk.to_bytes());
+    taproot_message.extend(farmer_pk.to_bytes());
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(taproot_message);
+    key_gen(hasher.finalize().as_slice())
+}
+
+pub fn generate_plot_public_key(
+    local_pk: &Bytes48,
+    farmer_pk: &Bytes48,
+    include_taproot: bool,
+) -> Result<Bytes48, Box<dyn Error>> {
+    if include_taproot {
+        let taproot_pk = secret_key_to_public_key(&generate_taproot_sk(local_pk, farmer_pk)?)?;
+        aggregate_public_keys(&vec![local_pk, farmer_pk, &taproot_pk])
+    } else {
+        aggregate_public_keys(&vec![local_pk, farmer_pk])
+    }
+}
@@ -1,6 +1,8 @@
 use crate::blockchain::proof_of_space::ProofOfSpace;
-use crate::blockchain::sized_bytes::{Bytes32, Bytes96};
+use crate::blockchain::reward_chain_block_unfinished::RewardChainBlockUnfinished;
+use crate::blockchain::sized_bytes::{Bytes32, Bytes96, SizedBytes};
 use crate::blockchain::vdf_info::VdfInfo;
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -20,3 +22,62 @@ pub struct RewardChainBlock {
     pub is_transaction_block: bool,
     pub proof_of_space: ProofOfSpace,
 }
+impl RewardChainBlock {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn get_unfinished(&self) -> RewardChainBlockUnfinished {
+        RewardChainBlockUnfinished {
+            total_iters: self.total_iters,
+            signage_point_index: self.signage_point_index,
+            pos_ss_cc_challenge_hash: self.pos_ss_cc_challenge_hash.clone(),
+            proof_of_space: self.proof_of_space.clone(),
+            challenge_chain_sp_vdf: self.challenge_chain_sp_vdf.clone(),
+            challenge_chain_sp_signature: self.challenge_chain_sp_signature.clone(),
+            reward_chain_sp_vdf: self.reward_chain_sp_vdf.clone(),
+            reward_chain_sp_signature: self.reward_chain_sp_signature.clone(),
+        }
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(self.weight.to_be_bytes());
+        bytes.extend((self.height as u32).to_be_bytes());
+        bytes.extend(self.total_iters.to_be_bytes());
+        bytes.push(self.signage_point_index);
+        bytes.extend(&self.pos_ss_cc_challenge_hash.to_bytes());
+        bytes.extend(self.proof_of_space.to_bytes());
+        match &self.challenge_chain_sp_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.challenge_chain_sp_signature.to_bytes());
+        bytes.extend(self.challenge_chain_ip_vdf.to_bytes());
+        match &self.reward_chain_sp_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.reward_chain_sp_signature.to_bytes());
+        bytes.extend(self.reward_chain_ip_vdf.to_bytes());
+        match &self.infused_challenge_chain_ip_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.push(self.is_transaction_block as u8);
+        bytes
+    }
+}
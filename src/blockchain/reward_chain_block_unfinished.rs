@@ -1,9 +1,10 @@
 use crate::blockchain::proof_of_space::ProofOfSpace;
-use crate::blockchain::sized_bytes::{Bytes32, Bytes96};
+use crate::blockchain::sized_bytes::{Bytes32, Bytes96, SizedBytes};
 use crate::blockchain::vdf_info::VdfInfo;
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RewardChainBlockUnfinished {
     pub total_iters: u128,
     pub signage_point_index: u8,
@@ -14,3 +15,36 @@ pub struct RewardChainBlockUnfinished {
     pub reward_chain_sp_vdf: Option<VdfInfo>,
     pub reward_chain_sp_signature: Bytes96,
 }
+impl RewardChainBlockUnfinished {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(self.total_iters.to_be_bytes());
+        bytes.push(self.signage_point_index);
+        bytes.extend(&self.pos_ss_cc_challenge_hash.to_bytes());
+        bytes.extend(self.proof_of_space.to_bytes());
+        match &self.challenge_chain_sp_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.challenge_chain_sp_signature.to_bytes());
+        match &self.reward_chain_sp_vdf {
+            Some(vdf) => {
+                bytes.push(1u8);
+                bytes.extend(vdf.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.extend(&self.reward_chain_sp_signature.to_bytes());
+        bytes
+    }
+}
@@ -1,5 +1,6 @@
-use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
 use crate::blockchain::vdf_info::VdfInfo;
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -9,3 +10,24 @@ pub struct RewardChainSubSlot {
     pub infused_challenge_chain_sub_slot_hash: Option<Bytes32>,
     pub deficit: u8,
 }
+impl RewardChainSubSlot {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(self.end_of_slot_vdf.to_bytes());
+        bytes.extend(&self.challenge_chain_sub_slot_hash.to_bytes());
+        match &self.infused_challenge_chain_sub_slot_hash {
+            Some(sub_slot_hash) => {
+                bytes.push(1u8);
+                bytes.extend(&sub_slot_hash.to_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes.push(self.deficit);
+        bytes
+    }
+}
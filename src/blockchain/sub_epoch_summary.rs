@@ -1,7 +1,8 @@
-use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct SubEpochSummary {
     pub prev_subepoch_summary_hash: Bytes32,
     pub reward_chain_hash: Bytes32,
@@ -9,3 +10,33 @@ pub struct SubEpochSummary {
     pub new_difficulty: Option<u64>,
     pub new_sub_slot_iters: Option<u64>,
 }
+impl SubEpochSummary {
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.to_bytes()))
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.prev_subepoch_summary_hash.to_bytes());
+        bytes.extend(&self.reward_chain_hash.to_bytes());
+        bytes.push(self.num_blocks_overflow);
+        match &self.new_difficulty {
+            Some(difficulty) => {
+                bytes.push(1u8);
+                bytes.extend(difficulty.to_be_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        match &self.new_sub_slot_iters {
+            Some(slot_iters) => {
+                bytes.push(1u8);
+                bytes.extend(slot_iters.to_be_bytes());
+            }
+            None => {
+                bytes.push(0u8);
+            }
+        }
+        bytes
+    }
+}
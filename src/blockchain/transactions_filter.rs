@@ -0,0 +1,310 @@
+use crate::blockchain::coin::Coin;
+use crate::blockchain::sized_bytes::{hex_to_bytes, Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
+use std::error::Error;
+
+//chiabip158 builds GCSFilter({0, 0, 20, 1 << 20}), zero siphash keys with P = 20 and M = 2^20
+pub const FILTER_P: u8 = 20;
+pub const FILTER_M: u64 = 1 << 20;
+const SIPHASH_K0: u64 = 0;
+const SIPHASH_K1: u64 = 0;
+
+fn siphash_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+pub fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+    let mut v: [u64; 4] = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        let m = u64::from_le_bytes(word);
+        v[3] ^= m;
+        siphash_round(&mut v);
+        siphash_round(&mut v);
+        v[0] ^= m;
+    }
+    let mut last = [0u8; 8];
+    let remainder = chunks.remainder();
+    last[..remainder.len()].copy_from_slice(remainder);
+    last[7] = data.len() as u8;
+    let b = u64::from_le_bytes(last);
+    v[3] ^= b;
+    siphash_round(&mut v);
+    siphash_round(&mut v);
+    v[0] ^= b;
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        siphash_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn hash_to_range(element: &[u8], range: u64, k0: u64, k1: u64) -> u64 {
+    let hash = siphash24(k0, k1, element);
+    ((hash as u128 * range as u128) >> 64) as u64
+}
+
+fn write_compact_size(size: u64, output: &mut Vec<u8>) {
+    if size < 253 {
+        output.push(size as u8);
+    } else if size <= 0xFFFF {
+        output.push(0xFD);
+        output.extend((size as u16).to_le_bytes());
+    } else if size <= 0xFFFFFFFF {
+        output.push(0xFE);
+        output.extend((size as u32).to_le_bytes());
+    } else {
+        output.push(0xFF);
+        output.extend(size.to_le_bytes());
+    }
+}
+
+fn read_compact_size(bytes: &[u8]) -> Result<(u64, usize), Box<dyn Error>> {
+    let first = *bytes.first().ok_or("Empty transactions filter")?;
+    let width = match first {
+        0xFD => 2,
+        0xFE => 4,
+        0xFF => 8,
+        _ => return Ok((first as u64, 1)),
+    };
+    let size_bytes = bytes
+        .get(1..1 + width)
+        .ok_or("Transactions filter too short")?;
+    let mut le_bytes = [0u8; 8];
+    le_bytes[..width].copy_from_slice(size_bytes);
+    Ok((u64::from_le_bytes(le_bytes), 1 + width))
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u8,
+    offset: u8,
+}
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u8) {
+        for i in (0..bits).rev() {
+            self.buffer |= (((value >> i) & 1) as u8) << (7 - self.offset);
+            self.offset += 1;
+            if self.offset == 8 {
+                self.bytes.push(self.buffer);
+                self.buffer = 0;
+                self.offset = 0;
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.offset != 0 {
+            self.bytes.push(self.buffer);
+            self.buffer = 0;
+            self.offset = 0;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+impl<'a> BitReader<'a> {
+    fn read_bit(&mut self) -> Result<u64, Box<dyn Error>> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or("Transactions filter too short")?;
+        let bit = (byte >> (7 - (self.position % 8))) & 1;
+        self.position += 1;
+        Ok(bit as u64)
+    }
+
+    fn read(&mut self, bits: u8) -> Result<u64, Box<dyn Error>> {
+        let mut rtn: u64 = 0;
+        for _ in 0..bits {
+            rtn = (rtn << 1) | self.read_bit()?;
+        }
+        Ok(rtn)
+    }
+}
+
+fn encode_filter(elements: &[Vec<u8>], k0: u64, k1: u64) -> (Vec<u8>, Vec<u64>) {
+    let mut unique: Vec<&Vec<u8>> = elements.iter().collect();
+    unique.sort();
+    unique.dedup();
+    let range = unique.len() as u64 * FILTER_M;
+    let mut values: Vec<u64> = unique
+        .iter()
+        .map(|element| hash_to_range(element, range, k0, k1))
+        .collect();
+    values.sort_unstable();
+    let mut encoded: Vec<u8> = Vec::new();
+    write_compact_size(unique.len() as u64, &mut encoded);
+    let mut writer = BitWriter {
+        bytes: encoded,
+        buffer: 0,
+        offset: 0,
+    };
+    let mut last_value: u64 = 0;
+    for value in &values {
+        let delta = value - last_value;
+        let mut quotient = delta >> FILTER_P;
+        while quotient > 0 {
+            let ones = quotient.min(64);
+            writer.write(u64::MAX, ones as u8);
+            quotient -= ones;
+        }
+        writer.write(0, 1);
+        writer.write(delta, FILTER_P);
+        last_value = *value;
+    }
+    writer.flush();
+    (writer.bytes, values)
+}
+
+pub struct TransactionsFilter {
+    encoded: Vec<u8>,
+    values: Vec<u64>,
+}
+impl TransactionsFilter {
+    pub fn new(elements: &[Vec<u8>]) -> Self {
+        let (encoded, values) = encode_filter(elements, SIPHASH_K0, SIPHASH_K1);
+        TransactionsFilter { encoded, values }
+    }
+
+    pub fn from_block(additions: &[Coin], removals: &[Bytes32]) -> Self {
+        //Additions are filtered by puzzle hash and removals by coin id
+        let mut elements: Vec<Vec<u8>> = Vec::new();
+        for coin in additions {
+            elements.push(coin.puzzle_hash.to_bytes());
+        }
+        for coin_id in removals {
+            elements.push(coin_id.to_bytes());
+        }
+        TransactionsFilter::new(&elements)
+    }
+
+    pub fn from_bytes(encoded: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let (count, offset) = read_compact_size(encoded)?;
+        let mut reader = BitReader {
+            bytes: &encoded[offset..],
+            position: 0,
+        };
+        let mut values: Vec<u64> = Vec::new();
+        let mut last_value: u64 = 0;
+        for _ in 0..count {
+            let mut quotient: u64 = 0;
+            while reader.read_bit()? == 1 {
+                quotient += 1;
+            }
+            let delta = (quotient << FILTER_P) | reader.read(FILTER_P)?;
+            last_value = last_value
+                .checked_add(delta)
+                .ok_or("Invalid transactions filter value")?;
+            values.push(last_value);
+        }
+        Ok(TransactionsFilter {
+            encoded: encoded.to_vec(),
+            values,
+        })
+    }
+
+    pub fn from_hex(hex: &String) -> Result<Self, Box<dyn Error>> {
+        TransactionsFilter::from_bytes(&hex_to_bytes(hex)?)
+    }
+
+    pub fn encoded(&self) -> &Vec<u8> {
+        &self.encoded
+    }
+
+    pub fn filter_hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.encoded.clone()))
+    }
+
+    pub fn verify(&self, filter_hash: &Bytes32) -> bool {
+        self.filter_hash() == *filter_hash
+    }
+
+    pub fn contains(&self, element: &[u8]) -> bool {
+        if self.values.is_empty() {
+            return false;
+        }
+        let value = hash_to_range(
+            element,
+            self.values.len() as u64 * FILTER_M,
+            SIPHASH_K0,
+            SIPHASH_K1,
+        );
+        self.values.binary_search(&value).is_ok()
+    }
+
+    pub fn match_any(&self, elements: &[Bytes32]) -> bool {
+        elements.iter().any(|element| self.contains(&element.bytes))
+    }
+
+    pub fn matching(&self, elements: &[Bytes32]) -> Vec<Bytes32> {
+        elements
+            .iter()
+            .filter(|element| self.contains(&element.bytes))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_filter() {
+        //Encoded with an independent GCS encoder using chiabip158's parameters
+        let coin = |puzzle_hash: u8| Coin {
+            amount: 1,
+            parent_coin_info: Bytes32::new(vec![0u8; 32]),
+            puzzle_hash: Bytes32::new(hash_256(vec![puzzle_hash])),
+        };
+        let additions = vec![coin(1), coin(2), coin(1)];
+        let removals = vec![
+            Bytes32::new(hash_256(vec![10])),
+            Bytes32::new(hash_256(vec![11])),
+        ];
+        let filter = TransactionsFilter::from_block(&additions, &removals);
+        assert_eq!(hex::encode(filter.encoded()), "044b95909d59b0db28892b20");
+        assert_eq!(
+            hex::encode(filter.filter_hash().to_bytes()),
+            "e9f9506df905dbf97fee509da11897da8979d36970fe282e8959bb4138bb2842"
+        );
+        assert!(filter.contains(&additions[1].puzzle_hash.to_bytes()));
+        assert!(filter.contains(&removals[0].to_bytes()));
+    }
+
+    #[test]
+    fn test_filter_round_trip() {
+        let elements: Vec<Vec<u8>> = (0..50u8).map(|i| hash_256(vec![i])).collect();
+        let filter = TransactionsFilter::new(&elements);
+        let decoded = TransactionsFilter::from_bytes(filter.encoded()).unwrap();
+        assert!(decoded.verify(&filter.filter_hash()));
+        for element in &elements {
+            assert!(decoded.contains(element));
+        }
+        assert!(!TransactionsFilter::new(&[]).contains(&elements[0]));
+    }
+}
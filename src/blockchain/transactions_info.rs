@@ -1,12 +1,37 @@
 use crate::blockchain::coin::Coin;
+use crate::blockchain::sized_bytes::{hex_to_bytes, Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TransactionsInfo {
     pub aggregated_signature: String,
-    pub generator_refs_root: String,
-    pub generator_root: String,
+    pub generator_refs_root: Bytes32,
+    pub generator_root: Bytes32,
     pub cost: u64,
     pub fees: u64,
     pub reward_claims_incorporated: Vec<Coin>,
 }
+impl TransactionsInfo {
+    pub fn hash(&self) -> Result<Bytes32, Box<dyn Error>> {
+        Ok(Bytes32::new(hash_256(self.to_bytes()?)))
+    }
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let signature = hex_to_bytes(&self.aggregated_signature)?;
+        if signature.len() != 96 {
+            return Err("Invalid aggregated signature length".into());
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.generator_root.to_bytes());
+        bytes.extend(&self.generator_refs_root.to_bytes());
+        bytes.extend(signature);
+        bytes.extend(self.fees.to_be_bytes());
+        bytes.extend(self.cost.to_be_bytes());
+        bytes.extend((self.reward_claims_incorporated.len() as u32).to_be_bytes());
+        for coin in &self.reward_claims_incorporated {
+            bytes.extend(coin.to_bytes());
+        }
+        Ok(bytes)
+    }
+}
@@ -1,9 +1,11 @@
 use crate::blockchain::foliage::Foliage;
 use crate::blockchain::foliage_transaction_block::FoliageTransactionBlock;
 use crate::blockchain::reward_chain_block_unfinished::RewardChainBlockUnfinished;
+use crate::blockchain::sized_bytes::Bytes32;
 use crate::blockchain::subslot_bundle::SubSlotBundle;
 use crate::blockchain::transactions_info::TransactionsInfo;
 use crate::blockchain::vdf_proof::VdfProof;
+use crate::clvm::serialized_program::SerializedProgram;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -16,6 +18,17 @@ pub struct UnfinishedBlock {
     pub finished_sub_slots: Vec<SubSlotBundle>,
     pub reward_chain_block: RewardChainBlockUnfinished,
     pub transactions_info: Option<TransactionsInfo>,
-    pub transactions_generator: Option<String>,
+    pub transactions_generator: Option<SerializedProgram>,
     pub transactions_generator_ref_list: Option<Vec<u32>>,
 }
+impl UnfinishedBlock {
+    pub fn partial_hash(&self) -> Bytes32 {
+        self.reward_chain_block.hash()
+    }
+    pub fn prev_header_hash(&self) -> Bytes32 {
+        self.foliage.prev_block_hash.clone()
+    }
+    pub fn is_transaction_block(&self) -> bool {
+        self.foliage.foliage_transaction_block_hash.is_some()
+    }
+}
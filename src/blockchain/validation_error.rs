@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum ValidationErrorCode {
+    InvalidBlockData,
+    InvalidPrevBlockHash,
+    InvalidHeight,
+    TooManyBlocks,
+    InvalidWeight,
+    InvalidTotalIters,
+    InvalidPrevChallengeSlotHash,
+    InvalidPrevRewardSlotHash,
+    InvalidChallengeSlotHashRc,
+    InvalidIccHashCc,
+    InvalidIccHashRc,
+    InvalidCcEosVdf,
+    InvalidIccEosVdf,
+    InvalidRcEosVdf,
+    InvalidDeficit,
+    InvalidNewSubSlotIters,
+    InvalidNewDifficulty,
+    InvalidSubEpochSummaryHash,
+    InvalidSubEpochSummary,
+    NoOverflowsInFirstSubSlotNewEpoch,
+    InvalidSpIndex,
+    InvalidPospaceChallenge,
+    InvalidPospace,
+    InvalidRequiredIters,
+    InvalidCcSpVdf,
+    InvalidRcSpVdf,
+    InvalidCcIpVdf,
+    InvalidRcIpVdf,
+    InvalidIccVdf,
+    InvalidCcSignature,
+    InvalidRcSignature,
+    InvalidPlotSignature,
+    InvalidPoolSignature,
+    InvalidPoolTarget,
+    InvalidPrefarm,
+    InvalidUrsbHash,
+    InvalidRewardBlockHash,
+    InvalidIsTransactionBlock,
+    InvalidFoliageBlockHash,
+    InvalidPrevTransactionBlockHash,
+    TimestampTooFarInPast,
+    TimestampTooFarInFuture,
+    InvalidTransactionsInfoHash,
+    InvalidTransactionsFilterHash,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct ValidationError {
+    pub error_code: ValidationErrorCode,
+    pub error_message: String,
+}
+impl ValidationError {
+    pub fn new(error_code: ValidationErrorCode, error_message: String) -> Self {
+        ValidationError {
+            error_code,
+            error_message,
+        }
+    }
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.error_code, self.error_message)
+    }
+}
+impl Error for ValidationError {}
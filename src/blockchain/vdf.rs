@@ -0,0 +1,113 @@
+use crate::blockchain::classgroup::{create_discriminant, verify_n_wesolowski};
+use crate::blockchain::constants::Constants;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::blockchain::vdf_info::VdfInfo;
+use crate::blockchain::vdf_output::VdfOutput;
+use crate::blockchain::vdf_proof::VdfProof;
+
+pub fn verify_vdf(
+    constants: &Constants,
+    challenge: &Bytes32,
+    input_el: &VdfOutput,
+    vdf_info: &VdfInfo,
+    proof: &VdfProof,
+) -> bool {
+    if vdf_info.challenge != *challenge {
+        return false;
+    }
+    if proof.witness_type as usize + 1 > constants.max_vdf_witness_size as usize {
+        return false;
+    }
+    let discriminant = create_discriminant(&challenge.to_bytes(), constants.discriminant_size_bits);
+    let mut proof_blob = vdf_info.output.data.to_bytes();
+    proof_blob.extend(proof.witness.to_bytes());
+    verify_n_wesolowski(
+        &discriminant,
+        &input_el.data.to_bytes(),
+        &proof_blob,
+        vdf_info.number_of_iterations,
+        proof.witness_type,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::constants::MAINNET;
+    use crate::blockchain::sized_bytes::UnsizedBytes;
+
+    fn vdf() -> (Bytes32, VdfInfo, VdfProof) {
+        //1000 iterations from the default element, proved by an independent chiavdf port
+        let challenge = Bytes32::new(
+            hex::decode("95729f6d5227d25577c98784b7f8d1dc0ef91388985e9ba45d87fe98eafd5fd6")
+                .unwrap(),
+        );
+        let vdf_info = VdfInfo {
+            challenge: challenge.clone(),
+            output: VdfOutput {
+                data: UnsizedBytes::new(hex::decode("03004ea1d80c31b4efab39d9223ec4048a61b069c61851a6d10bcd466239da3ae9d6b48a1b0864cd9913bddbf73109a6f4049854f4545f74567b466961efe32fa94eafb536b96ca9cddf588b3006dc5592000f8f2dad92fb0c744c9990d8a296133d0100").unwrap()),
+            },
+            number_of_iterations: 1000,
+        };
+        let proof = VdfProof {
+            normalized_to_identity: false,
+            witness: UnsizedBytes::new(hex::decode("0300ad71406e31c89e2beed7195021b90b6da027c4286465a1cfec0ed6783c18fd3bc8d26089d121437af07ff1f67cd85e39426eccb155ede2fd9bec3ee152c37f4b2f3d9bc1a5cc9821d997be5f7136e0cc7641c2d198c1e3c75d35717d4c617d5b0100").unwrap()),
+            witness_type: 0,
+        };
+        (challenge, vdf_info, proof)
+    }
+
+    #[test]
+    fn test_verify_vdf() {
+        let (challenge, vdf_info, proof) = vdf();
+        let input_el = VdfOutput::default_element();
+        assert!(verify_vdf(
+            &MAINNET, &challenge, &input_el, &vdf_info, &proof
+        ));
+        let mut wrong_iterations = vdf_info.clone();
+        wrong_iterations.number_of_iterations += 1;
+        assert!(!verify_vdf(
+            &MAINNET,
+            &challenge,
+            &input_el,
+            &wrong_iterations,
+            &proof
+        ));
+        let other_challenge = Bytes32::new(vec![0u8; 32]);
+        assert!(!verify_vdf(
+            &MAINNET,
+            &other_challenge,
+            &input_el,
+            &vdf_info,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_verify_vdf_rejects_tampering() {
+        let (challenge, vdf_info, proof) = vdf();
+        let input_el = VdfOutput::default_element();
+        let mut tampered_output = vdf_info.clone();
+        let mut data = tampered_output.output.data.to_bytes();
+        data[50] ^= 1;
+        tampered_output.output.data = UnsizedBytes::new(data);
+        assert!(!verify_vdf(
+            &MAINNET,
+            &challenge,
+            &input_el,
+            &tampered_output,
+            &proof
+        ));
+        let mut tampered_proof = proof.clone();
+        let mut witness = tampered_proof.witness.to_bytes();
+        witness[50] ^= 1;
+        tampered_proof.witness = UnsizedBytes::new(witness);
+        assert!(!verify_vdf(
+            &MAINNET,
+            &challenge,
+            &input_el,
+            &vdf_info,
+            &tampered_proof
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
 use crate::blockchain::vdf_output::VdfOutput;
 use serde::{Deserialize, Serialize};
 
@@ -8,3 +8,12 @@ pub struct VdfInfo {
     pub output: VdfOutput,
     pub number_of_iterations: u64,
 }
+impl VdfInfo {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(&self.challenge.to_bytes());
+        bytes.extend(self.number_of_iterations.to_be_bytes());
+        bytes.extend(&self.output.data.to_bytes());
+        bytes
+    }
+}
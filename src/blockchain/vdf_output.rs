@@ -1,7 +1,21 @@
-use crate::blockchain::sized_bytes::UnsizedBytes;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes, UnsizedBytes};
+use crate::clvm::utils::hash_256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VdfOutput {
     pub data: UnsizedBytes,
 }
+impl VdfOutput {
+    pub fn default_element() -> Self {
+        //Serialized generator form, the start of every VDF
+        let mut data = vec![0u8; 100];
+        data[0] = 0x08;
+        VdfOutput {
+            data: UnsizedBytes::new(data),
+        }
+    }
+    pub fn hash(&self) -> Bytes32 {
+        Bytes32::new(hash_256(self.data.to_bytes()))
+    }
+}
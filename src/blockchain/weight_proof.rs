@@ -0,0 +1,85 @@
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::header_block::HeaderBlock;
+use crate::blockchain::proof_of_space::ProofOfSpace;
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use crate::blockchain::vdf_info::VdfInfo;
+use crate::blockchain::vdf_proof::VdfProof;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SubEpochData {
+    pub reward_chain_hash: Bytes32,
+    pub num_blocks_overflow: u8,
+    pub new_sub_slot_iters: Option<u64>,
+    pub new_difficulty: Option<u64>,
+}
+impl From<&SubEpochSummary> for SubEpochData {
+    fn from(summary: &SubEpochSummary) -> Self {
+        SubEpochData {
+            reward_chain_hash: summary.reward_chain_hash.clone(),
+            num_blocks_overflow: summary.num_blocks_overflow,
+            new_sub_slot_iters: summary.new_sub_slot_iters,
+            new_difficulty: summary.new_difficulty,
+        }
+    }
+}
+
+//A challenge block when proof_of_space is set, otherwise a finished sub slot
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SubSlotData {
+    pub proof_of_space: Option<ProofOfSpace>,
+    pub cc_signage_point: Option<VdfProof>,
+    pub cc_infusion_point: Option<VdfProof>,
+    pub icc_infusion_point: Option<VdfProof>,
+    pub cc_sp_vdf_info: Option<VdfInfo>,
+    pub signage_point_index: Option<u8>,
+    pub cc_slot_end: Option<VdfProof>,
+    pub icc_slot_end: Option<VdfProof>,
+    pub cc_slot_end_info: Option<VdfInfo>,
+    pub icc_slot_end_info: Option<VdfInfo>,
+    pub cc_ip_vdf_info: Option<VdfInfo>,
+    pub icc_ip_vdf_info: Option<VdfInfo>,
+    pub total_iters: Option<u128>,
+}
+impl SubSlotData {
+    pub fn is_challenge(&self) -> bool {
+        self.proof_of_space.is_some()
+    }
+    pub fn is_end_of_slot(&self) -> bool {
+        self.cc_slot_end_info.is_some()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SubEpochChallengeSegment {
+    pub sub_epoch_n: u32,
+    pub sub_slots: Vec<SubSlotData>,
+    pub rc_slot_end_info: Option<VdfInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WeightProof {
+    pub sub_epochs: Vec<SubEpochData>,
+    pub sub_epoch_segments: Vec<SubEpochChallengeSegment>,
+    pub recent_chain_data: Vec<HeaderBlock>,
+}
+impl WeightProof {
+    pub fn peak(&self) -> Option<&HeaderBlock> {
+        self.recent_chain_data.last()
+    }
+}
+
+pub fn create_sub_epoch_data(
+    blocks: &impl BlockchainInterface,
+) -> Result<Vec<SubEpochData>, Box<dyn Error>> {
+    let mut sub_epoch_data: Vec<SubEpochData> = Vec::new();
+    for height in blocks.get_ses_heights() {
+        match blocks.get_ses(height) {
+            Some(ses) => sub_epoch_data.push(SubEpochData::from(&ses)),
+            None => return Err(format!("Missing sub epoch summary at height {}", height).into()),
+        }
+    }
+    Ok(sub_epoch_data)
+}
@@ -0,0 +1,1199 @@
+use crate::blockchain::block_cache::BlockCache;
+use crate::blockchain::block_header_validation::validate_header_block;
+use crate::blockchain::block_record::BlockRecord;
+use crate::blockchain::blockchain_interface::BlockchainInterface;
+use crate::blockchain::challenge_chain_subslot::ChallengeChainSubSlot;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::full_block_to_block_record::{
+    calculate_deficit, header_block_to_sub_block_record, BlockParts,
+};
+use crate::blockchain::header_block::HeaderBlock;
+use crate::blockchain::infused_challenge_chain_subslot::InfusedChallengeChainSubSlot;
+use crate::blockchain::pot_iterations::{
+    calculate_ip_iters, calculate_iterations_quality, calculate_sp_iters, is_overflow_block,
+};
+use crate::blockchain::reward_chain_subslot::RewardChainSubSlot;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::blockchain::sub_epoch_summary::SubEpochSummary;
+use crate::blockchain::vdf::verify_vdf;
+use crate::blockchain::vdf_info::VdfInfo;
+use crate::blockchain::vdf_output::VdfOutput;
+use crate::blockchain::vdf_proof::VdfProof;
+use crate::blockchain::weight_proof::{SubEpochChallengeSegment, SubSlotData, WeightProof};
+use crate::clvm::utils::hash_512;
+use std::error::Error;
+
+//Sampling parameters shared with the other nodes on the network
+const LAMBDA_L: f64 = 100.0;
+const C: f64 = 0.5;
+const MAX_SAMPLES: usize = 20;
+const LAST_BLOCKS_TO_VALIDATE: u32 = 100;
+
+const MT_N: usize = 624;
+const MT_M: usize = 397;
+
+//Mersenne Twister seeded the same way as python's random.Random, so every node samples the same sub epochs
+struct PythonRandom {
+    state: [u32; MT_N],
+    index: usize,
+}
+impl PythonRandom {
+    fn new(seed: &[u8]) -> Self {
+        let mut seed_bytes = seed.to_vec();
+        seed_bytes.extend(hash_512(seed.to_vec()));
+        //The seed is read as a big endian integer and split into 32 bit words, least significant first
+        let mut key: Vec<u32> = seed_bytes
+            .rchunks(4)
+            .map(|chunk| {
+                let mut word = [0u8; 4];
+                word[4 - chunk.len()..].copy_from_slice(chunk);
+                u32::from_be_bytes(word)
+            })
+            .collect();
+        while key.len() > 1 && key[key.len() - 1] == 0 {
+            key.pop();
+        }
+
+        let mut state = [0u32; MT_N];
+        state[0] = 19650218;
+        for i in 1..MT_N {
+            state[i] = 1812433253u32
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        let mut i: usize = 1;
+        let mut j: usize = 0;
+        for _ in 0..MT_N.max(key.len()) {
+            state[i] = (state[i] ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= MT_N {
+                state[0] = state[MT_N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..MT_N - 1 {
+            state[i] = (state[i] ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1566083941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= MT_N {
+                state[0] = state[MT_N - 1];
+                i = 1;
+            }
+        }
+        state[0] = 0x80000000;
+        PythonRandom { state, index: MT_N }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.index >= MT_N {
+            for i in 0..MT_N {
+                let y = (self.state[i] & 0x80000000) | (self.state[(i + 1) % MT_N] & 0x7fffffff);
+                let mut value = self.state[(i + MT_M) % MT_N] ^ (y >> 1);
+                if y & 1 != 0 {
+                    value ^= 0x9908b0df;
+                }
+                self.state[i] = value;
+            }
+            self.index = 0;
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c5680;
+        y ^= (y << 15) & 0xefc60000;
+        y ^ (y >> 18)
+    }
+
+    fn random(&mut self) -> f64 {
+        let a = self.next_u32() >> 5;
+        let b = self.next_u32() >> 6;
+        (a as f64 * 67108864.0 + b as f64) / 9007199254740992.0
+    }
+
+    fn randbelow(&mut self, n: usize) -> usize {
+        let bits = usize::BITS - n.leading_zeros();
+        loop {
+            let value = (self.next_u32() >> (32 - bits)) as usize;
+            if value < n {
+                return value;
+            }
+        }
+    }
+}
+
+fn last_ses_hash(constants: &Constants, recent_chain: &[HeaderBlock]) -> Option<Bytes32> {
+    //The summary is included in the first sub slot finished after the last sub epoch boundary
+    for start in (0..recent_chain.len()).rev() {
+        if recent_chain[start].height() % constants.sub_epoch_blocks != 0 {
+            continue;
+        }
+        for block in &recent_chain[start..] {
+            for sub_slot in &block.finished_sub_slots {
+                if let Some(ses_hash) = &sub_slot.challenge_chain.subepoch_summary_hash {
+                    return Some(ses_hash.clone());
+                }
+            }
+        }
+    }
+    None
+}
+
+//Rebuilds the chain of summaries and the starting weight of every sub epoch
+fn map_sub_epoch_summaries(
+    constants: &Constants,
+    weight_proof: &WeightProof,
+) -> (Vec<SubEpochSummary>, u128, Vec<u128>) {
+    let sub_epoch_data = &weight_proof.sub_epochs;
+    let mut ses_hash = constants.genesis_challenge.clone();
+    let mut curr_difficulty = constants.difficulty_starting;
+    let mut total_weight: u128 = 0;
+    let mut summaries: Vec<SubEpochSummary> = Vec::new();
+    let mut sub_epoch_weight_list: Vec<u128> = Vec::new();
+    for (idx, data) in sub_epoch_data.iter().enumerate() {
+        let ses = SubEpochSummary {
+            prev_subepoch_summary_hash: ses_hash,
+            reward_chain_hash: data.reward_chain_hash.clone(),
+            num_blocks_overflow: data.num_blocks_overflow,
+            new_difficulty: data.new_difficulty,
+            new_sub_slot_iters: data.new_sub_slot_iters,
+        };
+        if idx < sub_epoch_data.len() - 1 {
+            let delta: u128 = if idx > 0 {
+                data.num_blocks_overflow as u128
+            } else {
+                0
+            };
+            sub_epoch_weight_list.push(total_weight + curr_difficulty as u128);
+            total_weight += curr_difficulty as u128
+                * (constants.sub_epoch_blocks as u128
+                    + sub_epoch_data[idx + 1].num_blocks_overflow as u128
+                    - delta);
+        }
+        if let Some(new_difficulty) = data.new_difficulty {
+            curr_difficulty = new_difficulty;
+        }
+        ses_hash = ses.hash();
+        summaries.push(ses);
+    }
+    sub_epoch_weight_list.push(total_weight + curr_difficulty as u128);
+    (summaries, total_weight, sub_epoch_weight_list)
+}
+
+fn validate_sub_epoch_summaries(
+    constants: &Constants,
+    weight_proof: &WeightProof,
+) -> Result<(Vec<SubEpochSummary>, Vec<u128>), Box<dyn Error>> {
+    let last_ses_hash = last_ses_hash(constants, &weight_proof.recent_chain_data)
+        .ok_or("Could not find the last sub epoch summary in the recent chain")?;
+    let (summaries, total_weight, sub_epoch_weight_list) =
+        map_sub_epoch_summaries(constants, weight_proof);
+    let last_ses = summaries.last().ok_or("Weight proof has no sub epochs")?;
+
+    //The weight of the last block of the last full sub epoch must match the summaries
+    let ses_end_height = (summaries.len() as i64 - 1) * constants.sub_epoch_blocks as i64
+        + last_ses.num_blocks_overflow as i64
+        - 1;
+    let ses_end_block = weight_proof
+        .recent_chain_data
+        .iter()
+        .rev()
+        .find(|block| block.height() as i64 == ses_end_height)
+        .ok_or("Recent chain does not include the end of the last sub epoch")?;
+    if ses_end_block.weight() != total_weight {
+        return Err("Sub epoch summaries do not add up to the recent chain weight".into());
+    }
+    if last_ses.hash() != last_ses_hash {
+        return Err("Last sub epoch summary does not match the recent chain".into());
+    }
+    Ok((summaries, sub_epoch_weight_list))
+}
+
+fn weights_for_sampling(
+    rng: &mut PythonRandom,
+    total_weight: u128,
+    recent_chain: &[HeaderBlock],
+) -> Result<Option<Vec<u128>>, Box<dyn Error>> {
+    let (first, last) = match (recent_chain.first(), recent_chain.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err("Weight proof has no recent chain".into()),
+    };
+    if total_weight == 0 {
+        return Err("Weight proof has no weight".into());
+    }
+    let last_l_weight = last
+        .weight()
+        .checked_sub(first.weight())
+        .ok_or("Recent chain weight is not increasing")?;
+    let delta = last_l_weight as f64 / total_weight as f64;
+    if delta <= 0.0 || delta >= 1.0 {
+        return Err("Recent chain weight is out of range".into());
+    }
+    let prob_of_adv_succeeding = 1.0 - C.ln() / delta.ln();
+    if prob_of_adv_succeeding <= 0.0 {
+        return Ok(None);
+    }
+    let queries = -LAMBDA_L * 2f64.ln() / prob_of_adv_succeeding.ln();
+    let mut weight_to_check: Vec<u128> = Vec::new();
+    for _ in 0..queries as u64 + 1 {
+        let u = rng.random();
+        let q = 1.0 - delta.powf(u);
+        weight_to_check.push((q * total_weight as f64) as u128);
+    }
+    weight_to_check.sort_unstable();
+    Ok(Some(weight_to_check))
+}
+
+fn sample_sub_epoch(
+    start_of_epoch_weight: u128,
+    end_of_epoch_weight: u128,
+    weight_to_check: &Option<Vec<u128>>,
+) -> bool {
+    match weight_to_check {
+        Some(weights) => weights
+            .iter()
+            .any(|weight| start_of_epoch_weight < *weight && *weight < end_of_epoch_weight),
+        None => true,
+    }
+}
+
+fn validate_sub_epoch_sampling(
+    rng: &mut PythonRandom,
+    sub_epoch_weight_list: &[u128],
+    weight_proof: &WeightProof,
+) -> Result<(), Box<dyn Error>> {
+    let tip = weight_proof
+        .peak()
+        .ok_or("Weight proof has no recent chain")?;
+    let weight_to_check = weights_for_sampling(rng, tip.weight(), &weight_proof.recent_chain_data)?;
+    let mut sampled_sub_epochs: Vec<u32> = Vec::new();
+    for idx in 1..sub_epoch_weight_list.len() {
+        if sample_sub_epoch(
+            sub_epoch_weight_list[idx - 1],
+            sub_epoch_weight_list[idx],
+            &weight_to_check,
+        ) {
+            sampled_sub_epochs.push(idx as u32 - 1);
+            if sampled_sub_epochs.len() == MAX_SAMPLES {
+                break;
+            }
+        }
+    }
+    for sub_epoch_n in sampled_sub_epochs {
+        if !weight_proof
+            .sub_epoch_segments
+            .iter()
+            .any(|segment| segment.sub_epoch_n == sub_epoch_n)
+        {
+            return Err(format!("Missing segments for sampled sub epoch {}", sub_epoch_n).into());
+        }
+    }
+    Ok(())
+}
+
+fn map_segments_by_sub_epoch(
+    segments: &[SubEpochChallengeSegment],
+) -> Vec<(u32, Vec<&SubEpochChallengeSegment>)> {
+    let mut mapped: Vec<(u32, Vec<&SubEpochChallengeSegment>)> = Vec::new();
+    for segment in segments {
+        let new_sub_epoch = match mapped.last() {
+            Some((sub_epoch_n, _)) => *sub_epoch_n < segment.sub_epoch_n,
+            None => true,
+        };
+        if new_sub_epoch {
+            mapped.push((segment.sub_epoch_n, Vec::new()));
+        }
+        if let Some((_, sub_epoch_segments)) = mapped.last_mut() {
+            sub_epoch_segments.push(segment);
+        }
+    }
+    mapped
+}
+
+fn curr_diff_ssi(
+    constants: &Constants,
+    sub_epoch_n: usize,
+    summaries: &[SubEpochSummary],
+) -> (u64, u64) {
+    for ses in summaries[..sub_epoch_n].iter().rev() {
+        if let (Some(new_sub_slot_iters), Some(new_difficulty)) =
+            (ses.new_sub_slot_iters, ses.new_difficulty)
+        {
+            return (new_difficulty, new_sub_slot_iters);
+        }
+    }
+    (
+        constants.difficulty_starting,
+        constants.sub_slot_iters_starting,
+    )
+}
+
+fn total_iters(sub_slot_data: &SubSlotData) -> Result<u128, Box<dyn Error>> {
+    sub_slot_data
+        .total_iters
+        .ok_or_else(|| "Sub slot data is missing total iters".into())
+}
+
+fn end_of_slot_hash(info: &VdfInfo) -> Bytes32 {
+    InfusedChallengeChainSubSlot {
+        infused_challenge_chain_end_of_slot_vdf: info.clone(),
+    }
+    .hash()
+}
+
+//Rebuilds the reward chain sub slot that ended the previous sub epoch
+fn rc_sub_slot(
+    constants: &Constants,
+    segment: &SubEpochChallengeSegment,
+    summaries: &[SubEpochSummary],
+    curr_ssi: u64,
+) -> Result<RewardChainSubSlot, Box<dyn Error>> {
+    let ses = &summaries[segment.sub_epoch_n as usize - 1];
+    let slots = &segment.sub_slots;
+    let first_idx = match slots.iter().position(|slot| slot.cc_slot_end.is_none()) {
+        Some(idx) if idx > 0 => idx,
+        _ => return Err("Segment does not start with a finished sub slot".into()),
+    };
+    let first_signage_point_index = slots[first_idx]
+        .signage_point_index
+        .ok_or("Challenge block is missing its signage point index")?;
+    let overflow = is_overflow_block(constants, first_signage_point_index)?;
+
+    let mut slots_n = 1;
+    if overflow && first_idx >= 2 && slots[first_idx - 2].cc_slot_end.is_none() {
+        slots_n = 2;
+    }
+    let (mut ses_hash, mut new_sub_slot_iters, mut new_difficulty) =
+        (Some(ses.hash()), ses.new_sub_slot_iters, ses.new_difficulty);
+    //An overflow block after two finished slots was not the block the summary was included after
+    if overflow
+        && first_idx >= 2
+        && slots[first_idx - 2].cc_slot_end.is_some()
+        && slots[first_idx - 1].cc_slot_end.is_some()
+    {
+        ses_hash = None;
+        new_sub_slot_iters = None;
+        new_difficulty = None;
+    }
+
+    let mut idx = first_idx;
+    loop {
+        if slots[idx].cc_slot_end.is_some() {
+            slots_n -= 1;
+            if slots_n == 0 {
+                break;
+            }
+        }
+        if idx == 0 {
+            return Err("Segment does not include the end of the previous sub epoch".into());
+        }
+        idx -= 1;
+    }
+    let sub_slot = &slots[idx];
+    let cc_slot_end_info = sub_slot
+        .cc_slot_end_info
+        .as_ref()
+        .ok_or("Finished sub slot is missing its challenge chain info")?;
+    let rc_slot_end_info = segment
+        .rc_slot_end_info
+        .as_ref()
+        .ok_or("First segment of a sub epoch is missing its reward chain info")?;
+
+    //Only the first sub slot of the segment records its own iterations and commits to the summary
+    let (cc_vdf_info, icc_sub_slot_hash) = if idx != 0 {
+        ses_hash = None;
+        new_sub_slot_iters = None;
+        new_difficulty = None;
+        (
+            VdfInfo {
+                challenge: cc_slot_end_info.challenge.clone(),
+                number_of_iterations: curr_ssi,
+                output: cc_slot_end_info.output.clone(),
+            },
+            sub_slot.icc_slot_end_info.as_ref().map(|info| {
+                end_of_slot_hash(&VdfInfo {
+                    challenge: info.challenge.clone(),
+                    number_of_iterations: curr_ssi,
+                    output: info.output.clone(),
+                })
+            }),
+        )
+    } else {
+        (
+            cc_slot_end_info.clone(),
+            sub_slot.icc_slot_end_info.as_ref().map(end_of_slot_hash),
+        )
+    };
+    let cc_sub_slot = ChallengeChainSubSlot {
+        challenge_chain_end_of_slot_vdf: cc_vdf_info,
+        new_sub_slot_iters,
+        new_difficulty,
+        infused_challenge_chain_sub_slot_hash: icc_sub_slot_hash.clone(),
+        subepoch_summary_hash: ses_hash,
+    };
+    Ok(RewardChainSubSlot {
+        end_of_slot_vdf: rc_slot_end_info.clone(),
+        challenge_chain_sub_slot_hash: Bytes32::new(cc_sub_slot.hash()?),
+        infused_challenge_chain_sub_slot_hash: icc_sub_slot_hash,
+        deficit: constants.min_blocks_per_challenge_block,
+    })
+}
+
+//Rebuilds the challenge chain sub slot the block at idx was farmed in
+fn cc_sub_slot(
+    sub_slots: &[SubSlotData],
+    idx: usize,
+    ses: Option<&SubEpochSummary>,
+) -> Result<ChallengeChainSubSlot, Box<dyn Error>> {
+    let sub_slot = sub_slots[..idx]
+        .iter()
+        .rev()
+        .find(|sub_slot| sub_slot.cc_slot_end_info.is_some())
+        .ok_or("Challenge block has no finished sub slot before it")?;
+    let cc_slot_end_info = sub_slot
+        .cc_slot_end_info
+        .as_ref()
+        .ok_or("Finished sub slot is missing its challenge chain info")?;
+    Ok(ChallengeChainSubSlot {
+        challenge_chain_end_of_slot_vdf: cc_slot_end_info.clone(),
+        new_sub_slot_iters: ses.and_then(|ses| ses.new_sub_slot_iters),
+        new_difficulty: ses.and_then(|ses| ses.new_difficulty),
+        infused_challenge_chain_sub_slot_hash: sub_slot
+            .icc_slot_end_info
+            .as_ref()
+            .map(end_of_slot_hash),
+        subepoch_summary_hash: ses.map(|ses| ses.hash()),
+    })
+}
+
+fn validate_pospace(
+    constants: &Constants,
+    segment: &SubEpochChallengeSegment,
+    idx: usize,
+    curr_difficulty: u64,
+    ses: Option<&SubEpochSummary>,
+    first_in_sub_epoch: bool,
+) -> Result<u64, Box<dyn Error>> {
+    let cc_sub_slot_hash = if first_in_sub_epoch && segment.sub_epoch_n == 0 && idx == 0 {
+        constants.genesis_challenge.clone()
+    } else {
+        Bytes32::new(cc_sub_slot(&segment.sub_slots, idx, ses)?.hash()?)
+    };
+    let sub_slot_data = &segment.sub_slots[idx];
+    let signage_point_index = sub_slot_data
+        .signage_point_index
+        .ok_or("Challenge block is missing its signage point index")?;
+
+    //Overflow blocks are farmed against the challenge of the previous sub slot
+    let challenge = if is_overflow_block(constants, signage_point_index)? {
+        match idx
+            .checked_sub(1)
+            .map(|prev_idx| &segment.sub_slots[prev_idx])
+        {
+            Some(SubSlotData {
+                cc_slot_end_info: Some(cc_slot_end_info),
+                ..
+            }) => cc_slot_end_info.challenge.clone(),
+            _ => return Err("Overflow block does not follow a finished sub slot".into()),
+        }
+    } else {
+        cc_sub_slot_hash.clone()
+    };
+    let cc_sp_hash = match &sub_slot_data.cc_sp_vdf_info {
+        Some(cc_sp_vdf_info) => cc_sp_vdf_info.output.hash(),
+        None => cc_sub_slot_hash,
+    };
+
+    let proof_of_space = sub_slot_data
+        .proof_of_space
+        .as_ref()
+        .ok_or("Challenge block is missing its proof of space")?;
+    let quality_string = proof_of_space
+        .verify_and_get_quality_string(
+            &challenge,
+            &cc_sp_hash,
+            constants.min_plot_size,
+            constants.max_plot_size,
+        )?
+        .ok_or("Invalid proof of space in sub epoch segment")?;
+    Ok(calculate_iterations_quality(
+        constants.difficulty_constant_factor,
+        &quality_string,
+        proof_of_space.size,
+        curr_difficulty,
+        &cc_sp_hash,
+    ))
+}
+
+fn sp_total_iters(
+    constants: &Constants,
+    is_overflow: bool,
+    sub_slot_iters: u64,
+    sub_slot_data: &SubSlotData,
+) -> Result<u128, Box<dyn Error>> {
+    let cc_ip_vdf_info = sub_slot_data
+        .cc_ip_vdf_info
+        .as_ref()
+        .ok_or("Sub slot data is missing its infusion point info")?;
+    let signage_point_index = sub_slot_data
+        .signage_point_index
+        .ok_or("Sub slot data is missing its signage point index")?;
+    let sp_iters = calculate_sp_iters(constants, sub_slot_iters, signage_point_index)?;
+    let mut sp_sub_slot_total_iters = total_iters(sub_slot_data)?
+        .checked_sub(cc_ip_vdf_info.number_of_iterations as u128)
+        .ok_or("Infusion point is before the start of the chain")?;
+    if is_overflow {
+        sp_sub_slot_total_iters = sp_sub_slot_total_iters
+            .checked_sub(sub_slot_iters as u128)
+            .ok_or("Overflow signage point is before the start of the chain")?;
+    }
+    Ok(sp_sub_slot_total_iters + sp_iters as u128)
+}
+
+//Infusion point output of the last block before the signage point, if it is in the same sub slot
+fn ip_output_before_sp(
+    sub_slot_data: Option<&SubSlotData>,
+    sp_total_iters: u128,
+) -> Result<VdfOutput, Box<dyn Error>> {
+    if let Some(sub_slot_data) = sub_slot_data {
+        if let Some(cc_ip_vdf_info) = &sub_slot_data.cc_ip_vdf_info {
+            if total_iters(sub_slot_data)? < sp_total_iters {
+                return Ok(cc_ip_vdf_info.output.clone());
+            }
+        }
+    }
+    Ok(VdfOutput::default_element())
+}
+
+fn sub_slot_data_vdf_input(
+    constants: &Constants,
+    sub_slot_data: &SubSlotData,
+    sub_slot_idx: usize,
+    sub_slots: &[SubSlotData],
+    is_overflow: bool,
+    new_sub_slot: bool,
+    sub_slot_iters: u64,
+) -> Result<VdfOutput, Box<dyn Error>> {
+    let sp_total_iters = sp_total_iters(constants, is_overflow, sub_slot_iters, sub_slot_data)?;
+    match (is_overflow, new_sub_slot) {
+        (true, true) => {
+            if sub_slot_idx < 2 || sub_slots[sub_slot_idx - 2].cc_slot_end_info.is_some() {
+                return Ok(VdfOutput::default_element());
+            }
+            let mut ssd: Option<&SubSlotData> = None;
+            for ssd_idx in (0..sub_slot_idx - 1).rev() {
+                if sub_slots[ssd_idx].cc_slot_end_info.is_some() {
+                    ssd = Some(&sub_slots[ssd_idx + 1]);
+                    break;
+                }
+                ssd = Some(&sub_slots[ssd_idx]);
+                if total_iters(&sub_slots[ssd_idx])? <= sp_total_iters {
+                    break;
+                }
+            }
+            ip_output_before_sp(ssd, sp_total_iters)
+        }
+        (false, false) => {
+            let mut ssd: Option<&SubSlotData> = None;
+            for ssd_idx in (0..sub_slot_idx).rev() {
+                if sub_slots[ssd_idx].cc_slot_end_info.is_some() {
+                    ssd = Some(&sub_slots[ssd_idx + 1]);
+                    break;
+                }
+                ssd = Some(&sub_slots[ssd_idx]);
+                if total_iters(&sub_slots[ssd_idx])? <= sp_total_iters {
+                    break;
+                }
+            }
+            if ssd.is_none() {
+                return Err("Signage point has no sub slot data before it".into());
+            }
+            ip_output_before_sp(ssd, sp_total_iters)
+        }
+        (true, false) => {
+            let mut ssd: Option<&SubSlotData> = None;
+            let mut slots_seen = 0;
+            for ssd_idx in (0..sub_slot_idx).rev() {
+                let curr = &sub_slots[ssd_idx];
+                ssd = Some(curr);
+                if curr.cc_slot_end_info.is_some() {
+                    slots_seen += 1;
+                    if slots_seen == 2 {
+                        return Ok(VdfOutput::default_element());
+                    }
+                } else if total_iters(curr)? <= sp_total_iters {
+                    break;
+                }
+            }
+            if ssd.is_none() {
+                return Err("Signage point has no sub slot data before it".into());
+            }
+            ip_output_before_sp(ssd, sp_total_iters)
+        }
+        (false, true) => Ok(VdfOutput::default_element()),
+    }
+}
+
+fn is_valid_vdf(
+    constants: &Constants,
+    input_el: &VdfOutput,
+    vdf_info: &VdfInfo,
+    proof: &VdfProof,
+) -> bool {
+    verify_vdf(constants, &vdf_info.challenge, input_el, vdf_info, proof)
+}
+
+fn validate_cc_ip_vdf(
+    constants: &Constants,
+    sub_slot_data: &SubSlotData,
+    prev_ssd: Option<&SubSlotData>,
+) -> Result<bool, Box<dyn Error>> {
+    let cc_infusion_point = sub_slot_data
+        .cc_infusion_point
+        .as_ref()
+        .ok_or("Sub slot data is missing its infusion point proof")?;
+    let cc_ip_vdf_info = sub_slot_data
+        .cc_ip_vdf_info
+        .as_ref()
+        .ok_or("Sub slot data is missing its infusion point info")?;
+    //Uncompacted infusions continue from the previous block in the same sub slot
+    match prev_ssd {
+        Some(prev_ssd)
+            if !cc_infusion_point.normalized_to_identity && prev_ssd.cc_slot_end.is_none() =>
+        {
+            let prev_cc_ip_vdf_info = prev_ssd
+                .cc_ip_vdf_info
+                .as_ref()
+                .ok_or("Previous sub slot data is missing its infusion point info")?;
+            let ip_vdf_iters = total_iters(sub_slot_data)?
+                .checked_sub(total_iters(prev_ssd)?)
+                .ok_or("Infusion points are out of order")?;
+            let vdf_info = VdfInfo {
+                challenge: cc_ip_vdf_info.challenge.clone(),
+                number_of_iterations: ip_vdf_iters as u64,
+                output: cc_ip_vdf_info.output.clone(),
+            };
+            Ok(is_valid_vdf(
+                constants,
+                &prev_cc_ip_vdf_info.output,
+                &vdf_info,
+                cc_infusion_point,
+            ))
+        }
+        _ => Ok(is_valid_vdf(
+            constants,
+            &VdfOutput::default_element(),
+            cc_ip_vdf_info,
+            cc_infusion_point,
+        )),
+    }
+}
+
+fn validate_cc_sp_vdf(
+    constants: &Constants,
+    sub_slot_idx: usize,
+    sub_slots: &[SubSlotData],
+    sub_slot_iters: u64,
+) -> Result<bool, Box<dyn Error>> {
+    let sub_slot_data = &sub_slots[sub_slot_idx];
+    let (cc_signage_point, cc_sp_vdf_info) = match (
+        &sub_slot_data.cc_signage_point,
+        &sub_slot_data.cc_sp_vdf_info,
+    ) {
+        (Some(cc_signage_point), Some(cc_sp_vdf_info)) => (cc_signage_point, cc_sp_vdf_info),
+        _ => return Ok(true),
+    };
+    let signage_point_index = sub_slot_data
+        .signage_point_index
+        .ok_or("Sub slot data is missing its signage point index")?;
+    let mut sp_input = VdfOutput::default_element();
+    if !cc_signage_point.normalized_to_identity && sub_slot_idx >= 1 {
+        sp_input = sub_slot_data_vdf_input(
+            constants,
+            sub_slot_data,
+            sub_slot_idx,
+            sub_slots,
+            is_overflow_block(constants, signage_point_index)?,
+            sub_slots[sub_slot_idx - 1].is_end_of_slot(),
+            sub_slot_iters,
+        )?;
+    }
+    Ok(is_valid_vdf(
+        constants,
+        &sp_input,
+        cc_sp_vdf_info,
+        cc_signage_point,
+    ))
+}
+
+fn validate_challenge_block_vdfs(
+    constants: &Constants,
+    sub_slot_idx: usize,
+    sub_slots: &[SubSlotData],
+    sub_slot_iters: u64,
+) -> Result<bool, Box<dyn Error>> {
+    if !validate_cc_sp_vdf(constants, sub_slot_idx, sub_slots, sub_slot_iters)? {
+        return Ok(false);
+    }
+    let prev_ssd = sub_slot_idx.checked_sub(1).map(|idx| &sub_slots[idx]);
+    validate_cc_ip_vdf(constants, &sub_slots[sub_slot_idx], prev_ssd)
+}
+
+fn validate_sub_slot_data(
+    constants: &Constants,
+    sub_slot_idx: usize,
+    sub_slots: &[SubSlotData],
+    sub_slot_iters: u64,
+) -> Result<bool, Box<dyn Error>> {
+    let sub_slot_data = &sub_slots[sub_slot_idx];
+    let prev_ssd = sub_slot_idx
+        .checked_sub(1)
+        .map(|idx| &sub_slots[idx])
+        .ok_or("Sub slot data must follow a challenge block")?;
+    if sub_slot_data.is_end_of_slot() {
+        if let Some(icc_slot_end) = &sub_slot_data.icc_slot_end {
+            let icc_slot_end_info = sub_slot_data
+                .icc_slot_end_info
+                .as_ref()
+                .ok_or("Finished sub slot is missing its infused challenge chain info")?;
+            let input = match &prev_ssd.icc_ip_vdf_info {
+                Some(icc_ip_vdf_info) if !icc_slot_end.normalized_to_identity => {
+                    icc_ip_vdf_info.output.clone()
+                }
+                _ => VdfOutput::default_element(),
+            };
+            if !is_valid_vdf(constants, &input, icc_slot_end_info, icc_slot_end) {
+                return Ok(false);
+            }
+        }
+        let (cc_slot_end, cc_slot_end_info) =
+            match (&sub_slot_data.cc_slot_end, &sub_slot_data.cc_slot_end_info) {
+                (Some(cc_slot_end), Some(cc_slot_end_info)) => (cc_slot_end, cc_slot_end_info),
+                _ => return Err("Finished sub slot is missing its challenge chain proof".into()),
+            };
+        let input = if !prev_ssd.is_end_of_slot() && !cc_slot_end.normalized_to_identity {
+            prev_ssd
+                .cc_ip_vdf_info
+                .as_ref()
+                .ok_or("Previous sub slot data is missing its infusion point info")?
+                .output
+                .clone()
+        } else {
+            VdfOutput::default_element()
+        };
+        return Ok(is_valid_vdf(
+            constants,
+            &input,
+            cc_slot_end_info,
+            cc_slot_end,
+        ));
+    }
+
+    //Blocks in a compacted sub slot only keep their outputs, not the intermediate proofs
+    if let Some(end_of_slot) = sub_slots[sub_slot_idx..sub_slots.len() - 1]
+        .iter()
+        .find(|sub_slot| sub_slot.is_end_of_slot())
+    {
+        match &end_of_slot.cc_slot_end {
+            Some(cc_slot_end) if cc_slot_end.normalized_to_identity => return Ok(true),
+            Some(_) => {}
+            None => return Err("Finished sub slot is missing its challenge chain proof".into()),
+        }
+    }
+    if let (Some(icc_infusion_point), Some(icc_ip_vdf_info)) = (
+        &sub_slot_data.icc_infusion_point,
+        &sub_slot_data.icc_ip_vdf_info,
+    ) {
+        let input = match &prev_ssd.icc_ip_vdf_info {
+            Some(prev_icc_ip_vdf_info) if !prev_ssd.is_challenge() => {
+                prev_icc_ip_vdf_info.output.clone()
+            }
+            _ => VdfOutput::default_element(),
+        };
+        if !is_valid_vdf(constants, &input, icc_ip_vdf_info, icc_infusion_point) {
+            return Ok(false);
+        }
+    }
+    if sub_slot_data.signage_point_index.is_none() {
+        return Err("Sub slot data is missing its signage point index".into());
+    }
+    if !validate_cc_sp_vdf(constants, sub_slot_idx, sub_slots, sub_slot_iters)? {
+        return Ok(false);
+    }
+    validate_cc_ip_vdf(constants, sub_slot_data, Some(prev_ssd))
+}
+
+fn validate_segment(
+    constants: &Constants,
+    segment: &SubEpochChallengeSegment,
+    curr_ssi: u64,
+    curr_difficulty: u64,
+    ses: Option<&SubEpochSummary>,
+    first_segment_in_se: bool,
+    sampled: bool,
+) -> Result<(), Box<dyn Error>> {
+    //Only the sampled segment of each sub epoch has its proofs checked
+    if !sampled {
+        return Ok(());
+    }
+    let mut after_challenge = false;
+    for (idx, sub_slot_data) in segment.sub_slots.iter().enumerate() {
+        if sub_slot_data.is_challenge() {
+            after_challenge = true;
+            let required_iters = validate_pospace(
+                constants,
+                segment,
+                idx,
+                curr_difficulty,
+                ses,
+                first_segment_in_se,
+            )?;
+            let signage_point_index = sub_slot_data
+                .signage_point_index
+                .ok_or("Challenge block is missing its signage point index")?;
+            //Required iters must fit within the signage point interval
+            calculate_ip_iters(constants, curr_ssi, signage_point_index, required_iters)?;
+            if !validate_challenge_block_vdfs(constants, idx, &segment.sub_slots, curr_ssi)? {
+                return Err(format!(
+                    "Invalid challenge block VDFs in sub epoch {}",
+                    segment.sub_epoch_n
+                )
+                .into());
+            }
+        } else if after_challenge
+            && !validate_sub_slot_data(constants, idx, &segment.sub_slots, curr_ssi)?
+        {
+            return Err(
+                format!("Invalid sub slot VDFs in sub epoch {}", segment.sub_epoch_n).into(),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn validate_sub_epoch_segments(
+    constants: &Constants,
+    rng: &mut PythonRandom,
+    sub_epoch_segments: &[SubEpochChallengeSegment],
+    summaries: &[SubEpochSummary],
+) -> Result<(), Box<dyn Error>> {
+    let mut rc_sub_slot_hash = constants.genesis_challenge.clone();
+    for (sub_epoch_n, segments) in map_segments_by_sub_epoch(sub_epoch_segments) {
+        let sub_epoch_n = sub_epoch_n as usize;
+        let summary = summaries
+            .get(sub_epoch_n)
+            .ok_or_else(|| format!("Segment for unknown sub epoch {}", sub_epoch_n))?;
+        let (curr_difficulty, curr_ssi) = curr_diff_ssi(constants, sub_epoch_n, summaries);
+        let sampled_seg_index = rng.randbelow(segments.len());
+        let mut prev_ses: Option<&SubEpochSummary> = None;
+        if sub_epoch_n > 0 {
+            rc_sub_slot_hash = rc_sub_slot(constants, segments[0], summaries, curr_ssi)?.hash();
+            prev_ses = Some(&summaries[sub_epoch_n - 1]);
+        }
+        if summary.reward_chain_hash != rc_sub_slot_hash {
+            return Err(
+                format!("Reward chain hash does not match sub epoch {}", sub_epoch_n).into(),
+            );
+        }
+        for (idx, segment) in segments.iter().enumerate() {
+            validate_segment(
+                constants,
+                segment,
+                curr_ssi,
+                curr_difficulty,
+                prev_ses,
+                idx == 0,
+                sampled_seg_index == idx,
+            )?;
+            prev_ses = None;
+        }
+    }
+    Ok(())
+}
+
+fn validate_pospace_recent_chain(
+    constants: &Constants,
+    block: &HeaderBlock,
+    challenge: &Bytes32,
+    difficulty: u64,
+    overflow: bool,
+    prev_challenge: &Bytes32,
+) -> Result<u64, Box<dyn Error>> {
+    let reward_chain_block = &block.reward_chain_block;
+    //The first signage point of a sub slot has no VDF of its own
+    let cc_sp_hash = match &reward_chain_block.challenge_chain_sp_vdf {
+        Some(challenge_chain_sp_vdf) => challenge_chain_sp_vdf.output.hash(),
+        None => challenge.clone(),
+    };
+    let quality_string = reward_chain_block
+        .proof_of_space
+        .verify_and_get_quality_string(
+            if overflow { prev_challenge } else { challenge },
+            &cc_sp_hash,
+            constants.min_plot_size,
+            constants.max_plot_size,
+        )?
+        .ok_or_else(|| format!("Invalid proof of space at height {}", block.height()))?;
+    Ok(calculate_iterations_quality(
+        constants.difficulty_constant_factor,
+        &quality_string,
+        reward_chain_block.proof_of_space.size,
+        difficulty,
+        &cc_sp_hash,
+    ))
+}
+
+fn validate_recent_blocks(
+    constants: &Constants,
+    recent_chain: &[HeaderBlock],
+    summaries: &[SubEpochSummary],
+) -> Result<(), Box<dyn Error>> {
+    let mut sub_blocks = BlockCache::new();
+    let recent_ses_count = recent_chain
+        .iter()
+        .flat_map(|block| block.finished_sub_slots.iter())
+        .filter(|sub_slot| sub_slot.challenge_chain.subepoch_summary_hash.is_some())
+        .count();
+    let mut ses_idx = summaries
+        .len()
+        .checked_sub(recent_ses_count)
+        .ok_or("Recent chain includes more summaries than the weight proof")?;
+    let mut sub_slot_iters = constants.sub_slot_iters_starting;
+    let mut difficulty = constants.difficulty_starting;
+    for summary in &summaries[..ses_idx] {
+        if let Some(new_sub_slot_iters) = summary.new_sub_slot_iters {
+            sub_slot_iters = new_sub_slot_iters;
+        }
+        if let Some(new_difficulty) = summary.new_difficulty {
+            difficulty = new_difficulty;
+        }
+    }
+
+    let tip_height = recent_chain
+        .last()
+        .ok_or("Weight proof has no recent chain")?
+        .height();
+    let (mut ses_blocks, mut sub_slots, mut transaction_blocks) = (0, 0, 0);
+    let mut challenge = recent_chain[0]
+        .reward_chain_block
+        .pos_ss_cc_challenge_hash
+        .clone();
+    let mut prev_challenge: Option<Bytes32> = None;
+    let mut prev_block_record: Option<BlockRecord> = None;
+    let mut prev_transaction_block_height: Option<u32> = None;
+    let mut deficit: u8 = 0;
+    let mut adjusted = false;
+    for block in recent_chain {
+        let mut required_iters: u64 = 0;
+        let mut overflow = false;
+        let mut ses = false;
+        for sub_slot in &block.finished_sub_slots {
+            prev_challenge = Some(
+                sub_slot
+                    .challenge_chain
+                    .challenge_chain_end_of_slot_vdf
+                    .challenge
+                    .clone(),
+            );
+            challenge = Bytes32::new(sub_slot.challenge_chain.hash()?);
+            deficit = sub_slot.reward_chain.deficit;
+            if let Some(ses_hash) = &sub_slot.challenge_chain.subepoch_summary_hash {
+                ses = true;
+                let summary = summaries
+                    .get(ses_idx)
+                    .ok_or("Recent chain includes more summaries than the weight proof")?;
+                if summary.hash() != *ses_hash {
+                    return Err(format!(
+                        "Sub epoch summary at height {} does not match the weight proof",
+                        block.height()
+                    )
+                    .into());
+                }
+                ses_idx += 1;
+            }
+            if let Some(new_sub_slot_iters) = sub_slot.challenge_chain.new_sub_slot_iters {
+                sub_slot_iters = new_sub_slot_iters;
+            }
+            if let Some(new_difficulty) = sub_slot.challenge_chain.new_difficulty {
+                difficulty = new_difficulty;
+            }
+        }
+
+        //Blocks are only checked once two sub slots are known, before that the challenge is missing
+        if let Some(prev_challenge) = &prev_challenge {
+            overflow = is_overflow_block(constants, block.reward_chain_block.signage_point_index)?;
+            if !adjusted {
+                if let Some(prev_block_record) = prev_block_record.as_mut() {
+                    prev_block_record.deficit = deficit % constants.min_blocks_per_challenge_block;
+                    sub_blocks.add_block_record(prev_block_record.clone());
+                }
+                adjusted = true;
+            }
+            deficit = match &prev_block_record {
+                Some(prev_block_record) => calculate_deficit(
+                    constants,
+                    prev_block_record.height + 1,
+                    Some(prev_block_record),
+                    overflow,
+                    block.finished_sub_slots.len(),
+                )?,
+                None if deficit >= 1
+                    && !(overflow && deficit == constants.min_blocks_per_challenge_block) =>
+                {
+                    deficit - 1
+                }
+                None => deficit,
+            };
+            required_iters = if sub_slots > 2
+                && transaction_blocks > 11
+                && tip_height.saturating_sub(block.height()) < LAST_BLOCKS_TO_VALIDATE
+            {
+                validate_header_block(
+                    constants,
+                    &sub_blocks,
+                    block,
+                    false,
+                    difficulty,
+                    sub_slot_iters,
+                    ses_blocks > 2,
+                    false,
+                )?
+            } else {
+                validate_pospace_recent_chain(
+                    constants,
+                    block,
+                    &challenge,
+                    difficulty,
+                    overflow,
+                    prev_challenge,
+                )?
+            };
+        }
+
+        let curr_block_ses = if ses {
+            Some(summaries[ses_idx - 1].clone())
+        } else {
+            None
+        };
+        let block_record = header_block_to_sub_block_record(
+            constants,
+            required_iters,
+            &BlockParts::from(block),
+            sub_slot_iters,
+            overflow,
+            deficit,
+            prev_transaction_block_height.unwrap_or_else(|| block.height()),
+            curr_block_ses,
+        )?;
+        sub_blocks.add_block_record(block_record.clone());
+
+        if block.first_in_sub_slot() {
+            sub_slots += 1;
+        }
+        if block.is_transaction_block() {
+            transaction_blocks += 1;
+            prev_transaction_block_height = Some(block.height());
+        }
+        if ses {
+            ses_blocks += 1;
+        }
+        prev_block_record = Some(block_record);
+    }
+    Ok(())
+}
+
+fn get_fork_point(
+    blocks: &impl BlockchainInterface,
+    received_summaries: &[SubEpochSummary],
+) -> u32 {
+    let ses_heights = blocks.get_ses_heights();
+    let mut fork_point_index = 0;
+    for (idx, summary_height) in ses_heights.iter().enumerate() {
+        if idx == received_summaries.len() - 1 {
+            break;
+        }
+        match blocks.get_ses(*summary_height) {
+            Some(local_ses) if local_ses.hash() == received_summaries[idx].hash() => {
+                fork_point_index = idx
+            }
+            _ => break,
+        }
+    }
+    //Two matching summaries can still cover different blocks until a full sub epoch later
+    if fork_point_index > 2 {
+        ses_heights[fork_point_index - 2]
+    } else {
+        0
+    }
+}
+
+//Validates a weight proof ending at peak, returning the height our chain forks from it
+pub fn validate_weight_proof(
+    constants: &Constants,
+    blocks: &impl BlockchainInterface,
+    weight_proof: &WeightProof,
+    peak: &BlockRecord,
+) -> Result<u32, Box<dyn Error>> {
+    let tip = weight_proof
+        .peak()
+        .ok_or("Weight proof has no recent chain")?;
+    if tip.header_hash() != peak.header_hash
+        || tip.height() != peak.height
+        || tip.weight() != peak.weight
+    {
+        return Err("Weight proof does not end at the peak".into());
+    }
+    if weight_proof.sub_epochs.is_empty() {
+        return Err("Weight proof has no sub epochs".into());
+    }
+    let (summaries, sub_epoch_weight_list) = validate_sub_epoch_summaries(constants, weight_proof)?;
+    if summaries.len() < 2 {
+        return Err("Weight proof needs at least two sub epochs".into());
+    }
+    let mut rng = PythonRandom::new(&summaries[summaries.len() - 2].hash().to_bytes());
+    validate_sub_epoch_sampling(&mut rng, &sub_epoch_weight_list, weight_proof)?;
+    validate_sub_epoch_segments(
+        constants,
+        &mut rng,
+        &weight_proof.sub_epoch_segments,
+        &summaries,
+    )?;
+    validate_recent_blocks(constants, &weight_proof.recent_chain_data, &summaries)?;
+    Ok(get_fork_point(blocks, &summaries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_random_matches_cpython() {
+        //Expected values from CPython's random.Random(bytes(range(32)))
+        let seed: Vec<u8> = (0..32u8).collect();
+        let mut rng = PythonRandom::new(&seed);
+        assert_eq!(rng.random(), 0.9592884430034848);
+        assert_eq!(rng.random(), 0.904383003978874);
+        assert_eq!(rng.random(), 0.6544723243938474);
+        let below: Vec<usize> = (0..5).map(|_| rng.randbelow(10)).collect();
+        assert_eq!(below, vec![8, 3, 9, 6, 2]);
+        //random.Random(bytes(32)), then getrandbits(32)
+        let mut rng = PythonRandom::new(&[0u8; 32]);
+        assert_eq!(rng.random(), 0.279945442455909);
+        assert_eq!(rng.next_u32(), 2581610280);
+    }
+
+    #[test]
+    fn test_sample_sub_epoch_bounds() {
+        let weights = Some(vec![100u128, 250]);
+        assert!(!sample_sub_epoch(100, 200, &weights));
+        assert!(sample_sub_epoch(99, 200, &weights));
+        assert!(!sample_sub_epoch(200, 250, &weights));
+        assert!(sample_sub_epoch(200, 251, &weights));
+        assert!(sample_sub_epoch(0, 1, &None));
+    }
+}
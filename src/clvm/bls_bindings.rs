@@ -1,6 +1,7 @@
-use crate::blockchain::sized_bytes::{Bytes48, Bytes96, SizedBytes};
-use blst::min_pk::{PublicKey, Signature};
+use crate::blockchain::sized_bytes::{Bytes32, Bytes48, Bytes96, SizedBytes};
+use blst::min_pk::{AggregatePublicKey, AggregateSignature, PublicKey, SecretKey, Signature};
 use blst::BLST_ERROR;
+use std::error::Error;
 
 //const BASIC_SCHEME_DST: &[u8; 43] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
 const AUG_SCHEME_DST: &[u8; 43] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
@@ -8,8 +9,14 @@ const AUG_SCHEME_DST: &[u8; 43] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_"
 // const AUG_SCHEME_POP_DST: &[u8; 43] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
 
 pub fn verify_signature(public_key: &Bytes48, msg: &[u8], signature: &Bytes96) -> bool {
-    let sig = Signature::from_bytes(signature.to_bytes().as_slice()).unwrap();
-    let pk = PublicKey::from_bytes(public_key.to_bytes().as_slice()).unwrap();
+    let sig = match Signature::from_bytes(signature.to_bytes().as_slice()) {
+        Ok(sig) => sig,
+        Err(_) => return false,
+    };
+    let pk = match PublicKey::from_bytes(public_key.to_bytes().as_slice()) {
+        Ok(pk) => pk,
+        Err(_) => return false,
+    };
     match sig.verify(
         true,
         &msg,
@@ -96,3 +103,56 @@ pub fn aggregate_verify_signature(
         }
     }
 }
+
+pub fn aggregate_signatures(signatures: &Vec<Bytes96>) -> Result<Bytes96, Box<dyn Error>> {
+    let mut sigs: Vec<Signature> = Vec::new();
+    for signature in signatures {
+        match Signature::from_bytes(signature.to_bytes().as_slice()) {
+            Ok(sig) => sigs.push(sig),
+            Err(error) => return Err(format!("Invalid Signature: {:?}", error).into()),
+        }
+    }
+    let _sigs: Vec<&Signature> = sigs.iter().map(|e| e).collect();
+    match AggregateSignature::aggregate(_sigs.as_slice(), true) {
+        Ok(agg_sig) => Ok(Bytes96::new(agg_sig.to_signature().to_bytes().to_vec())),
+        Err(error) => Err(format!("Failed to Aggregate Signatures: {:?}", error).into()),
+    }
+}
+
+pub fn key_gen(seed: &[u8]) -> Result<Bytes32, Box<dyn Error>> {
+    match SecretKey::key_gen_v3(seed, &[]) {
+        Ok(secret_key) => Ok(Bytes32::new(secret_key.to_bytes().to_vec())),
+        Err(error) => Err(format!("Failed to Generate Key: {:?}", error).into()),
+    }
+}
+
+pub fn derive_child_sk(secret_key: &Bytes32, index: u32) -> Result<Bytes32, Box<dyn Error>> {
+    match SecretKey::from_bytes(secret_key.to_bytes().as_slice()) {
+        Ok(secret_key) => Ok(Bytes32::new(
+            secret_key.derive_child_eip2333(index).to_bytes().to_vec(),
+        )),
+        Err(error) => Err(format!("Invalid Secret Key: {:?}", error).into()),
+    }
+}
+
+pub fn secret_key_to_public_key(secret_key: &Bytes32) -> Result<Bytes48, Box<dyn Error>> {
+    match SecretKey::from_bytes(secret_key.to_bytes().as_slice()) {
+        Ok(secret_key) => Ok(Bytes48::new(secret_key.sk_to_pk().compress().to_vec())),
+        Err(error) => Err(format!("Invalid Secret Key: {:?}", error).into()),
+    }
+}
+
+pub fn aggregate_public_keys(public_keys: &Vec<&Bytes48>) -> Result<Bytes48, Box<dyn Error>> {
+    let mut keys: Vec<PublicKey> = Vec::new();
+    for key in public_keys {
+        match PublicKey::from_bytes(key.to_bytes().as_slice()) {
+            Ok(pk) => keys.push(pk),
+            Err(error) => return Err(format!("Invalid Public Key: {:?}", error).into()),
+        }
+    }
+    let _keys: Vec<&PublicKey> = keys.iter().map(|e| e).collect();
+    match AggregatePublicKey::aggregate(_keys.as_slice(), false) {
+        Ok(agg_key) => Ok(Bytes48::new(agg_key.to_public_key().compress().to_vec())),
+        Err(error) => Err(format!("Failed to Aggregate Public Keys: {:?}", error).into()),
+    }
+}
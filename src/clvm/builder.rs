@@ -0,0 +1,99 @@
+use crate::clvm::program::Program;
+
+pub const QUOTE_KW: u8 = 1;
+pub const APPLY_KW: u8 = 2;
+pub const CONS_KW: u8 = 4;
+
+#[macro_export]
+macro_rules! list {
+    ($($item:expr),* $(,)?) => {
+        $crate::clvm::program::Program::from(
+            vec![$($crate::clvm::program::Program::from($item)),*]
+        )
+    };
+}
+
+//Builds a Program from a tree of Rust values, [a, b, c] is a proper list and (a . b) is a pair.
+//Items are token trees so expressions with spaces or operators need to be wrapped in parentheses
+#[macro_export]
+macro_rules! clvm {
+    (( $first:tt . $rest:tt )) => {
+        $crate::clvm::builder::cons($crate::clvm!($first), $crate::clvm!($rest))
+    };
+    ([ $($item:tt),* $(,)? ]) => {
+        $crate::clvm::program::Program::from(
+            vec![$($crate::clvm!($item)),*]
+        )
+    };
+    ($value:expr) => {
+        $crate::clvm::program::Program::from($value)
+    };
+}
+
+pub fn nil() -> Program {
+    Program::null()
+}
+
+pub fn cons<F: Into<Program>, R: Into<Program>>(first: F, rest: R) -> Program {
+    first.into().cons(&rest.into())
+}
+
+pub fn quote<T: Into<Program>>(value: T) -> Program {
+    cons(QUOTE_KW, value)
+}
+
+pub fn apply<P: Into<Program>, E: Into<Program>>(puzzle: P, environment: E) -> Program {
+    let (puzzle, environment): (Program, Program) = (puzzle.into(), environment.into());
+    list![APPLY_KW, puzzle, environment]
+}
+
+pub fn cons_op<F: Into<Program>, R: Into<Program>>(first: F, rest: R) -> Program {
+    let (first, rest): (Program, Program) = (first.into(), rest.into());
+    list![CONS_KW, first, rest]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clvm, list};
+
+    #[test]
+    fn test_list_macro() {
+        assert_eq!(
+            hex::encode(list![1u8, 2u8, 3u8].serialized()),
+            "ff01ff02ff0380"
+        );
+        assert_eq!(
+            hex::encode(list!["abc", 0u8].serialized()),
+            "ff83616263ff8080"
+        );
+        assert_eq!(hex::encode(list![128u8,].serialized()), "ff82008080");
+    }
+
+    #[test]
+    fn test_clvm_macro() {
+        assert_eq!(hex::encode(clvm!((1u8 . 2u8)).serialized()), "ff0102");
+        assert_eq!(
+            hex::encode(clvm!([1u8, [2u8, 3u8], (4u8 . 5u8)]).serialized()),
+            "ff01ffff02ff0380ffff040580"
+        );
+        assert_eq!(
+            hex::encode(clvm!([(-1i32), "a"]).serialized()),
+            "ff81ffff6180"
+        );
+        assert_eq!(clvm!((1u8 . [2u8])), list![1u8, 2u8]);
+    }
+
+    #[test]
+    fn test_operator_helpers() {
+        assert_eq!(hex::encode(quote(1u8).serialized()), "ff0101");
+        assert_eq!(
+            hex::encode(apply(quote(1u8), nil()).serialized()),
+            "ff02ffff0101ff8080"
+        );
+        assert_eq!(
+            hex::encode(cons_op(1u8, 2u8).serialized()),
+            "ff04ff01ff0280"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::blockchain::sized_bytes::*;
+use crate::clvm::disassembler::is_canonical_int;
+use crate::clvm::program::Program;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::error::Error;
+
+pub trait ToClvm {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>>;
+}
+
+pub trait FromClvm: Sized {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>>;
+}
+
+pub fn int_to_atom(value: &BigInt) -> Vec<u8> {
+    if value == &BigInt::from(0) {
+        Vec::new()
+    } else {
+        value.to_signed_bytes_be()
+    }
+}
+
+pub fn atom_to_canonical_int(atom: &[u8]) -> Result<BigInt, Box<dyn Error>> {
+    if !is_canonical_int(atom) {
+        return Err(format!("Non-canonical integer encoding: {}", hex::encode(atom)).into());
+    }
+    Ok(BigInt::from_signed_bytes_be(atom))
+}
+
+impl ToClvm for Program {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        Ok(self.clone())
+    }
+}
+impl FromClvm for Program {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        Ok(program.clone())
+    }
+}
+
+macro_rules! impl_clvm_ints {
+    ($($name: ident, $to_fn: ident);*) => {
+        $(
+            impl ToClvm for $name {
+                fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+                    Ok(int_to_atom(&BigInt::from(*self)).into())
+                }
+            }
+            impl FromClvm for $name {
+                fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+                    match program.as_vec() {
+                        Some(atom) => match atom_to_canonical_int(&atom)?.$to_fn() {
+                            Some(value) => Ok(value),
+                            None => Err(format!(
+                                "Integer {} out of range for {}",
+                                BigInt::from_signed_bytes_be(&atom),
+                                stringify!($name)
+                            )
+                            .into()),
+                        },
+                        None => Err(format!("Expected atom for {}, found pair", stringify!($name)).into()),
+                    }
+                }
+            }
+        )*
+    };
+    ()=>{};
+}
+
+impl_clvm_ints!(
+    u8, to_u8;
+    u16, to_u16;
+    u32, to_u32;
+    u64, to_u64;
+    u128, to_u128;
+    i8, to_i8;
+    i16, to_i16;
+    i32, to_i32;
+    i64, to_i64;
+    i128, to_i128
+);
+
+macro_rules! impl_clvm_sized_bytes {
+    ($($name: ident, $size:expr);*) => {
+        $(
+            impl ToClvm for $name {
+                fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+                    Ok(self.to_bytes().into())
+                }
+            }
+            impl FromClvm for $name {
+                fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+                    match program.as_vec() {
+                        Some(atom) => {
+                            if $size != 0 && atom.len() != $size {
+                                return Err(format!(
+                                    "Expected {} bytes for {}, found {}",
+                                    $size,
+                                    stringify!($name),
+                                    atom.len()
+                                )
+                                .into());
+                            }
+                            Ok($name::new(atom))
+                        }
+                        None => Err(format!("Expected atom for {}, found pair", stringify!($name)).into()),
+                    }
+                }
+            }
+        )*
+    };
+    ()=>{};
+}
+
+impl_clvm_sized_bytes!(
+    UnsizedBytes, 0;
+    Bytes4, 4;
+    Bytes8, 8;
+    Bytes16, 16;
+    Bytes32, 32;
+    Bytes48, 48;
+    Bytes96, 96;
+    Bytes192, 192
+);
+
+impl ToClvm for String {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        Ok(self.as_bytes().to_vec().into())
+    }
+}
+impl FromClvm for String {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        match program.as_vec() {
+            Some(atom) => Ok(String::from_utf8(atom)?),
+            None => Err("Expected atom for String, found pair".into()),
+        }
+    }
+}
+
+impl<T: ToClvm> ToClvm for Vec<T> {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        let mut items: Vec<Program> = Vec::new();
+        for item in self {
+            items.push(item.to_clvm()?);
+        }
+        Ok(items.into())
+    }
+}
+impl<T: FromClvm> FromClvm for Vec<T> {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        let mut rtn: Vec<T> = Vec::new();
+        let mut current = program.clone();
+        loop {
+            match current.as_pair() {
+                Some((first, rest)) => {
+                    rtn.push(T::from_clvm(&first)?);
+                    current = rest;
+                }
+                None => break,
+            }
+        }
+        expect_nil(&current)?;
+        Ok(rtn)
+    }
+}
+
+impl<T: ToClvm> ToClvm for Option<T> {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        match self {
+            Some(value) => value.to_clvm(),
+            None => Ok(Program::null()),
+        }
+    }
+}
+impl<T: FromClvm> FromClvm for Option<T> {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        if is_nil(program) {
+            Ok(None)
+        } else {
+            Ok(Some(T::from_clvm(program)?))
+        }
+    }
+}
+
+impl<A: ToClvm, B: ToClvm> ToClvm for (A, B) {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        Ok(self.0.to_clvm()?.cons(&self.1.to_clvm()?))
+    }
+}
+impl<A: FromClvm, B: FromClvm> FromClvm for (A, B) {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        match program.as_pair() {
+            Some((first, rest)) => Ok((A::from_clvm(&first)?, B::from_clvm(&rest)?)),
+            None => Err("Expected pair for tuple, found atom".into()),
+        }
+    }
+}
+
+impl<A: ToClvm, B: ToClvm, C: ToClvm> ToClvm for (A, B, C) {
+    fn to_clvm(&self) -> Result<Program, Box<dyn Error>> {
+        Ok(self
+            .0
+            .to_clvm()?
+            .cons(&self.1.to_clvm()?.cons(&self.2.to_clvm()?)))
+    }
+}
+impl<A: FromClvm, B: FromClvm, C: FromClvm> FromClvm for (A, B, C) {
+    fn from_clvm(program: &Program) -> Result<Self, Box<dyn Error>> {
+        let (a, (b, c)) = <(A, (B, C))>::from_clvm(program)?;
+        Ok((a, b, c))
+    }
+}
+
+pub fn is_nil(program: &Program) -> bool {
+    match program.as_vec() {
+        Some(atom) => atom.is_empty(),
+        None => false,
+    }
+}
+
+pub fn expect_nil(program: &Program) -> Result<(), Box<dyn Error>> {
+    if is_nil(program) {
+        Ok(())
+    } else {
+        Err(format!("Expected nil terminator, found {}", program).into())
+    }
+}
+
+pub fn curried_args_from_clvm(
+    program: &Program,
+    count: usize,
+) -> Result<Vec<Program>, Box<dyn Error>> {
+    //Parses the (c (q . arg1) (c (q . arg2) 1)) environment built by curry
+    let mut rtn: Vec<Program> = Vec::new();
+    let mut current = program.clone();
+    for _ in 0..count {
+        let items: Vec<Program> = current.iter().collect();
+        if items.len() != 3 || items[0].as_vec() != Some(vec![4u8]) {
+            return Err(format!("Expected curried argument, found {}", current).into());
+        }
+        match items[1].as_pair() {
+            Some((quote, value)) if quote.as_vec() == Some(vec![1u8]) => rtn.push(value),
+            _ => {
+                return Err(format!("Expected quoted curried argument, found {}", items[1]).into())
+            }
+        }
+        current = items[2].clone();
+    }
+    if current.as_vec() != Some(vec![1u8]) {
+        return Err(format!("Expected end of curried arguments, found {}", current).into());
+    }
+    Ok(rtn)
+}
+
+pub fn curried_args_to_clvm(args: Vec<Program>) -> Program {
+    let mut rtn: Program = 1u8.into();
+    for arg in args.into_iter().rev() {
+        rtn = vec![Program::from(4u8), Program::from(1u8).cons(&arg), rtn].into();
+    }
+    rtn
+}
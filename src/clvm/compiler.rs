@@ -0,0 +1,158 @@
+use crate::clvm::serialized_program::SerializedProgram;
+use clvm_rs::allocator::Allocator as Allocator2;
+use clvm_rs::node::Node as Node2;
+use clvm_rs::serialize::node_to_bytes as serialize2;
+use clvm_tools_rs::classic::clvm_tools::clvmc::compile_clvm_text;
+use clvm_tools_rs::compiler::compiler::DefaultCompilerOpts;
+use clvm_tools_rs::compiler::comptypes::CompilerOpts;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub fn compile_clsp(
+    source: &str,
+    include_paths: &[PathBuf],
+) -> Result<SerializedProgram, Box<dyn Error>> {
+    compile_clsp_named(source, "*inline*", include_paths)
+}
+
+pub fn compile_clsp_file(
+    path: &Path,
+    include_paths: &[PathBuf],
+) -> Result<SerializedProgram, Box<dyn Error>> {
+    let source = fs::read_to_string(path)?;
+    let mut search_paths: Vec<PathBuf> = Vec::new();
+    if let Some(parent) = path.parent() {
+        search_paths.push(parent.to_path_buf());
+    }
+    search_paths.extend(include_paths.iter().cloned());
+    compile_clsp_named(
+        source.as_str(),
+        path.to_string_lossy().as_ref(),
+        search_paths.as_slice(),
+    )
+}
+
+fn compile_clsp_named(
+    source: &str,
+    input_path: &str,
+    include_paths: &[PathBuf],
+) -> Result<SerializedProgram, Box<dyn Error>> {
+    let mut allocator = Allocator2::new();
+    let search_paths: Vec<String> = include_paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let opts: Rc<dyn CompilerOpts> =
+        Rc::new(DefaultCompilerOpts::new(input_path)).set_search_paths(&search_paths);
+    let mut symbol_table: HashMap<String, String> = HashMap::new();
+    let ptr = match compile_clvm_text(&mut allocator, opts, &mut symbol_table, source, input_path) {
+        Ok(ptr) => ptr,
+        Err(error) => {
+            return Err(format!("Failed to compile {}: {:?}", input_path, error).into());
+        }
+    };
+    match serialize2(&Node2::new(&mut allocator, ptr)) {
+        Ok(bytes) => Ok(SerializedProgram::from_bytes(&bytes)),
+        Err(error) => Err(format!("Failed to serialize {}: {:?}", input_path, error).into()),
+    }
+}
+
+pub fn hex_path_for_clsp(path: &Path) -> PathBuf {
+    let mut hex_path = path.as_os_str().to_owned();
+    hex_path.push(".hex");
+    PathBuf::from(hex_path)
+}
+
+pub fn sync_clsp_hex(path: &Path, include_paths: &[PathBuf]) -> Result<bool, Box<dyn Error>> {
+    //Intended for build.rs, recompiles the source and rewrites <name>.clsp.hex when it is stale
+    let compiled = compile_clsp_file(path, include_paths)?.to_string();
+    let hex_path = hex_path_for_clsp(path);
+    let existing = match fs::read_to_string(&hex_path) {
+        Ok(hex) => hex.trim().to_string(),
+        Err(_) => String::new(),
+    };
+    if existing == compiled {
+        return Ok(false);
+    }
+    fs::write(&hex_path, format!("{}\n", compiled))?;
+    Ok(true)
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ClspSync {
+    pub updated: Vec<PathBuf>,
+    pub watched: Vec<PathBuf>,
+}
+
+pub fn sync_clsp_hex_dir(
+    dir: &Path,
+    include_paths: &[PathBuf],
+) -> Result<ClspSync, Box<dyn Error>> {
+    //The build script should emit cargo:rerun-if-changed for every watched path
+    let mut sync = ClspSync::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "clsp").unwrap_or(false) {
+            let mut search_paths: Vec<PathBuf> = Vec::new();
+            if let Some(parent) = path.parent() {
+                search_paths.push(parent.to_path_buf());
+            }
+            search_paths.extend(include_paths.iter().cloned());
+            collect_clsp_includes(&path, &search_paths, &mut sync.watched)?;
+            if sync_clsp_hex(&path, include_paths)? {
+                sync.updated.push(path);
+            }
+        }
+    }
+    Ok(sync)
+}
+
+fn collect_clsp_includes(
+    path: &Path,
+    search_paths: &[PathBuf],
+    watched: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    if watched.iter().any(|p| p == path) {
+        return Ok(());
+    }
+    watched.push(path.to_path_buf());
+    let source = fs::read_to_string(path)?;
+    for name in clsp_include_names(&source) {
+        if let Some(include) = search_paths
+            .iter()
+            .map(|dir| dir.join(&name))
+            .find(|candidate| candidate.is_file())
+        {
+            collect_clsp_includes(&include, search_paths, watched)?;
+        }
+    }
+    Ok(())
+}
+
+fn clsp_include_names(source: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = source;
+    while let Some(index) = rest.find("(include") {
+        rest = &rest[index + "(include".len()..];
+        if !rest.starts_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let name: String = rest
+            .trim_start()
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != ')')
+            .collect();
+        let name = name.trim_matches('"').to_string();
+        if !name.is_empty() {
+            names.push(name);
+        }
+    }
+    names
+}
+
+pub fn load_clsp_hex(hex: &str) -> SerializedProgram {
+    SerializedProgram::from_hex(hex.trim().to_string())
+}
@@ -1,4 +1,5 @@
 use crate::clvm::program::Program;
+use crate::clvm::serialize::node_from_bytes_backrefs;
 use crate::clvm::serialized_program::SerializedProgram;
 use clvm_rs::allocator::Allocator as Allocator2;
 use clvm_rs::node::Node as Node2;
@@ -31,7 +32,7 @@ lazy_static! {
     pub static ref UNCURRY_PATTERN_CORE: SerializedProgram = assemble("(c (q . (: . parm)) (: . core))");
 }
 
-const BYTE_MATCH: [u8; 1] = [81 as u8];
+const ENV_ATOM: [u8; 1] = [1 as u8];
 const ATOM_MATCH: [u8; 1] = ['$' as u8];
 const SEXP_MATCH: [u8; 1] = [':' as u8];
 
@@ -42,14 +43,14 @@ pub fn uncurry(
     let pattern_func =
         node_from_bytes(&mut alloc, &UNCURRY_PATTERN_FUNCTION.to_bytes().as_slice())?;
     let pattern_core = node_from_bytes(&mut alloc, &UNCURRY_PATTERN_CORE.to_bytes().as_slice())?;
-    let sexp = node_from_bytes(&mut alloc, &curried_program.to_bytes().as_slice())?;
-    match match_sexp(&mut alloc, pattern_func, sexp, HashMap::new()) {
+    let sexp = node_from_bytes_backrefs(&mut alloc, &curried_program.to_bytes().as_slice())?;
+    match match_sexp(&alloc, pattern_func, sexp, HashMap::new()) {
         Some(func_results) => {
             let func = *func_results.get("function").unwrap();
             let mut core = *func_results.get("core").unwrap();
             let mut args: Vec<NodePtr> = Vec::new();
             loop {
-                match match_sexp(&mut alloc, pattern_core.clone(), core, HashMap::new()) {
+                match match_sexp(&alloc, pattern_core.clone(), core, HashMap::new()) {
                     Some(core_results) => {
                         args.push(*core_results.get("parm").unwrap());
                         core = core_results.get("core").unwrap().clone();
@@ -57,28 +58,23 @@ pub fn uncurry(
                     None => break,
                 }
             }
-            match &alloc.sexp(core) {
-                Atom(buf) => {
-                    let bytes = alloc.buf(buf).to_vec();
-                    let _byte_match_vec = Vec::from(BYTE_MATCH);
-                    match bytes {
-                        _byte_match_vec => {
-                            let node_ptr = match &alloc.new_concat(args.len(), args.as_slice()) {
-                                Ok(value) => *value,
-                                Err(error) => {
-                                    return Err(error.1.clone().into());
-                                }
-                            };
-                            Ok(Some((
-                                SerializedProgram::from_bytes(&node_to_bytes(&Node::new(
-                                    &alloc, func,
-                                ))?),
-                                SerializedProgram::from_bytes(&node_to_bytes(&Node::new(
-                                    &mut alloc, node_ptr,
-                                ))?),
-                            )))
-                        }
+            match alloc.sexp(core) {
+                Atom(buf) if alloc.buf(&buf) == ENV_ATOM.as_slice() => {
+                    let mut args_list = alloc.null();
+                    for arg in args.iter().rev() {
+                        args_list = match alloc.new_pair(*arg, args_list) {
+                            Ok(value) => value,
+                            Err(error) => {
+                                return Err(error.1.into());
+                            }
+                        };
                     }
+                    Ok(Some((
+                        SerializedProgram::from_bytes(&node_to_bytes(&Node::new(&alloc, func))?),
+                        SerializedProgram::from_bytes(&node_to_bytes(&Node::new(
+                            &alloc, args_list,
+                        ))?),
+                    )))
                 }
                 _ => Ok(None),
             }
@@ -112,7 +108,7 @@ fn make_args(args: Vec<Program>) -> Program {
 }
 
 pub fn match_sexp<'a>(
-    allocator: &'a mut Allocator,
+    allocator: &'a Allocator,
     pattern: NodePtr,
     sexp: NodePtr,
     known_bindings: HashMap<String, NodePtr>,
@@ -218,7 +214,7 @@ pub fn match_sexp<'a>(
 }
 
 pub fn unify_bindings<'a>(
-    allocator: &'a mut Allocator,
+    allocator: &'a Allocator,
     bindings: HashMap<String, NodePtr>,
     new_key: &Vec<u8>,
     new_value: NodePtr,
@@ -243,7 +239,7 @@ pub fn unify_bindings<'a>(
     }
 }
 
-pub fn equal_to<'a>(allocator: &'a mut Allocator, first_: NodePtr, second_: NodePtr) -> bool {
+pub fn equal_to<'a>(allocator: &'a Allocator, first_: NodePtr, second_: NodePtr) -> bool {
     let mut first = first_;
     let mut second = second_;
 
@@ -267,3 +263,43 @@ pub fn equal_to<'a>(allocator: &'a mut Allocator, first_: NodePtr, second_: Node
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::sized_bytes::Bytes48;
+    use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::MOD;
+
+    #[test]
+    fn test_curry_uncurry_round_trip() {
+        let mod_program = MOD.to_program().unwrap();
+        let args: Vec<Program> = vec![
+            Bytes48::from(vec![7u8; 48]).into(),
+            42u64.into(),
+            Program::null(),
+            vec![Program::from(1u8), Program::from(2u8)].into(),
+        ];
+        let curried = mod_program.curry(args.clone()).unwrap();
+        assert_ne!(curried.tree_hash(), mod_program.tree_hash());
+        let (uncurried_mod, uncurried_args) = uncurry(&curried.clone().into()).unwrap().unwrap();
+        assert_eq!(uncurried_mod.to_program().unwrap(), mod_program);
+        assert_eq!(
+            uncurried_args.to_program().unwrap().as_list().unwrap(),
+            args
+        );
+        let (program_mod, program_args) = curried.uncurry().unwrap();
+        assert_eq!(program_mod, mod_program);
+        assert_eq!(program_args.as_list().unwrap(), args);
+    }
+
+    #[test]
+    fn test_uncurry_without_curried_args() {
+        let atom: Program = 1u8.into();
+        assert!(uncurry(&atom.clone().into()).unwrap().is_none());
+        let (program_mod, program_args) = atom.uncurry().unwrap();
+        assert_eq!(program_mod, atom);
+        assert_eq!(program_args, Program::null());
+        let list: Program = vec![Program::from(1u8), Program::from(2u8)].into();
+        assert!(uncurry(&list.into()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::clvm::serialize::node_from_bytes_backrefs;
+use clvmr::allocator::{Allocator, NodePtr, SExp};
+use hex::encode;
+use lazy_static::lazy_static;
+use num_bigint::BigInt;
+use std::collections::HashMap;
+use std::error::Error;
+
+const KEYWORDS: [&str; 37] = [
+    ".",
+    "q",
+    "a",
+    "i",
+    "c",
+    "f",
+    "r",
+    "l",
+    "x",
+    "=",
+    ">s",
+    "sha256",
+    "substr",
+    "strlen",
+    "concat",
+    ".",
+    "+",
+    "-",
+    "*",
+    "/",
+    "divmod",
+    ">",
+    "ash",
+    "lsh",
+    "logand",
+    "logior",
+    "logxor",
+    "lognot",
+    ".",
+    "point_add",
+    "pubkey_for_exp",
+    ".",
+    "not",
+    "any",
+    "all",
+    ".",
+    "softfork",
+];
+
+lazy_static! {
+    pub static ref KEYWORD_FROM_ATOM: HashMap<Vec<u8>, String> = {
+        let mut map = HashMap::new();
+        for (opcode, keyword) in KEYWORDS.iter().enumerate() {
+            if *keyword != "." {
+                map.insert(vec![opcode as u8], keyword.to_string());
+            }
+        }
+        map
+    };
+}
+
+pub fn disassemble(
+    serialized: &[u8],
+    keywords: Option<&HashMap<Vec<u8>, String>>,
+) -> Result<String, Box<dyn Error>> {
+    let mut alloc = Allocator::new();
+    let node = node_from_bytes_backrefs(&mut alloc, serialized)?;
+    let mut output = String::new();
+    disassemble_node(&alloc, node, keywords, true, &mut output);
+    Ok(output)
+}
+
+pub fn disassemble_node(
+    alloc: &Allocator,
+    node: NodePtr,
+    keywords: Option<&HashMap<Vec<u8>, String>>,
+    allow_keyword: bool,
+    output: &mut String,
+) {
+    disassemble_node_limited(alloc, node, keywords, allow_keyword, output, usize::MAX);
+}
+
+pub fn disassemble_node_limited(
+    alloc: &Allocator,
+    node: NodePtr,
+    keywords: Option<&HashMap<Vec<u8>, String>>,
+    allow_keyword: bool,
+    output: &mut String,
+    max_length: usize,
+) {
+    //Stops walking the tree once output is longer than max_length, callers truncate the tail
+    if output.len() > max_length {
+        return;
+    }
+    match alloc.sexp(node) {
+        SExp::Pair(first, rest) => {
+            output.push('(');
+            disassemble_node_limited(alloc, first, keywords, true, output, max_length);
+            let mut current = rest;
+            loop {
+                if output.len() > max_length {
+                    return;
+                }
+                match alloc.sexp(current) {
+                    SExp::Pair(first, rest) => {
+                        output.push(' ');
+                        disassemble_node_limited(alloc, first, keywords, false, output, max_length);
+                        current = rest;
+                    }
+                    SExp::Atom(_) => {
+                        if !alloc.atom(current).is_empty() {
+                            output.push_str(" . ");
+                            disassemble_node_limited(
+                                alloc, current, keywords, false, output, max_length,
+                            );
+                        }
+                        break;
+                    }
+                }
+            }
+            output.push(')');
+        }
+        SExp::Atom(_) => {
+            let atom = alloc.atom(node);
+            if atom.len() > max_length {
+                output.push_str(format!("0x{}", encode(&atom[..max_length])).as_str());
+            } else {
+                output.push_str(atom_to_string(atom, keywords, allow_keyword).as_str());
+            }
+        }
+    }
+}
+
+pub fn atom_to_string(
+    atom: &[u8],
+    keywords: Option<&HashMap<Vec<u8>, String>>,
+    allow_keyword: bool,
+) -> String {
+    if atom.is_empty() {
+        return "()".to_string();
+    }
+    if allow_keyword {
+        if let Some(keyword) = keywords.and_then(|k| k.get(atom)) {
+            return keyword.clone();
+        }
+    }
+    if atom.len() > 2 {
+        match std::str::from_utf8(atom) {
+            Ok(as_str) if as_str.chars().all(is_printable) => {
+                if as_str.contains('"') && !as_str.contains('\'') {
+                    format!("'{}'", as_str)
+                } else {
+                    format!("\"{}\"", as_str)
+                }
+            }
+            _ => format!("0x{}", encode(atom)),
+        }
+    } else if is_canonical_int(atom) {
+        BigInt::from_signed_bytes_be(atom).to_string()
+    } else {
+        format!("0x{}", encode(atom))
+    }
+}
+
+pub fn is_canonical_int(atom: &[u8]) -> bool {
+    match atom.len() {
+        0 => true,
+        1 => atom[0] != 0,
+        _ => {
+            !((atom[0] == 0x00 && atom[1] & 0x80 == 0) || (atom[0] == 0xFF && atom[1] & 0x80 != 0))
+        }
+    }
+}
+
+fn is_printable(c: char) -> bool {
+    c.is_ascii_graphic() || c == ' ' || ('\t'..='\r').contains(&c)
+}
@@ -1,6 +1,14 @@
 pub mod bls_bindings;
+pub mod builder;
+pub mod clvm_traits;
+pub mod compiler;
 pub mod condition_utils;
 pub mod curry_utils;
+pub mod disassembler;
+pub mod p2_delegated_puzzle_or_hidden_puzzle;
 pub mod program;
+pub mod puzzle_layer;
+pub mod serialize;
 pub mod serialized_program;
+pub mod trace;
 pub mod utils;
@@ -0,0 +1,35 @@
+use crate::blockchain::sized_bytes::{Bytes32, Bytes48};
+use crate::clvm::program::Program;
+use crate::clvm::serialized_program::SerializedProgram;
+use lazy_static::lazy_static;
+use std::error::Error;
+
+lazy_static! {
+    pub static ref MOD: SerializedProgram = SerializedProgram::from_hex("ff02ffff01ff02ffff03ff0bffff01ff02ffff03ffff09ff05ffff1dff0bffff1effff0bff0bffff02ff06ffff04ff02ffff04ff17ff8080808080808080ffff01ff02ff17ff2f80ffff01ff088080ff0180ffff01ff04ffff04ff04ffff04ff05ffff04ffff02ff06ffff04ff02ffff04ff17ff80808080ff80808080ffff02ff17ff2f808080ff0180ffff04ffff01ff32ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff06ffff04ff02ffff04ff09ff80808080ffff02ff06ffff04ff02ffff04ff0dff8080808080ffff01ff0bffff0101ff058080ff0180ff018080".to_string());
+}
+
+pub fn puzzle_for_synthetic_public_key(
+    synthetic_public_key: &Bytes48,
+) -> Result<Program, Box<dyn Error>> {
+    MOD.clone()
+        .to_program()?
+        .curry(vec![synthetic_public_key.into()])
+}
+
+pub fn puzzle_hash_for_synthetic_public_key(
+    synthetic_public_key: &Bytes48,
+) -> Result<Bytes32, Box<dyn Error>> {
+    Ok(puzzle_for_synthetic_public_key(synthetic_public_key)?.tree_hash())
+}
+
+pub fn puzzle_for_conditions(conditions: Vec<Program>) -> Program {
+    Program::from(1u8).cons(&conditions.into())
+}
+
+pub fn solution_for_delegated_puzzle(delegated_puzzle: &Program, solution: &Program) -> Program {
+    vec![Program::null(), delegated_puzzle.clone(), solution.clone()].into()
+}
+
+pub fn solution_for_conditions(conditions: Vec<Program>) -> Program {
+    solution_for_delegated_puzzle(&puzzle_for_conditions(conditions), &Program::null())
+}
@@ -1,28 +1,30 @@
 use crate::blockchain::sized_bytes::*;
-use crate::clvm::curry_utils::{curry, uncurry};
+use crate::clvm::clvm_traits::{int_to_atom, FromClvm};
+use crate::clvm::curry_utils::{curry, match_sexp, uncurry};
+use crate::clvm::disassembler::{disassemble, KEYWORD_FROM_ATOM};
+use crate::clvm::serialize::{node_from_bytes_backrefs, node_to_bytes_backrefs, tree_hash_node};
 use crate::clvm::serialized_program::SerializedProgram;
 
-use clvm_rs::allocator::Allocator as Allocator2;
-use clvm_rs::serialize::node_from_bytes as deserialize2;
-use clvm_tools_rs::classic::clvm_tools::sha256tree::sha256tree;
 use clvmr::allocator::SExp::{Atom, Pair};
-use clvmr::allocator::{Allocator, SExp};
+use clvmr::allocator::{Allocator, NodePtr, SExp};
 use clvmr::node::Node;
-use clvmr::serialize::{node_from_bytes, node_to_bytes};
+use clvmr::serialize::node_to_bytes;
 use hex::encode;
 use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use serde::ser::StdError;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 
-#[derive(Debug)]
 pub struct Program {
     pub serialized: Vec<u8>,
-    alloc: Allocator,
-    nodeptr: i32,
+    alloc: Arc<Mutex<Allocator>>,
+    nodeptr: NodePtr,
+    owned: OnceLock<(Allocator, NodePtr)>,
 }
 impl Program {
     pub fn curry(&self, args: Vec<Program>) -> Result<Program, Box<dyn Error>> {
@@ -31,13 +33,25 @@ impl Program {
     }
 
     pub fn uncurry(&self) -> Result<(Program, Program), Box<dyn Error>> {
-        let serial_program = SerializedProgram::from_bytes(&self.serialized);
+        let serial_program = SerializedProgram::from_bytes(&self.serialized());
         match uncurry(&serial_program)? {
             Some((program, args)) => Ok((program.to_program()?, args.to_program()?)),
             None => Ok((serial_program.to_program()?, 0.into())),
         }
     }
 
+    pub fn matches(&self, pattern: &Program) -> Option<HashMap<String, Program>> {
+        let pattern_ptr = self.import(pattern).ok()?;
+        //Release the allocator before building programs, from_allocator locks it again
+        let bindings = match_sexp(&self.lock(), pattern_ptr, self.nodeptr, HashMap::new())?;
+        Some(
+            bindings
+                .into_iter()
+                .map(|(name, node)| (name, Program::from_allocator(self.alloc.clone(), node)))
+                .collect(),
+        )
+    }
+
     pub fn as_atom_list(&mut self) -> Vec<Vec<u8>> {
         let mut rtn: Vec<Vec<u8>> = Vec::new();
         let mut current = self.clone();
@@ -85,69 +99,78 @@ impl Program {
     }
 
     pub fn to_sexp(&self) -> SExp {
-        self.alloc.sexp(self.nodeptr)
+        self.lock().sexp(self.nodeptr)
     }
 
+    pub fn with_node<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Node) -> R,
+    {
+        let alloc = self.lock();
+        f(&Node::new(&alloc, self.nodeptr))
+    }
+
+    #[deprecated(note = "use Program::with_node()")]
     pub fn to_node(&self) -> Node {
-        Node::new(&self.alloc, self.nodeptr).clone()
+        //The shared allocator sits behind a lock, so the old API reads a private copy
+        let (alloc, nodeptr) = self.owned.get_or_init(|| {
+            let mut owned = Allocator::new();
+            let nodeptr = match node_from_bytes_backrefs(&mut owned, &self.serialized) {
+                Ok(node) => node,
+                Err(_) => owned.null(),
+            };
+            (owned, nodeptr)
+        });
+        Node::new(alloc, *nodeptr)
+    }
+
+    pub fn allocator(&self) -> Arc<Mutex<Allocator>> {
+        self.alloc.clone()
+    }
+
+    pub fn node_ptr(&self) -> NodePtr {
+        self.nodeptr
     }
 
     pub fn is_atom(&self) -> bool {
-        self.as_atom().is_some()
+        matches!(self.to_sexp(), Atom(_))
     }
 
     pub fn is_pair(&self) -> bool {
-        self.as_pair().is_some()
+        matches!(self.to_sexp(), Pair(_, _))
     }
 
     pub fn as_atom(&self) -> Option<Program> {
         match self.to_sexp() {
-            Atom(_) => Some(Program::new(self.alloc.atom(self.nodeptr).to_vec())),
+            Atom(_) => Some(self.clone()),
             _ => None,
         }
     }
     pub fn as_vec(&self) -> Option<Vec<u8>> {
         match self.to_sexp() {
-            Atom(_) => Some(self.alloc.atom(self.nodeptr).to_vec()),
+            Atom(_) => Some(self.lock().atom(self.nodeptr).to_vec()),
             _ => None,
         }
     }
 
     pub fn as_pair(&self) -> Option<(Program, Program)> {
         match self.to_sexp() {
-            Pair(p1, p2) => {
-                let left_node = Node::new(&self.alloc, p1);
-                let right_node = Node::new(&self.alloc, p2);
-                let left = match node_to_bytes(&left_node) {
-                    Ok(serial_data) => Program::new(serial_data),
-                    Err(_) => Program::new(Vec::new()),
-                };
-                let right = match node_to_bytes(&right_node) {
-                    Ok(serial_data) => Program::new(serial_data),
-                    Err(_) => Program::new(Vec::new()),
-                };
-                Some((left, right))
-            }
+            Pair(p1, p2) => Some((
+                Program::from_allocator(self.alloc.clone(), p1),
+                Program::from_allocator(self.alloc.clone(), p2),
+            )),
             _ => None,
         }
     }
 
     pub fn cons(&self, other: &Program) -> Program {
-        let mut alloc = Allocator::new();
-        let first = match node_from_bytes(&mut alloc, &self.serialized.as_slice()) {
+        let rest = match self.import(other) {
             Ok(ptr) => ptr,
-            Err(_) => alloc.null(),
+            Err(_) => return Program::null(),
         };
-        let rest = match node_from_bytes(&mut alloc, &other.serialized.as_slice()) {
-            Ok(ptr) => ptr,
-            Err(_) => alloc.null(),
-        };
-        match alloc.new_pair(first, rest) {
-            Ok(pair) => {
-                let node = Node::new(&alloc, pair);
-                let node_bytes = node_to_bytes(&node).unwrap();
-                Program::new(node_bytes)
-            }
+        let pair = self.lock().new_pair(self.nodeptr, rest);
+        match pair {
+            Ok(pair) => Program::from_allocator(self.alloc.clone(), pair),
             Err(_) => Program::null(),
         }
     }
@@ -158,12 +181,74 @@ impl Program {
                 atom.as_vec().unwrap().as_slice(),
             )),
             None => {
-                log::debug!("BAD INT: {:?}", self.serialized);
+                log::debug!("BAD INT: {:?}", self.serialized());
                 Err("Program is Pair not Atom".into())
             }
         }
     }
 
+    pub fn as_u64(&self) -> Result<u64, Box<dyn Error>> {
+        u64::from_clvm(self)
+    }
+
+    pub fn as_u32(&self) -> Result<u32, Box<dyn Error>> {
+        u32::from_clvm(self)
+    }
+
+    pub fn as_i64(&self) -> Result<i64, Box<dyn Error>> {
+        i64::from_clvm(self)
+    }
+
+    pub fn as_bytes32(&self) -> Result<Bytes32, Box<dyn Error>> {
+        Bytes32::from_clvm(self)
+    }
+
+    pub fn as_bytes48(&self) -> Result<Bytes48, Box<dyn Error>> {
+        Bytes48::from_clvm(self)
+    }
+
+    pub fn as_str(&self) -> Result<String, Box<dyn Error>> {
+        String::from_clvm(self)
+    }
+
+    pub fn as_list(&self) -> Result<Vec<Program>, Box<dyn Error>> {
+        match self.proper_list() {
+            Some(items) => Ok(items),
+            None => Err(format!("Expected proper list, found {}", self).into()),
+        }
+    }
+
+    pub fn proper_list(&self) -> Option<Vec<Program>> {
+        let mut rtn: Vec<Program> = Vec::new();
+        let mut current = self.clone();
+        loop {
+            match current.as_pair() {
+                Some((first, rest)) => {
+                    rtn.push(first);
+                    current = rest;
+                }
+                None => {
+                    return match current.as_vec() {
+                        Some(atom) if atom.is_empty() => Some(rtn),
+                        _ => None,
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn at(&self, path: &str) -> Result<Program, Box<dyn Error>> {
+        let mut current = self.clone();
+        for step in path.chars() {
+            current = match step {
+                'f' => current.first()?,
+                'r' => current.rest()?,
+                _ => return Err(format!("Invalid path step '{}' in {}", step, path).into()),
+            };
+        }
+        Ok(current)
+    }
+
     pub fn first(&self) -> Result<Program, Box<dyn Error>> {
         match self.as_pair() {
             Some((p1, _)) => Ok(p1),
@@ -178,37 +263,33 @@ impl Program {
         }
     }
 
+    pub fn disassemble(&self) -> String {
+        self.disassemble_with_keywords(Some(&KEYWORD_FROM_ATOM))
+    }
+
+    pub fn disassemble_with_keywords(&self, keywords: Option<&HashMap<Vec<u8>, String>>) -> String {
+        match disassemble(&self.serialized(), keywords) {
+            Ok(text) => text,
+            Err(_) => format!("0x{}", encode(&self.serialized())),
+        }
+    }
+
     pub fn iter(&self) -> ProgramIter {
         ProgramIter {
-            node: Node::new(&self.alloc, self.nodeptr).clone().into_iter(),
+            current: self.clone(),
         }
     }
 }
 
 impl Into<SerializedProgram> for Program {
     fn into(self) -> SerializedProgram {
-        SerializedProgram::from_bytes(&self.serialized)
+        SerializedProgram::from_bytes(&self.serialized())
     }
 }
 
 impl From<Vec<u8>> for Program {
     fn from(bytes: Vec<u8>) -> Self {
-        let mut alloc = Allocator::new();
-        let atom = match alloc.new_atom(bytes.as_slice()) {
-            Ok(ptr) => ptr,
-            Err(_) => alloc.null(),
-        };
-        let node = Node::new(&alloc, atom);
-        let node_bytes = match node_to_bytes(&node) {
-            Ok(n_bytes) => n_bytes,
-            Err(_) => Vec::new(),
-        };
-        let prog = Program {
-            serialized: node_bytes,
-            alloc: alloc,
-            nodeptr: atom,
-        };
-        prog
+        Program::from(&bytes)
     }
 }
 
@@ -219,39 +300,79 @@ impl From<&Vec<u8>> for Program {
             Ok(ptr) => ptr,
             Err(_) => alloc.null(),
         };
-        let node = Node::new(&alloc, atom);
-        let node_bytes = match node_to_bytes(&node) {
-            Ok(n_bytes) => n_bytes,
-            Err(_) => Vec::new(),
+        Program::from_allocator(Arc::new(Mutex::new(alloc)), atom)
+    }
+}
+
+impl From<&str> for Program {
+    fn from(text: &str) -> Self {
+        Program::from(text.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Program {
+    fn from(text: String) -> Self {
+        Program::from(text.into_bytes())
+    }
+}
+
+impl From<&Program> for Program {
+    fn from(program: &Program) -> Self {
+        program.clone()
+    }
+}
+
+impl From<Vec<Program>> for Program {
+    fn from(items: Vec<Program>) -> Self {
+        //Copy every item into the first item's allocator once, then build the list there
+        let alloc = match items.first() {
+            Some(first) => first.alloc.clone(),
+            None => return Program::null(),
         };
-        let prog = Program {
-            serialized: node_bytes,
-            alloc: alloc,
-            nodeptr: atom,
+        let mut copies: HashMap<*const Mutex<Allocator>, HashMap<NodePtr, NodePtr>> =
+            HashMap::new();
+        let mut nodes: Vec<NodePtr> = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            if Arc::ptr_eq(&alloc, &item.alloc) {
+                nodes.push(item.nodeptr);
+            } else {
+                let memo = copies.entry(Arc::as_ptr(&item.alloc)).or_default();
+                let (src, mut dst) = lock_both(&item.alloc, &alloc);
+                match copy_node_memo(&src, &mut dst, item.nodeptr, memo) {
+                    Ok(ptr) => nodes.push(ptr),
+                    Err(_) => return Program::null(),
+                }
+            }
+        }
+        let list = {
+            let mut dst = lock(&alloc);
+            let mut list = dst.null();
+            for node in nodes.into_iter().rev() {
+                list = match dst.new_pair(node, list) {
+                    Ok(pair) => pair,
+                    Err(_) => return Program::null(),
+                };
+            }
+            list
         };
-        prog
+        Program::from_allocator(alloc, list)
     }
 }
 
 impl TryFrom<(Program, Program)> for Program {
     type Error = Box<(dyn StdError + 'static)>;
     fn try_from((first, second): (Program, Program)) -> Result<Self, Self::Error> {
-        let mut alloc = Allocator::new();
-        let first = node_from_bytes(&mut alloc, &first.serialized.as_slice())?;
-        let rest = node_from_bytes(&mut alloc, &second.serialized.as_slice())?;
-        match alloc.new_pair(first, rest) {
-            Ok(pair) => {
-                let node = Node::new(&alloc, pair);
-                let node_bytes = node_to_bytes(&node)?;
-                Ok(Program::new(node_bytes))
-            }
+        let rest = first.import(&second)?;
+        let pair = first.lock().new_pair(first.nodeptr, rest);
+        match pair {
+            Ok(pair) => Ok(Program::from_allocator(first.alloc.clone(), pair)),
             Err(error) => Err(error.1.into()),
         }
     }
 }
 
 macro_rules! impl_sized_bytes {
-    ($($name: ident, $size:expr);*) => {
+    ($($name: ident);*) => {
         $(
             impl From<$name> for Program {
                 fn from(bytes: $name) -> Self {
@@ -265,22 +386,13 @@ macro_rules! impl_sized_bytes {
             }
             impl Into<$name> for Program {
                 fn into(self) -> $name {
-                    let vec_len = self.serialized.len();
-                    if vec_len == $size + 1 {
-                        $name::new(self.serialized[1..].to_vec())
-                    } else {
-                        $name::new(self.serialized)
-                    }
+                    (&self).into()
                 }
             }
             impl Into<$name> for &Program {
                 fn into(self) -> $name {
-                    let vec_len = self.serialized.len();
-                    if vec_len == $size + 1 {
-                        $name::new(self.serialized[1..].to_vec())
-                    } else {
-                        $name::new(self.serialized.clone())
-                    }
+                    //Pads or truncates like From<Vec<u8>>, the as_bytes32 style accessors reject bad lengths
+                    $name::from(self.as_vec().unwrap_or_default())
                 }
             }
         )*
@@ -289,37 +401,48 @@ macro_rules! impl_sized_bytes {
 }
 
 impl_sized_bytes!(
-    UnsizedBytes, 0;
-    Bytes4, 4;
-    Bytes8, 8;
-    Bytes16, 16;
-    Bytes32, 32;
-    Bytes48, 48;
-    Bytes96, 96;
-    Bytes192, 192
+    UnsizedBytes;
+    Bytes4;
+    Bytes8;
+    Bytes16;
+    Bytes32;
+    Bytes48;
+    Bytes96;
+    Bytes192
 );
 
 macro_rules! impl_ints {
-    ($($name: ident, $size: expr);*) => {
+    ($($name: ident, $to_fn: ident);*) => {
         $(
             impl From<$name> for Program {
                 fn from(int_val: $name) -> Self {
-                    if int_val == 0 {
-                        return Program::new(Vec::new());
-                    }
-                    let as_ary = int_val.to_be_bytes();
-                    let mut as_bytes = as_ary.as_slice();
-                    while as_bytes.len() > 1 && as_bytes[0] == ( if as_bytes[1] & 0x80 > 0{0xFF} else {0}) {
-                        as_bytes = &as_bytes[1..];
-                    }
-                    as_bytes.to_vec().into()
+                    int_to_atom(&BigInt::from(int_val)).into()
                 }
             }
+            //Kept for existing callers, prefer TryFrom<&Program> which reports bad atoms instead of panicking
             impl Into<$name> for Program {
                 fn into(self) -> $name {
-                    let mut byte_ary: [u8; $size] = [0; $size];
-                    byte_ary[..$size].clone_from_slice(&self.serialized);
-                    $name::from_be_bytes(byte_ary)
+                    match $name::try_from(&self) {
+                        Ok(value) => value,
+                        Err(error) => panic!("{}", error),
+                    }
+                }
+            }
+            impl TryFrom<&Program> for $name {
+                type Error = Box<dyn Error>;
+                fn try_from(program: &Program) -> Result<Self, Self::Error> {
+                    match program.as_vec() {
+                        Some(atom) => match BigInt::from_signed_bytes_be(&atom).$to_fn() {
+                            Some(value) => Ok(value),
+                            None => Err(format!(
+                                "Integer {} out of range for {}",
+                                BigInt::from_signed_bytes_be(&atom),
+                                stringify!($name)
+                            )
+                            .into()),
+                        },
+                        None => Err(format!("Expected atom for {}, found pair", stringify!($name)).into()),
+                    }
                 }
             }
         )*
@@ -328,32 +451,29 @@ macro_rules! impl_ints {
 }
 
 impl_ints!(
-    u8, 1;
-    u16, 2;
-    u32, 4;
-    u64, 8;
-    u128, 16;
-    i8, 1;
-    i16, 2;
-    i32, 4;
-    i64, 8;
-    i128, 16
+    u8, to_u8;
+    u16, to_u16;
+    u32, to_u32;
+    u64, to_u64;
+    u128, to_u128;
+    i8, to_i8;
+    i16, to_i16;
+    i32, to_i32;
+    i64, to_i64;
+    i128, to_i128
 );
 
-pub struct ProgramIter<'a> {
-    node: Node<'a>,
+pub struct ProgramIter {
+    current: Program,
 }
-impl Iterator for ProgramIter<'_> {
+impl Iterator for ProgramIter {
     type Item = Program;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.node.next() {
-            Some(m_node) => match node_to_bytes(&m_node) {
-                Ok(bytes) => {
-                    let prog = Program::new(bytes);
-                    Some(prog)
-                }
-                Err(_) => None,
-            },
+        match self.current.as_pair() {
+            Some((first, rest)) => {
+                self.current = rest;
+                Some(first)
+            }
             None => None,
         }
     }
@@ -361,68 +481,239 @@ impl Iterator for ProgramIter<'_> {
 
 impl Clone for Program {
     fn clone(&self) -> Self {
-        Program::new(self.serialized.clone())
+        Program {
+            serialized: self.serialized.clone(),
+            alloc: self.alloc.clone(),
+            nodeptr: self.nodeptr,
+            owned: OnceLock::new(),
+        }
     }
 }
 
 impl Hash for Program {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.serialized.hash(state);
+        self.serialized().hash(state);
     }
 }
 
 impl PartialEq for Program {
     fn eq(&self, other: &Self) -> bool {
-        self.serialized == other.serialized
+        if Arc::ptr_eq(&self.alloc, &other.alloc) && self.nodeptr == other.nodeptr {
+            return true;
+        }
+        self.serialized() == other.serialized()
     }
 }
 impl Eq for Program {}
 
 impl fmt::Display for Program {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({})", encode(&self.serialized))
+        write!(f, "({})", encode(&self.serialized()))
+    }
+}
+
+impl fmt::Debug for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Program({})", encode(&self.serialized()))
     }
 }
 
 impl Program {
     pub fn new(serialized: Vec<u8>) -> Self {
-        let alloc = Allocator::new();
-        let null = alloc.null();
-        let mut prog = Program {
-            serialized: serialized.clone(),
-            alloc: alloc,
-            nodeptr: null,
+        let mut alloc = Allocator::new();
+        let nodeptr = match node_from_bytes_backrefs(&mut alloc, &serialized) {
+            Ok(node) => node,
+            Err(_) => alloc.null(),
         };
-        prog.set_node();
-        prog
+        Program::from_allocator(Arc::new(Mutex::new(alloc)), nodeptr)
     }
     pub fn null() -> Self {
         let alloc = Allocator::new();
         let null = alloc.null();
-        let serial = match node_to_bytes(&Node::new(&alloc, null)) {
+        Program::from_allocator(Arc::new(Mutex::new(alloc)), null)
+    }
+    pub fn from_allocator(alloc: Arc<Mutex<Allocator>>, nodeptr: NodePtr) -> Self {
+        let serialized = match node_to_bytes(&Node::new(&lock(&alloc), nodeptr)) {
             Ok(bytes) => bytes,
-            Err(_) => vec![],
-        };
-        let mut prog = Program {
-            serialized: serial,
-            alloc: alloc,
-            nodeptr: null,
+            Err(_) => Vec::new(),
         };
-        prog.set_node();
-        prog
+        Program {
+            serialized,
+            alloc,
+            nodeptr,
+            owned: OnceLock::new(),
+        }
     }
-    fn set_node(&mut self) {
-        self.nodeptr = match node_from_bytes(&mut self.alloc, &self.serialized) {
-            Ok(node) => node,
-            Err(_) => self.alloc.null(),
-        };
+    pub fn serialized(&self) -> Vec<u8> {
+        self.serialized.clone()
+    }
+    pub fn serialized_compressed(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let alloc = self.lock();
+        node_to_bytes_backrefs(&alloc, self.nodeptr)
     }
     pub fn tree_hash(&self) -> Bytes32 {
-        let mut alloc2 = Allocator2::new();
-        let nodeptr = match deserialize2(&mut alloc2, &self.serialized) {
-            Ok(node) => node,
-            Err(_) => alloc2.null(),
-        };
-        Bytes32::new(sha256tree(&mut alloc2, nodeptr).raw())
+        let alloc = self.lock();
+        tree_hash_node(&alloc, self.nodeptr)
+    }
+    fn lock(&self) -> MutexGuard<Allocator> {
+        lock(&self.alloc)
+    }
+    fn import(&self, other: &Program) -> Result<NodePtr, Box<dyn Error>> {
+        if Arc::ptr_eq(&self.alloc, &other.alloc) {
+            return Ok(other.nodeptr);
+        }
+        let (src, mut dst) = lock_both(&other.alloc, &self.alloc);
+        copy_node(&src, &mut dst, other.nodeptr)
+    }
+}
+
+fn lock(alloc: &Mutex<Allocator>) -> MutexGuard<Allocator> {
+    //A panic while holding the lock cannot leave the append-only allocator half written
+    alloc
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_both<'a>(
+    src: &'a Mutex<Allocator>,
+    dst: &'a Mutex<Allocator>,
+) -> (MutexGuard<'a, Allocator>, MutexGuard<'a, Allocator>) {
+    //Always lock in address order so two threads copying in opposite directions cannot deadlock
+    if (src as *const Mutex<Allocator>) < (dst as *const Mutex<Allocator>) {
+        let src = lock(src);
+        (src, lock(dst))
+    } else {
+        let dst = lock(dst);
+        (lock(src), dst)
+    }
+}
+
+fn copy_node(
+    src: &Allocator,
+    dst: &mut Allocator,
+    node: NodePtr,
+) -> Result<NodePtr, Box<dyn Error>> {
+    copy_node_memo(src, dst, node, &mut HashMap::new())
+}
+
+fn copy_node_memo(
+    src: &Allocator,
+    dst: &mut Allocator,
+    node: NodePtr,
+    copies: &mut HashMap<NodePtr, NodePtr>,
+) -> Result<NodePtr, Box<dyn Error>> {
+    //Shared subtrees are copied once so back-reference DAGs keep their shape
+    let mut ops: Vec<(NodePtr, bool)> = vec![(node, false)];
+    let mut values: Vec<NodePtr> = Vec::new();
+    while let Some((node, is_cons)) = ops.pop() {
+        if is_cons {
+            let rest = values.pop().ok_or("copy_node stack underflow")?;
+            let first = values.pop().ok_or("copy_node stack underflow")?;
+            match dst.new_pair(first, rest) {
+                Ok(ptr) => {
+                    copies.insert(node, ptr);
+                    values.push(ptr);
+                }
+                Err(error) => return Err(error.1.into()),
+            }
+        } else if let Some(ptr) = copies.get(&node) {
+            values.push(*ptr);
+        } else {
+            match src.sexp(node) {
+                Atom(_) => match dst.new_atom(src.atom(node)) {
+                    Ok(ptr) => {
+                        copies.insert(node, ptr);
+                        values.push(ptr);
+                    }
+                    Err(error) => return Err(error.1.into()),
+                },
+                Pair(first, rest) => {
+                    ops.push((node, true));
+                    ops.push((rest, false));
+                    ops.push((first, false));
+                }
+            }
+        }
+    }
+    match values.pop() {
+        Some(ptr) => Ok(ptr),
+        None => Err("copy_node produced no node".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clvm::curry_utils::assemble;
+    use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::MOD;
+
+    #[test]
+    fn test_program_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Program>();
+        let program: Program = vec![Program::from(1u8), "two".into()].into();
+        let serialized = program.serialized.clone();
+        let handle = std::thread::spawn(move || program.tree_hash());
+        assert_eq!(handle.join().unwrap(), Program::new(serialized).tree_hash());
+    }
+
+    #[test]
+    fn test_int_conversions() {
+        let program = Program::from(300u16);
+        assert_eq!(u16::try_from(&program).unwrap(), 300);
+        let value: u64 = program.clone().into();
+        assert_eq!(value, 300);
+        let negative: i32 = Program::from(-2i32).into();
+        assert_eq!(negative, -2);
+        assert!(u8::try_from(&program).is_err());
+        assert!(u8::try_from(&Program::from(vec![Program::null()])).is_err());
+    }
+
+    #[test]
+    fn test_sized_bytes_accessors() {
+        let program: Program = vec![7u8; 32].into();
+        assert_eq!(program.as_bytes32().unwrap(), Bytes32::new(vec![7u8; 32]));
+        assert!(program.as_bytes48().is_err());
+        let short: Program = vec![7u8; 31].into();
+        assert!(short.as_bytes32().is_err());
+        let padded: Bytes32 = (&short).into();
+        let mut expected = vec![7u8; 31];
+        expected.push(0);
+        assert_eq!(padded, Bytes32::new(expected));
+        let truncated: Bytes4 = (&program).into();
+        assert_eq!(truncated, Bytes4::new(vec![7u8; 4]));
+        let pair: Program = vec![program].into();
+        assert!(pair.as_bytes32().is_err());
+        let from_pair: Bytes32 = (&pair).into();
+        assert_eq!(from_pair, Bytes32::new(vec![0u8; 32]));
+    }
+
+    #[test]
+    fn test_matches_curried_puzzle() {
+        let mod_program = MOD.to_program().unwrap();
+        let key: Program = Bytes48::from(vec![3u8; 48]).into();
+        let curried = mod_program.curry(vec![key.clone()]).unwrap();
+        let pattern = assemble("(a (q . (: . function)) (c (q . (: . key)) (: . core)))")
+            .to_program()
+            .unwrap();
+        let bindings = curried.matches(&pattern).unwrap();
+        assert_eq!(bindings["function"], mod_program);
+        assert_eq!(bindings["key"], key);
+        assert_eq!(bindings["core"], Program::from(1u8));
+        assert!(key.matches(&pattern).is_none());
+    }
+
+    #[test]
+    fn test_matches_atoms_and_repeated_bindings() {
+        let atom_pattern = assemble("($ . name)").to_program().unwrap();
+        let atom: Program = "hello".into();
+        assert_eq!(atom.matches(&atom_pattern).unwrap()["name"], atom);
+        let pair: Program = vec![atom.clone()].into();
+        assert!(pair.matches(&atom_pattern).is_none());
+        let same_pattern = assemble("((: . x) (: . x))").to_program().unwrap();
+        let same: Program = vec![atom.clone(), atom.clone()].into();
+        assert_eq!(same.matches(&same_pattern).unwrap()["x"], atom);
+        let different: Program = vec![atom, "world".into()].into();
+        assert!(different.matches(&same_pattern).is_none());
     }
 }
@@ -0,0 +1,185 @@
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::clvm::clvm_traits::{FromClvm, ToClvm};
+use crate::clvm::curry_utils::uncurry;
+use crate::clvm::program::Program;
+use crate::clvm::serialized_program::SerializedProgram;
+use std::error::Error;
+
+pub trait PuzzleLayer: Sized {
+    fn parse_puzzle(puzzle: &Program) -> Result<Option<Self>, Box<dyn Error>>;
+}
+
+//Any puzzle is a valid innermost layer
+impl PuzzleLayer for Program {
+    fn parse_puzzle(puzzle: &Program) -> Result<Option<Self>, Box<dyn Error>> {
+        Ok(Some(puzzle.clone()))
+    }
+}
+
+pub trait LayerArgs: FromClvm {
+    fn mod_hash() -> Bytes32;
+    fn inner_puzzle(&self) -> Option<&Program> {
+        None
+    }
+}
+
+pub struct Layer<A: LayerArgs, I: PuzzleLayer> {
+    pub mod_program: Program,
+    pub args: A,
+    pub inner: Option<I>,
+}
+impl<A: LayerArgs + ToClvm, I: PuzzleLayer> Layer<A, I> {
+    pub fn puzzle(&self) -> Result<Program, Box<dyn Error>> {
+        let args: Vec<Program> = self.args.to_clvm()?.iter().collect();
+        self.mod_program.curry(args)
+    }
+}
+impl<A: LayerArgs, I: PuzzleLayer> PuzzleLayer for Layer<A, I> {
+    fn parse_puzzle(puzzle: &Program) -> Result<Option<Self>, Box<dyn Error>> {
+        let (mod_program, args) = match uncurry_puzzle(puzzle)? {
+            Some(uncurried) => uncurried,
+            None => return Ok(None),
+        };
+        if mod_program.tree_hash() != A::mod_hash() {
+            return Ok(None);
+        }
+        let args = A::from_clvm(&args)?;
+        let inner = match args.inner_puzzle() {
+            Some(inner_puzzle) => match I::parse_puzzle(inner_puzzle)? {
+                Some(inner) => Some(inner),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+        Ok(Some(Layer {
+            mod_program,
+            args,
+            inner,
+        }))
+    }
+}
+
+pub fn uncurry_puzzle(puzzle: &Program) -> Result<Option<(Program, Program)>, Box<dyn Error>> {
+    let serialized = SerializedProgram::from_bytes(&puzzle.serialized());
+    match uncurry(&serialized)? {
+        Some((mod_program, args)) => Ok(Some((mod_program.to_program()?, args.to_program()?))),
+        None => Ok(None),
+    }
+}
+
+pub fn parse_curried_args<A: FromClvm>(
+    puzzle: &Program,
+    mod_hash: &Bytes32,
+) -> Result<Option<A>, Box<dyn Error>> {
+    match uncurry_puzzle(puzzle)? {
+        Some((mod_program, args)) if mod_program.tree_hash() == *mod_hash => {
+            Ok(Some(A::from_clvm(&args)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::sized_bytes::Bytes48;
+    use crate::clvm::curry_utils::assemble;
+    use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::{puzzle_for_synthetic_public_key, MOD};
+    use crate::{FromClvm, ToClvm};
+
+    const STANDARD_MOD_HASH: &str =
+        "e9aaa49f45bad5c889b86ee3341550c155cfdd10c3a6757de618d20612fffd52";
+
+    #[derive(ToClvm, FromClvm)]
+    struct StandardArgs {
+        synthetic_key: Bytes48,
+    }
+    impl LayerArgs for StandardArgs {
+        fn mod_hash() -> Bytes32 {
+            Bytes32::from(hex::decode(STANDARD_MOD_HASH).unwrap())
+        }
+    }
+
+    #[derive(ToClvm, FromClvm)]
+    struct WrapperArgs {
+        inner_puzzle: Program,
+    }
+    impl LayerArgs for WrapperArgs {
+        fn mod_hash() -> Bytes32 {
+            wrapper_mod().tree_hash()
+        }
+        fn inner_puzzle(&self) -> Option<&Program> {
+            Some(&self.inner_puzzle)
+        }
+    }
+
+    fn wrapper_mod() -> Program {
+        assemble("(a 2 3)").to_program().unwrap()
+    }
+
+    fn synthetic_key() -> Bytes48 {
+        Bytes48::from(vec![0x42u8; 48])
+    }
+
+    #[test]
+    fn test_standard_mod_hash() {
+        assert_eq!(
+            MOD.to_program().unwrap().tree_hash(),
+            StandardArgs::mod_hash()
+        );
+    }
+
+    #[test]
+    fn test_parse_standard_layer() {
+        let puzzle = puzzle_for_synthetic_public_key(&synthetic_key()).unwrap();
+        let layer = Layer::<StandardArgs, Program>::parse_puzzle(&puzzle)
+            .unwrap()
+            .unwrap();
+        assert_eq!(layer.args.synthetic_key, synthetic_key());
+        assert!(layer.inner.is_none());
+        assert_eq!(layer.puzzle().unwrap(), puzzle);
+        let args: StandardArgs = parse_curried_args(&puzzle, &StandardArgs::mod_hash())
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.synthetic_key, synthetic_key());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_puzzles() {
+        let other_mod = Program::from(1u8)
+            .curry(vec![synthetic_key().into()])
+            .unwrap();
+        assert!(Layer::<StandardArgs, Program>::parse_puzzle(&other_mod)
+            .unwrap()
+            .is_none());
+        assert!(
+            Layer::<StandardArgs, Program>::parse_puzzle(&MOD.to_program().unwrap())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Layer::<StandardArgs, Program>::parse_puzzle(&Program::null())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_layers() {
+        let inner = puzzle_for_synthetic_public_key(&synthetic_key()).unwrap();
+        let puzzle = wrapper_mod().curry(vec![inner.clone()]).unwrap();
+        let layer = Layer::<WrapperArgs, Layer<StandardArgs, Program>>::parse_puzzle(&puzzle)
+            .unwrap()
+            .unwrap();
+        assert_eq!(layer.args.inner_puzzle, inner);
+        let inner_layer = layer.inner.as_ref().unwrap();
+        assert_eq!(inner_layer.args.synthetic_key, synthetic_key());
+        assert_eq!(layer.puzzle().unwrap(), puzzle);
+        let wrapped_other = wrapper_mod().curry(vec![Program::from(1u8)]).unwrap();
+        assert!(
+            Layer::<WrapperArgs, Layer<StandardArgs, Program>>::parse_puzzle(&wrapped_other)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
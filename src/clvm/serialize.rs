@@ -0,0 +1,736 @@
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use clvmr::allocator::{Allocator, NodePtr, SExp};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+pub const MAX_SINGLE_BYTE: u8 = 0x7F;
+pub const BACK_REFERENCE: u8 = 0xFE;
+pub const CONS_BOX_MARKER: u8 = 0xFF;
+
+pub type TreeHash = [u8; 32];
+
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        match self.bytes.get(self.pos) {
+            Some(b) => {
+                self.pos += 1;
+                Ok(*b)
+            }
+            None => Err("bad encoding: unexpected end of input".into()),
+        }
+    }
+
+    pub fn read_slice(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.bytes.len() - self.pos < len {
+            return Err("bad encoding: unexpected end of input".into());
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+}
+
+pub fn decode_size(reader: &mut ByteReader, first_byte: u8) -> Result<u64, Box<dyn Error>> {
+    let mut bit_mask: u8 = 0x80;
+    let mut b = first_byte;
+    let mut bit_count = 0;
+    while b & bit_mask != 0 {
+        bit_count += 1;
+        b &= 0xFF ^ bit_mask;
+        bit_mask >>= 1;
+    }
+    if bit_count >= 7 {
+        return Err("bad encoding: invalid atom size prefix".into());
+    }
+    let mut size: u64 = b as u64;
+    if bit_count > 1 {
+        for byte in reader.read_slice(bit_count - 1)? {
+            size = (size << 8) | *byte as u64;
+        }
+    }
+    if size >= 0x400000000 {
+        return Err("bad encoding: atom too large".into());
+    }
+    Ok(size)
+}
+
+pub fn parse_atom<'a>(
+    reader: &mut ByteReader<'a>,
+    first_byte: u8,
+) -> Result<&'a [u8], Box<dyn Error>> {
+    if first_byte == 0x80 {
+        Ok(&[])
+    } else if first_byte <= MAX_SINGLE_BYTE {
+        let pos = reader.position();
+        Ok(&reader.bytes[pos - 1..pos])
+    } else {
+        let size = decode_size(reader, first_byte)?;
+        reader.read_slice(size as usize)
+    }
+}
+
+pub fn encode_atom(atom: &[u8], output: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+    let len = atom.len() as u64;
+    if len == 0 {
+        output.push(0x80);
+        return Ok(());
+    }
+    if len == 1 && atom[0] <= MAX_SINGLE_BYTE {
+        output.push(atom[0]);
+        return Ok(());
+    }
+    if len < 0x40 {
+        output.push(0x80 | len as u8);
+    } else if len < 0x2000 {
+        output.extend([0xC0 | (len >> 8) as u8, len as u8]);
+    } else if len < 0x100000 {
+        output.extend([0xE0 | (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+    } else if len < 0x8000000 {
+        output.extend([
+            0xF0 | (len >> 24) as u8,
+            (len >> 16) as u8,
+            (len >> 8) as u8,
+            len as u8,
+        ]);
+    } else if len < 0x400000000 {
+        output.extend([
+            0xF8 | (len >> 32) as u8,
+            (len >> 24) as u8,
+            (len >> 16) as u8,
+            (len >> 8) as u8,
+            len as u8,
+        ]);
+    } else {
+        return Err("atom too large to serialize".into());
+    }
+    output.extend(atom);
+    Ok(())
+}
+
+pub fn atom_serialized_length(atom: &[u8]) -> u64 {
+    let len = atom.len() as u64;
+    if len == 0 || (len == 1 && atom[0] <= MAX_SINGLE_BYTE) {
+        1
+    } else if len < 0x40 {
+        1 + len
+    } else if len < 0x2000 {
+        2 + len
+    } else if len < 0x100000 {
+        3 + len
+    } else if len < 0x8000000 {
+        4 + len
+    } else {
+        5 + len
+    }
+}
+
+pub fn hash_atom(atom: &[u8]) -> TreeHash {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(atom);
+    hasher.finalize().into()
+}
+
+pub fn hash_pair(first: &TreeHash, rest: &TreeHash) -> TreeHash {
+    let mut hasher = Sha256::new();
+    hasher.update([2u8]);
+    hasher.update(first);
+    hasher.update(rest);
+    hasher.finalize().into()
+}
+
+fn new_pair(
+    allocator: &mut Allocator,
+    first: NodePtr,
+    rest: NodePtr,
+) -> Result<NodePtr, Box<dyn Error>> {
+    match allocator.new_pair(first, rest) {
+        Ok(ptr) => Ok(ptr),
+        Err(error) => Err(error.1.into()),
+    }
+}
+
+fn msb_mask(byte: u8) -> u8 {
+    let mut mask = byte as u32;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    ((mask + 1) >> 1) as u8
+}
+
+fn path_bits(path: &[u8]) -> Vec<bool> {
+    //Returns the left/right steps of a clvm path atom, starting from the root
+    let mut rtn: Vec<bool> = Vec::new();
+    let first_byte_index = match path.iter().position(|b| *b != 0) {
+        Some(index) => index,
+        None => return rtn,
+    };
+    let end_bitmask = msb_mask(path[first_byte_index]);
+    let mut byte_index = path.len() - 1;
+    let mut bitmask: u8 = 0x01;
+    while byte_index > first_byte_index || bitmask < end_bitmask {
+        rtn.push(path[byte_index] & bitmask != 0);
+        if bitmask == 0x80 {
+            bitmask = 0x01;
+            byte_index -= 1;
+        } else {
+            bitmask <<= 1;
+        }
+    }
+    rtn
+}
+
+pub fn traverse_path(
+    allocator: &Allocator,
+    path: &[u8],
+    root: NodePtr,
+) -> Result<NodePtr, Box<dyn Error>> {
+    if path.iter().all(|b| *b == 0) {
+        return Ok(allocator.null());
+    }
+    let mut current = root;
+    for is_right in path_bits(path) {
+        match allocator.sexp(current) {
+            SExp::Pair(first, rest) => {
+                current = if is_right { rest } else { first };
+            }
+            SExp::Atom(_) => return Err("bad encoding: path into atom".into()),
+        }
+    }
+    Ok(current)
+}
+
+pub fn reversed_path_to_bytes(path: &[u8]) -> Vec<u8> {
+    //path holds the left(0)/right(1) steps from the target node up to the root
+    let mut value = BigUint::from(1u8);
+    for direction in path {
+        value = (value << 1usize) | BigUint::from(*direction);
+    }
+    value.to_bytes_be()
+}
+
+enum ParseOp {
+    SExp,
+    Cons,
+}
+
+pub fn node_from_bytes_backrefs(
+    allocator: &mut Allocator,
+    bytes: &[u8],
+) -> Result<NodePtr, Box<dyn Error>> {
+    let mut reader = ByteReader::new(bytes);
+    let mut values = allocator.null();
+    let mut ops = vec![ParseOp::SExp];
+    while let Some(op) = ops.pop() {
+        match op {
+            ParseOp::SExp => {
+                let b = reader.read_u8()?;
+                if b == CONS_BOX_MARKER {
+                    ops.push(ParseOp::Cons);
+                    ops.push(ParseOp::SExp);
+                    ops.push(ParseOp::SExp);
+                } else if b == BACK_REFERENCE {
+                    let first_byte = reader.read_u8()?;
+                    let path = parse_atom(&mut reader, first_byte)?;
+                    let node = traverse_path(allocator, path, values)?;
+                    values = new_pair(allocator, node, values)?;
+                } else {
+                    let atom = parse_atom(&mut reader, b)?;
+                    let node = match allocator.new_atom(atom) {
+                        Ok(ptr) => ptr,
+                        Err(error) => return Err(error.1.into()),
+                    };
+                    values = new_pair(allocator, node, values)?;
+                }
+            }
+            ParseOp::Cons => {
+                let (rest, stack) = match allocator.sexp(values) {
+                    SExp::Pair(first, stack) => (first, stack),
+                    SExp::Atom(_) => return Err("bad encoding: stack underflow".into()),
+                };
+                let (first, stack) = match allocator.sexp(stack) {
+                    SExp::Pair(first, stack) => (first, stack),
+                    SExp::Atom(_) => return Err("bad encoding: stack underflow".into()),
+                };
+                let node = new_pair(allocator, first, rest)?;
+                values = new_pair(allocator, node, stack)?;
+            }
+        }
+    }
+    match allocator.sexp(values) {
+        SExp::Pair(node, _) => Ok(node),
+        SExp::Atom(_) => Err("bad encoding: empty stack".into()),
+    }
+}
+
+pub fn serialized_length_from_bytes(bytes: &[u8]) -> Result<u64, Box<dyn Error>> {
+    let mut reader = ByteReader::new(bytes);
+    let mut ops_count: u64 = 1;
+    while ops_count > 0 {
+        ops_count -= 1;
+        let b = reader.read_u8()?;
+        if b == CONS_BOX_MARKER {
+            ops_count += 2;
+        } else if b == BACK_REFERENCE {
+            let first_byte = reader.read_u8()?;
+            parse_atom(&mut reader, first_byte)?;
+        } else {
+            parse_atom(&mut reader, b)?;
+        }
+    }
+    Ok(reader.position() as u64)
+}
+
+pub fn tree_hash_node(allocator: &Allocator, node: NodePtr) -> Bytes32 {
+    let info = node_hashes_and_lengths(allocator, node);
+    Bytes32::new(info[&node].0.to_vec())
+}
+
+fn node_hashes_and_lengths(
+    allocator: &Allocator,
+    root: NodePtr,
+) -> HashMap<NodePtr, (TreeHash, u64)> {
+    let mut info: HashMap<NodePtr, (TreeHash, u64)> = HashMap::new();
+    let mut stack: Vec<(NodePtr, bool)> = vec![(root, false)];
+    while let Some((node, children_done)) = stack.pop() {
+        if info.contains_key(&node) {
+            continue;
+        }
+        match allocator.sexp(node) {
+            SExp::Atom(_) => {
+                let atom = allocator.atom(node);
+                info.insert(node, (hash_atom(atom), atom_serialized_length(atom)));
+            }
+            SExp::Pair(first, rest) => {
+                if children_done {
+                    let (first_hash, first_len) = info[&first];
+                    let (rest_hash, rest_len) = info[&rest];
+                    info.insert(
+                        node,
+                        (hash_pair(&first_hash, &rest_hash), 1 + first_len + rest_len),
+                    );
+                } else {
+                    stack.push((node, true));
+                    stack.push((rest, false));
+                    stack.push((first, false));
+                }
+            }
+        }
+    }
+    info
+}
+
+struct ReadCacheLookup {
+    root_hash: TreeHash,
+    read_stack: Vec<(TreeHash, TreeHash)>,
+    count: HashMap<TreeHash, usize>,
+    parent_paths_for_child: HashMap<TreeHash, Vec<(TreeHash, u8)>>,
+}
+impl ReadCacheLookup {
+    fn new() -> Self {
+        let root_hash = hash_atom(&[]);
+        let mut count = HashMap::new();
+        count.insert(root_hash, 1);
+        ReadCacheLookup {
+            root_hash,
+            read_stack: Vec::new(),
+            count,
+            parent_paths_for_child: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, id: TreeHash) {
+        let new_root_hash = hash_pair(&id, &self.root_hash);
+        self.read_stack.push((id, self.root_hash));
+        *self.count.entry(id).or_insert(0) += 1;
+        *self.count.entry(new_root_hash).or_insert(0) += 1;
+        self.parent_paths_for_child
+            .entry(id)
+            .or_insert_with(Vec::new)
+            .push((new_root_hash, 0));
+        self.parent_paths_for_child
+            .entry(self.root_hash)
+            .or_insert_with(Vec::new)
+            .push((new_root_hash, 1));
+        self.root_hash = new_root_hash;
+    }
+
+    fn pop(&mut self) -> Result<TreeHash, Box<dyn Error>> {
+        let item = match self.read_stack.pop() {
+            Some(item) => item,
+            None => return Err("read stack underflow".into()),
+        };
+        *self.count.entry(item.0).or_insert(1) -= 1;
+        *self.count.entry(self.root_hash).or_insert(1) -= 1;
+        self.root_hash = item.1;
+        Ok(item.0)
+    }
+
+    fn pop2_and_cons(&mut self) -> Result<(), Box<dyn Error>> {
+        let rest = self.pop()?;
+        let first = self.pop()?;
+        *self.count.entry(first).or_insert(0) += 1;
+        *self.count.entry(rest).or_insert(0) += 1;
+        let new_obj_hash = hash_pair(&first, &rest);
+        self.parent_paths_for_child
+            .entry(first)
+            .or_insert_with(Vec::new)
+            .push((new_obj_hash, 0));
+        self.parent_paths_for_child
+            .entry(rest)
+            .or_insert_with(Vec::new)
+            .push((new_obj_hash, 1));
+        self.push(new_obj_hash);
+        Ok(())
+    }
+
+    fn find_path(&self, id: &TreeHash, serialized_length: u64) -> Option<Vec<u8>> {
+        //A back reference costs the 0xfe marker plus the path atom, so it has to be shorter than that
+        if serialized_length < 3 {
+            return None;
+        }
+        let max_path_length = (serialized_length as usize - 2) * 8 - 1;
+        let mut seen_ids: HashSet<TreeHash> = HashSet::new();
+        seen_ids.insert(*id);
+        let mut partial_paths: Vec<(TreeHash, Vec<u8>)> = vec![(*id, Vec::new())];
+        while !partial_paths.is_empty() {
+            let mut new_partial_paths: Vec<(TreeHash, Vec<u8>)> = Vec::new();
+            let mut found: Option<Vec<u8>> = None;
+            for (node, path) in &partial_paths {
+                if *node == self.root_hash {
+                    let bytes = reversed_path_to_bytes(path);
+                    if found
+                        .as_ref()
+                        .map(|f| bytes.len() < f.len())
+                        .unwrap_or(true)
+                    {
+                        found = Some(bytes);
+                    }
+                    continue;
+                }
+                if let Some(parent_paths) = self.parent_paths_for_child.get(node) {
+                    for (parent, direction) in parent_paths {
+                        if self.count.get(parent).cloned().unwrap_or(0) > 0
+                            && !seen_ids.contains(parent)
+                        {
+                            let mut new_path = path.clone();
+                            new_path.push(*direction);
+                            if new_path.len() > max_path_length {
+                                return None;
+                            }
+                            new_partial_paths.push((*parent, new_path));
+                        }
+                    }
+                }
+            }
+            if let Some(path) = found {
+                if 1 + atom_serialized_length(&path) < serialized_length {
+                    return Some(path);
+                }
+                return None;
+            }
+            for (parent, _) in &new_partial_paths {
+                seen_ids.insert(*parent);
+            }
+            partial_paths = new_partial_paths;
+        }
+        None
+    }
+}
+
+pub fn node_to_bytes_backrefs(
+    allocator: &Allocator,
+    node: NodePtr,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let info = node_hashes_and_lengths(allocator, node);
+    let mut output: Vec<u8> = Vec::new();
+    let mut read_op_stack: Vec<ParseOp> = vec![ParseOp::SExp];
+    let mut write_stack: Vec<NodePtr> = vec![node];
+    let mut read_cache_lookup = ReadCacheLookup::new();
+    while let Some(node_to_write) = write_stack.pop() {
+        read_op_stack.pop();
+        let (node_hash, node_length) = info[&node_to_write];
+        match read_cache_lookup.find_path(&node_hash, node_length) {
+            Some(path) => {
+                output.push(BACK_REFERENCE);
+                encode_atom(&path, &mut output)?;
+                read_cache_lookup.push(node_hash);
+            }
+            None => match allocator.sexp(node_to_write) {
+                SExp::Pair(first, rest) => {
+                    output.push(CONS_BOX_MARKER);
+                    write_stack.push(rest);
+                    write_stack.push(first);
+                    read_op_stack.push(ParseOp::Cons);
+                    read_op_stack.push(ParseOp::SExp);
+                    read_op_stack.push(ParseOp::SExp);
+                }
+                SExp::Atom(_) => {
+                    encode_atom(allocator.atom(node_to_write), &mut output)?;
+                    read_cache_lookup.push(node_hash);
+                }
+            },
+        }
+        while let Some(ParseOp::Cons) = read_op_stack.last() {
+            read_op_stack.pop();
+            read_cache_lookup.pop2_and_cons()?;
+        }
+    }
+    Ok(output)
+}
+
+enum HashNode {
+    Atom(TreeHash),
+    Pair(TreeHash, usize, usize),
+}
+impl HashNode {
+    fn hash(&self) -> &TreeHash {
+        match self {
+            HashNode::Atom(hash) => hash,
+            HashNode::Pair(hash, _, _) => hash,
+        }
+    }
+}
+
+pub fn tree_hash_from_bytes(bytes: &[u8]) -> Result<Bytes32, Box<dyn Error>> {
+    //Plain serializations only need a stack of hashes, back references need the tree shape
+    let mut reader = ByteReader::new(bytes);
+    let mut values: Vec<TreeHash> = Vec::new();
+    let mut ops = vec![ParseOp::SExp];
+    while let Some(op) = ops.pop() {
+        match op {
+            ParseOp::SExp => {
+                let b = reader.read_u8()?;
+                if b == CONS_BOX_MARKER {
+                    ops.push(ParseOp::Cons);
+                    ops.push(ParseOp::SExp);
+                    ops.push(ParseOp::SExp);
+                } else if b == BACK_REFERENCE {
+                    return tree_hash_from_bytes_backrefs(bytes);
+                } else {
+                    let atom = parse_atom(&mut reader, b)?;
+                    values.push(hash_atom(atom));
+                }
+            }
+            ParseOp::Cons => {
+                let rest = values.pop().ok_or("bad encoding: stack underflow")?;
+                let first = values.pop().ok_or("bad encoding: stack underflow")?;
+                values.push(hash_pair(&first, &rest));
+            }
+        }
+    }
+    match values.pop() {
+        Some(hash) => Ok(Bytes32::new(hash.to_vec())),
+        None => Err("bad encoding: empty stack".into()),
+    }
+}
+
+fn tree_hash_from_bytes_backrefs(bytes: &[u8]) -> Result<Bytes32, Box<dyn Error>> {
+    //Only hashes and child indexes are kept, atoms are hashed and dropped as they are read
+    let mut reader = ByteReader::new(bytes);
+    let mut arena: Vec<HashNode> = Vec::new();
+    let mut values: Vec<usize> = Vec::new();
+    let mut ops = vec![ParseOp::SExp];
+    while let Some(op) = ops.pop() {
+        match op {
+            ParseOp::SExp => {
+                let b = reader.read_u8()?;
+                if b == CONS_BOX_MARKER {
+                    ops.push(ParseOp::Cons);
+                    ops.push(ParseOp::SExp);
+                    ops.push(ParseOp::SExp);
+                } else if b == BACK_REFERENCE {
+                    let first_byte = reader.read_u8()?;
+                    let path = parse_atom(&mut reader, first_byte)?;
+                    let index = traverse_hash_path(&mut arena, &values, path)?;
+                    values.push(index);
+                } else {
+                    let atom = parse_atom(&mut reader, b)?;
+                    arena.push(HashNode::Atom(hash_atom(atom)));
+                    values.push(arena.len() - 1);
+                }
+            }
+            ParseOp::Cons => {
+                let rest = values.pop().ok_or("bad encoding: stack underflow")?;
+                let first = values.pop().ok_or("bad encoding: stack underflow")?;
+                let hash = hash_pair(arena[first].hash(), arena[rest].hash());
+                arena.push(HashNode::Pair(hash, first, rest));
+                values.push(arena.len() - 1);
+            }
+        }
+    }
+    match values.pop() {
+        Some(index) => Ok(Bytes32::new(arena[index].hash().to_vec())),
+        None => Err("bad encoding: empty stack".into()),
+    }
+}
+
+fn traverse_hash_path(
+    arena: &mut Vec<HashNode>,
+    values: &Vec<usize>,
+    path: &[u8],
+) -> Result<usize, Box<dyn Error>> {
+    //The parse stack acts as a list with the most recent value first, cells are indexed by depth
+    enum Cursor {
+        StackCell(usize),
+        Node(usize),
+    }
+    let mut cursor = Cursor::StackCell(0);
+    if !path.iter().all(|b| *b == 0) {
+        for is_right in path_bits(path) {
+            cursor = match cursor {
+                Cursor::StackCell(depth) => {
+                    if depth >= values.len() {
+                        return Err("bad encoding: path into atom".into());
+                    }
+                    if is_right {
+                        Cursor::StackCell(depth + 1)
+                    } else {
+                        Cursor::Node(values[values.len() - 1 - depth])
+                    }
+                }
+                Cursor::Node(index) => match arena[index] {
+                    HashNode::Pair(_, first, rest) => {
+                        Cursor::Node(if is_right { rest } else { first })
+                    }
+                    HashNode::Atom(_) => return Err("bad encoding: path into atom".into()),
+                },
+            };
+        }
+    } else {
+        arena.push(HashNode::Atom(hash_atom(&[])));
+        return Ok(arena.len() - 1);
+    }
+    match cursor {
+        Cursor::Node(index) => Ok(index),
+        Cursor::StackCell(depth) => {
+            arena.push(HashNode::Atom(hash_atom(&[])));
+            let mut index = arena.len() - 1;
+            for cell in (depth..values.len()).rev() {
+                let first = values[values.len() - 1 - cell];
+                let hash = hash_pair(arena[first].hash(), arena[index].hash());
+                arena.push(HashNode::Pair(hash, first, index));
+                index = arena.len() - 1;
+            }
+            Ok(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(allocator: &mut Allocator, bytes: &[u8]) -> NodePtr {
+        allocator.new_atom(bytes).unwrap()
+    }
+
+    fn list(allocator: &mut Allocator, items: &[NodePtr]) -> NodePtr {
+        let mut rtn = allocator.null();
+        for item in items.iter().rev() {
+            rtn = allocator.new_pair(*item, rtn).unwrap();
+        }
+        rtn
+    }
+
+    fn assert_round_trip(allocator: &mut Allocator, node: NodePtr) -> Vec<u8> {
+        let expected = tree_hash_node(allocator, node);
+        let compressed = node_to_bytes_backrefs(allocator, node).unwrap();
+        assert_eq!(tree_hash_from_bytes(&compressed).unwrap(), expected);
+        let decompressed = node_from_bytes_backrefs(allocator, &compressed).unwrap();
+        assert_eq!(tree_hash_node(allocator, decompressed), expected);
+        compressed
+    }
+
+    #[test]
+    fn test_msb_mask() {
+        assert_eq!(msb_mask(0x01), 0x01);
+        assert_eq!(msb_mask(0x2F), 0x20);
+        assert_eq!(msb_mask(0x80), 0x80);
+        assert_eq!(msb_mask(0xBF), 0x80);
+        assert_eq!(msb_mask(0xFF), 0x80);
+    }
+
+    #[test]
+    fn test_path_bits_high_byte() {
+        assert_eq!(path_bits(&[0x80]), vec![false; 7]);
+        assert_eq!(path_bits(&[0xFF]), vec![true; 7]);
+        assert_eq!(
+            path_bits(&[0x02, 0xFF]),
+            [vec![true; 8], vec![false]].concat()
+        );
+    }
+
+    #[test]
+    fn test_repeated_atom_back_reference() {
+        let mut allocator = Allocator::new();
+        let repeated = atom(&mut allocator, &[0x5A; 40]);
+        let node = allocator.new_pair(repeated, repeated).unwrap();
+        let compressed = assert_round_trip(&mut allocator, node);
+        let mut expected = vec![CONS_BOX_MARKER, 0x80 | 40];
+        expected.extend([0x5A; 40]);
+        expected.extend([BACK_REFERENCE, 0x02]);
+        assert_eq!(compressed, expected);
+    }
+
+    #[test]
+    fn test_long_back_reference_paths() {
+        //The repeated atom sits deeper in the parse stack for every filler item in between, the
+        //trailing item keeps the list tail from matching the parse stack itself
+        for fillers in 0..12u8 {
+            let mut allocator = Allocator::new();
+            let repeated = atom(&mut allocator, &[0xA5; 40]);
+            let mut items = vec![repeated];
+            for filler in 1..=fillers {
+                items.push(atom(&mut allocator, &[filler]));
+            }
+            items.push(repeated);
+            items.push(atom(&mut allocator, b"c"));
+            let node = list(&mut allocator, &items);
+            let compressed = assert_round_trip(&mut allocator, node);
+            assert!(
+                compressed.len() < 2 * 41,
+                "no back reference with {} fillers",
+                fillers
+            );
+            let path = reversed_path_to_bytes(&[vec![0u8], vec![1u8; fillers as usize]].concat());
+            let mut back_reference = vec![BACK_REFERENCE];
+            encode_atom(&path, &mut back_reference).unwrap();
+            assert!(compressed
+                .windows(back_reference.len())
+                .any(|w| w == back_reference.as_slice()));
+            match fillers {
+                6 => assert_eq!(path, vec![0xBF]),
+                8 => assert_eq!(path, vec![0x02, 0xFF]),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_subtree_round_trip() {
+        let mut allocator = Allocator::new();
+        let leaf = atom(&mut allocator, b"a shared leaf long enough to reference");
+        let mut node = leaf;
+        for _ in 0..8 {
+            node = allocator.new_pair(node, node).unwrap();
+        }
+        let compressed = assert_round_trip(&mut allocator, node);
+        let info = node_hashes_and_lengths(&allocator, node);
+        assert!((compressed.len() as u64) < info[&node].1);
+    }
+}
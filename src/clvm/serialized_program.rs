@@ -1,5 +1,10 @@
 use crate::blockchain::sized_bytes::hex_to_bytes;
+use crate::blockchain::sized_bytes::Bytes32;
 use crate::clvm::program::Program;
+use crate::clvm::serialize::{
+    node_from_bytes_backrefs, serialized_length_from_bytes, tree_hash_from_bytes,
+};
+use crate::clvm::trace::{node_to_text, LayerTracker, TracedRun, TracingDialect};
 use crate::clvm::utils::MEMPOOL_MODE;
 use clvmr::allocator::{Allocator, NodePtr};
 use clvmr::chia_dialect::ChiaDialect;
@@ -13,25 +18,30 @@ use std::error::Error;
 use std::fmt;
 use std::fs;
 use std::path::Path;
+use std::sync::OnceLock;
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct SerializedProgram {
     buffer: Vec<u8>,
+    tree_hash: OnceLock<Bytes32>,
 }
 impl SerializedProgram {
     pub fn from_file(path: &Path) -> SerializedProgram {
         SerializedProgram {
             buffer: fs::read(path).unwrap(),
+            tree_hash: OnceLock::new(),
         }
     }
     pub fn from_bytes(bytes: &Vec<u8>) -> SerializedProgram {
         SerializedProgram {
             buffer: bytes.clone(),
+            tree_hash: OnceLock::new(),
         }
     }
     pub fn from_hex(hex_str: String) -> SerializedProgram {
         SerializedProgram {
             buffer: hex_to_bytes(&hex_str).unwrap_or(Vec::new()),
+            tree_hash: OnceLock::new(),
         }
     }
     //pub fn uncurry(&self) -> (SerializedProgram, SerializedProgram) {}
@@ -60,6 +70,82 @@ impl SerializedProgram {
         self.run(allocator, max_cost, 0, args)
     }
 
+    pub fn run_with_flags(
+        &self,
+        allocator: &mut Allocator,
+        max_cost: Cost,
+        flags: u32,
+        args: &Program,
+    ) -> Result<(u64, NodePtr), Box<dyn Error>> {
+        self.run(allocator, max_cost, flags, args)
+    }
+
+    pub fn run_with_trace(
+        &self,
+        allocator: &mut Allocator,
+        max_cost: Cost,
+        flags: u32,
+        args: &Program,
+        max_trace_entries: usize,
+    ) -> Result<TracedRun, Box<dyn Error>> {
+        let program = node_from_bytes_backrefs(allocator, &self.buffer.as_slice())?;
+        let args = node_from_bytes(allocator, args.serialized().as_slice())?;
+        let dialect = TracingDialect::new(ChiaDialect::new(flags), max_trace_entries);
+        let layers = LayerTracker::new();
+        let response = run_program(
+            allocator,
+            &dialect,
+            program,
+            args,
+            max_cost,
+            Some(layers.pre_eval()),
+        );
+        let (trace, truncated) = dialect.take_trace();
+        match response {
+            Ok(reduct) => Ok(TracedRun {
+                cost: Some(reduct.0),
+                result: Some(reduct.1),
+                error: None,
+                error_node: None,
+                trace,
+                truncated,
+                stack: Vec::new(),
+            }),
+            Err(error) => Ok(TracedRun {
+                cost: None,
+                result: None,
+                error: Some(error.1.clone()),
+                error_node: Some(node_to_text(allocator, error.0)),
+                trace,
+                truncated,
+                stack: layers.stack(allocator),
+            }),
+        }
+    }
+
+    pub fn compressed(&self) -> Result<SerializedProgram, Box<dyn Error>> {
+        let program = Program::new(self.buffer.clone());
+        Ok(SerializedProgram::from_bytes(
+            &program.serialized_compressed()?,
+        ))
+    }
+
+    pub fn decompressed(&self) -> SerializedProgram {
+        SerializedProgram::from_bytes(&Program::new(self.buffer.clone()).serialized())
+    }
+
+    pub fn tree_hash(&self) -> Result<Bytes32, Box<dyn Error>> {
+        if let Some(hash) = self.tree_hash.get() {
+            return Ok(hash.clone());
+        }
+        let hash = tree_hash_from_bytes(&self.buffer)?;
+        Ok(self.tree_hash.get_or_init(|| hash).clone())
+    }
+
+    pub fn serialized_length(&self) -> Result<u64, Box<dyn Error>> {
+        serialized_length_from_bytes(&self.buffer)
+    }
+
     pub fn to_program<'a>(self) -> Result<Program, Box<dyn Error>> {
         Ok(Program::new(self.buffer.clone()))
     }
@@ -71,8 +157,8 @@ impl SerializedProgram {
         flags: u32,
         args: &Program,
     ) -> Result<(u64, NodePtr), Box<dyn Error>> {
-        let program = node_from_bytes(allocator, &self.buffer.as_slice())?;
-        let args = node_from_bytes(allocator, args.serialized.as_slice())?;
+        let program = node_from_bytes_backrefs(allocator, &self.buffer.as_slice())?;
+        let args = node_from_bytes(allocator, args.serialized().as_slice())?;
         let dialect = ChiaDialect::new(flags);
         match run_program(allocator, &dialect, program, args, max_cost, None) {
             Ok(reduct) => Ok((reduct.0, reduct.1)),
@@ -80,6 +166,13 @@ impl SerializedProgram {
         }
     }
 }
+impl PartialEq for SerializedProgram {
+    fn eq(&self, other: &Self) -> bool {
+        self.buffer == other.buffer
+    }
+}
+impl Eq for SerializedProgram {}
+
 impl From<String> for SerializedProgram {
     fn from(hex: String) -> Self {
         SerializedProgram::from_hex(hex)
@@ -0,0 +1,306 @@
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::clvm::disassembler::{disassemble_node_limited, KEYWORD_FROM_ATOM};
+use crate::clvm::serialize::tree_hash_node;
+use clvmr::allocator::{Allocator, NodePtr};
+use clvmr::cost::Cost;
+use clvmr::dialect::Dialect;
+use clvmr::reduction::{EvalErr, Reduction, Response};
+use clvmr::run_program::{PostEval, PreEval};
+use hex::encode;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+pub const DEFAULT_MAX_TRACE_ENTRIES: usize = 10000;
+const MAX_TRACE_TEXT_LENGTH: usize = 512;
+
+#[derive(Clone, Debug)]
+pub struct TraceEntry {
+    pub operator: String,
+    pub args: String,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub cost: Cost,
+}
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.result, &self.error) {
+            (Some(result), _) => write!(
+                f,
+                "({} {}) => {} [cost {}]",
+                self.operator, self.args, result, self.cost
+            ),
+            (None, Some(error)) => write!(f, "({} {}) FAILED: {}", self.operator, self.args, error),
+            (None, None) => write!(f, "({} {})", self.operator, self.args),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StackFrame {
+    pub puzzle_hash: Bytes32,
+    pub program: String,
+    pub env: String,
+}
+
+#[derive(Debug)]
+pub struct TracedRun {
+    pub cost: Option<Cost>,
+    pub result: Option<NodePtr>,
+    pub error: Option<String>,
+    pub error_node: Option<String>,
+    pub trace: Vec<TraceEntry>,
+    pub truncated: bool,
+    pub stack: Vec<StackFrame>,
+}
+impl TracedRun {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn stack_trace(&self) -> String {
+        let mut output = String::new();
+        match &self.error {
+            Some(error) => {
+                output.push_str(format!("FAIL: {}", error).as_str());
+                if let Some(node) = &self.error_node {
+                    output.push_str(format!(" {}", node).as_str());
+                }
+                output.push('\n');
+            }
+            None => {
+                output.push_str("OK\n");
+            }
+        }
+        for (depth, frame) in self.stack.iter().rev().enumerate() {
+            output.push_str(
+                format!(
+                    "  at layer {} puzzle_hash: {}\n    program: {}\n    env: {}\n",
+                    depth, frame.puzzle_hash, frame.program, frame.env
+                )
+                .as_str(),
+            );
+        }
+        if let Some(last) = self.trace.last() {
+            output.push_str(format!("  last operator: {}\n", last).as_str());
+        }
+        if self.truncated {
+            output.push_str("  (trace truncated)\n");
+        }
+        output
+    }
+}
+
+pub struct TracingDialect<D: Dialect> {
+    inner: D,
+    trace: RefCell<VecDeque<TraceEntry>>,
+    max_entries: usize,
+    truncated: RefCell<bool>,
+}
+impl<D: Dialect> TracingDialect<D> {
+    pub fn new(inner: D, max_entries: usize) -> Self {
+        TracingDialect {
+            inner,
+            trace: RefCell::new(VecDeque::new()),
+            max_entries,
+            truncated: RefCell::new(false),
+        }
+    }
+
+    pub fn take_trace(&self) -> (Vec<TraceEntry>, bool) {
+        (
+            self.trace.replace(VecDeque::new()).into_iter().collect(),
+            *self.truncated.borrow(),
+        )
+    }
+
+    fn record(&self, entry: TraceEntry) {
+        let mut trace = self.trace.borrow_mut();
+        if trace.len() >= self.max_entries {
+            trace.pop_front();
+            *self.truncated.borrow_mut() = true;
+        }
+        trace.push_back(entry);
+    }
+}
+impl<D: Dialect> Dialect for TracingDialect<D> {
+    fn quote_kw(&self) -> &[u8] {
+        self.inner.quote_kw()
+    }
+
+    fn apply_kw(&self) -> &[u8] {
+        self.inner.apply_kw()
+    }
+
+    fn op(
+        &self,
+        allocator: &mut Allocator,
+        op: NodePtr,
+        args: NodePtr,
+        max_cost: Cost,
+    ) -> Response {
+        let response = self.inner.op(allocator, op, args, max_cost);
+        let op_atom = allocator.atom(op).to_vec();
+        let operator = match KEYWORD_FROM_ATOM.get(&op_atom) {
+            Some(keyword) => keyword.clone(),
+            None => format!("0x{}", encode(&op_atom)),
+        };
+        let args_text = node_to_text(allocator, args);
+        match &response {
+            Ok(Reduction(cost, result)) => self.record(TraceEntry {
+                operator,
+                args: args_text,
+                result: Some(node_to_text(allocator, *result)),
+                error: None,
+                cost: *cost,
+            }),
+            Err(EvalErr(_, message)) => self.record(TraceEntry {
+                operator,
+                args: args_text,
+                result: None,
+                error: Some(message.clone()),
+                cost: 0,
+            }),
+        }
+        response
+    }
+}
+
+struct LayerFrame {
+    program: NodePtr,
+    args: NodePtr,
+    puzzle_hash: Bytes32,
+    is_layer: bool,
+}
+
+pub struct LayerTracker {
+    frames: Rc<RefCell<Vec<LayerFrame>>>,
+    hashes: Rc<RefCell<HashMap<NodePtr, Bytes32>>>,
+}
+impl LayerTracker {
+    pub fn new() -> Self {
+        LayerTracker {
+            frames: Rc::new(RefCell::new(Vec::new())),
+            hashes: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    pub fn pre_eval(&self) -> PreEval {
+        //Every environment change is tracked so it can be popped, but only a new puzzle hash starts a layer
+        let frames = self.frames.clone();
+        let hashes = self.hashes.clone();
+        Box::new(move |allocator, program, args| {
+            let layer_hash = match frames.borrow().last() {
+                Some(frame) if frame.args == args => return Ok(None),
+                Some(frame) => Some(frame.puzzle_hash.clone()),
+                None => None,
+            };
+            let puzzle_hash = hashes
+                .borrow_mut()
+                .entry(program)
+                .or_insert_with(|| tree_hash_node(allocator, program))
+                .clone();
+            let is_layer = layer_hash.as_ref() != Some(&puzzle_hash);
+            frames.borrow_mut().push(LayerFrame {
+                program,
+                args,
+                puzzle_hash: match (is_layer, layer_hash) {
+                    (false, Some(layer_hash)) => layer_hash,
+                    _ => puzzle_hash,
+                },
+                is_layer,
+            });
+            let post_frames = frames.clone();
+            let post_eval: Box<PostEval> = Box::new(move |_result| {
+                post_frames.borrow_mut().pop();
+            });
+            Ok(Some(post_eval))
+        })
+    }
+
+    pub fn stack(&self, allocator: &Allocator) -> Vec<StackFrame> {
+        let mut rtn: Vec<StackFrame> = Vec::new();
+        for frame in self.frames.borrow().iter() {
+            match rtn.last_mut() {
+                Some(last) if !frame.is_layer => {
+                    last.env = node_to_text(allocator, frame.args);
+                }
+                _ => rtn.push(StackFrame {
+                    puzzle_hash: frame.puzzle_hash.clone(),
+                    program: node_to_text(allocator, frame.program),
+                    env: node_to_text(allocator, frame.args),
+                }),
+            }
+        }
+        rtn
+    }
+}
+impl Default for LayerTracker {
+    fn default() -> Self {
+        LayerTracker::new()
+    }
+}
+
+pub fn node_to_text(allocator: &Allocator, node: NodePtr) -> String {
+    let mut output = String::new();
+    disassemble_node_limited(
+        allocator,
+        node,
+        Some(&KEYWORD_FROM_ATOM),
+        true,
+        &mut output,
+        MAX_TRACE_TEXT_LENGTH,
+    );
+    if output.len() > MAX_TRACE_TEXT_LENGTH {
+        let mut end = MAX_TRACE_TEXT_LENGTH;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+        output.push_str("...");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clvm::curry_utils::assemble;
+    use crate::clvm::program::Program;
+
+    #[test]
+    fn test_layers_follow_applied_puzzles() {
+        let outer = assemble("(a (q . (x 2)) (c 2 ()))");
+        let inner = assemble("(x 2)").to_program().unwrap();
+        let mut allocator = Allocator::new();
+        let run = outer
+            .run_with_trace(
+                &mut allocator,
+                1_000_000,
+                0,
+                &vec![Program::from(7u8)].into(),
+                100,
+            )
+            .unwrap();
+        assert!(!run.is_ok());
+        assert_eq!(run.stack.len(), 2);
+        assert_eq!(run.stack[0].puzzle_hash, outer.tree_hash().unwrap());
+        assert_eq!(run.stack[1].puzzle_hash, inner.tree_hash());
+        assert_eq!(run.stack[1].env, "(7)");
+    }
+
+    #[test]
+    fn test_recursion_stays_in_one_layer() {
+        let puzzle = assemble("(a (i 5 (q . (a 2 (c 2 (c (- 5 (q . 1)) ())))) (q . (x))) 1)");
+        let args: Program = vec![puzzle.clone().to_program().unwrap(), Program::from(3u8)].into();
+        let mut allocator = Allocator::new();
+        let run = puzzle
+            .run_with_trace(&mut allocator, 1_000_000, 0, &args, 100)
+            .unwrap();
+        assert!(!run.is_ok());
+        assert_eq!(run.stack.len(), 1);
+        assert_eq!(run.stack[0].puzzle_hash, puzzle.tree_hash().unwrap());
+        assert!(run.stack[0].env.ends_with(" ())"));
+    }
+}
@@ -1,4 +1,7 @@
 extern crate core;
+extern crate self as druid_garden_chia_types;
+
+pub use druid_garden_chia_types_derive::{FromClvm, ToClvm};
 
 pub mod blockchain;
 pub mod clvm;
@@ -1,3 +1,10 @@
+pub mod accounting;
+pub mod partials;
+pub mod payments;
+pub mod pool_error_code;
+pub mod post_partial_payload;
+pub mod post_partial_request;
+
 use crate::blockchain::coin_spend::CoinSpend;
 use crate::blockchain::sized_bytes::{Bytes32, Bytes48};
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,120 @@
+use crate::blockchain::pending_payment::PendingPayment;
+use crate::blockchain::sized_bytes::{hex_to_bytes, Bytes32};
+use crate::pool::Farmer;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DifficultyConfig {
+    pub number_of_partials_target: u64,
+    pub time_target: u64,
+    pub min_difficulty: u64,
+}
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        DifficultyConfig {
+            number_of_partials_target: 300,
+            time_target: 24 * 3600,
+            min_difficulty: 10,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct PayoutConfig {
+    pub pool_fee: f64,
+    pub pool_fee_puzzle_hash: Bytes32,
+}
+
+pub fn get_new_difficulty(
+    config: &DifficultyConfig,
+    recent_partials: &[(u64, u64)],
+    current_difficulty: u64,
+    current_time: u64,
+) -> u64 {
+    //recent_partials is (timestamp, difficulty) with the most recent partial first
+    if recent_partials.is_empty() {
+        return current_difficulty;
+    }
+    if recent_partials
+        .iter()
+        .any(|(_, difficulty)| *difficulty != current_difficulty)
+    {
+        return current_difficulty;
+    }
+    let last_timestamp = recent_partials[0].0;
+    let since_last = current_time.saturating_sub(last_timestamp);
+    if since_last > 3 * 3600 {
+        return config.min_difficulty.max(current_difficulty / 5);
+    }
+    if since_last > 3600 {
+        return config
+            .min_difficulty
+            .max((current_difficulty as f64 / 1.5) as u64);
+    }
+    let mut time_taken = recent_partials[0]
+        .0
+        .saturating_sub(recent_partials[recent_partials.len() - 1].0);
+    if (recent_partials.len() as u64) < config.number_of_partials_target
+        && time_taken < config.time_target
+    {
+        return current_difficulty;
+    }
+    if (recent_partials.len() as u64) < config.number_of_partials_target {
+        time_taken = (time_taken as u128 * config.number_of_partials_target as u128
+            / recent_partials.len() as u128) as u64;
+    }
+    if time_taken == 0 {
+        return current_difficulty;
+    }
+    let new_difficulty =
+        (current_difficulty as u128 * config.time_target as u128 / time_taken as u128) as u64;
+    config.min_difficulty.max(new_difficulty)
+}
+
+pub fn distribute_payouts(
+    config: &PayoutConfig,
+    amount: u64,
+    farmers: &[Farmer],
+) -> Result<Vec<PendingPayment>, Box<dyn Error>> {
+    if !(0.0..=1.0).contains(&config.pool_fee) {
+        return Err(format!("Invalid pool fee: {}", config.pool_fee).into());
+    }
+    let amount_to_distribute = (amount as f64 * (1.0 - config.pool_fee)) as u64;
+    let total_points: u128 = farmers.iter().map(|f| f.points as u128).sum();
+    let mut payments: Vec<PendingPayment> = Vec::new();
+    let mut distributed: u64 = 0;
+    if total_points > 0 {
+        let mojo_per_point = amount_to_distribute as u128 / total_points;
+        for farmer in farmers {
+            if farmer.points == 0 {
+                continue;
+            }
+            let puzzle_hash_bytes = hex_to_bytes(&farmer.payout_instructions)?;
+            if puzzle_hash_bytes.len() != 32 {
+                return Err(format!(
+                    "Invalid payout instructions for {}: {}",
+                    farmer.launcher_id, farmer.payout_instructions
+                )
+                .into());
+            }
+            let farmer_amount = (farmer.points as u128 * mojo_per_point) as u64;
+            if farmer_amount == 0 {
+                continue;
+            }
+            distributed += farmer_amount;
+            payments.push(PendingPayment {
+                puzzle_hash: puzzle_hash_bytes.into(),
+                amount: farmer_amount,
+            });
+        }
+    }
+    let pool_amount = amount - distributed;
+    if pool_amount > 0 {
+        payments.push(PendingPayment {
+            puzzle_hash: config.pool_fee_puzzle_hash.clone(),
+            amount: pool_amount,
+        });
+    }
+    Ok(payments)
+}
@@ -0,0 +1,153 @@
+use crate::blockchain::constants::Constants;
+use crate::blockchain::pot_iterations::{
+    calculate_iterations_quality, calculate_sp_interval_iters,
+};
+use crate::blockchain::sized_bytes::Bytes32;
+use crate::clvm::bls_bindings::aggregate_verify_signature;
+use crate::pool::pool_error_code::{PoolError, PoolErrorCode};
+use crate::pool::post_partial_request::PostPartialRequest;
+use crate::pool::Farmer;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn get_current_authentication_token(timeout: u8) -> u64 {
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0,
+    };
+    //A zero timeout would divide by zero, so it is treated as the smallest window
+    now / 60 / timeout.max(1) as u64
+}
+
+pub fn validate_authentication_token(token: u64, timeout: u8) -> bool {
+    if timeout == 0 {
+        return false;
+    }
+    let current_token = get_current_authentication_token(timeout);
+    let diff = if token > current_token {
+        token - current_token
+    } else {
+        current_token - token
+    };
+    diff <= timeout as u64
+}
+
+pub fn validate_partial(
+    constants: &Constants,
+    partial: &PostPartialRequest,
+    farmer: &Farmer,
+    challenge_hash: &Bytes32,
+    authentication_token_timeout: u8,
+) -> Result<u64, PoolError> {
+    let payload = &partial.payload;
+    let proof_of_space = &payload.proof_of_space;
+    if authentication_token_timeout == 0 {
+        return Err(PoolError::new(
+            PoolErrorCode::InvalidAuthenticationToken,
+            "Invalid authentication token timeout 0".to_string(),
+        ));
+    }
+    if !validate_authentication_token(payload.authentication_token, authentication_token_timeout) {
+        return Err(PoolError::new(
+            PoolErrorCode::InvalidAuthenticationToken,
+            format!(
+                "Invalid authentication token {}",
+                payload.authentication_token
+            ),
+        ));
+    }
+    if payload.launcher_id != farmer.launcher_id || !farmer.is_pool_member {
+        return Err(PoolError::new(
+            PoolErrorCode::FarmerNotKnown,
+            format!("Farmer with launcher_id {} not known.", payload.launcher_id),
+        ));
+    }
+    let quality_string = match proof_of_space.verify_and_get_quality_string(
+        challenge_hash,
+        &payload.sp_hash,
+        constants.min_plot_size,
+        constants.max_plot_size,
+    ) {
+        Ok(Some(quality_string)) => quality_string,
+        Ok(None) => {
+            return Err(PoolError::new(
+                PoolErrorCode::InvalidProof,
+                format!("Invalid proof of space {}", payload.sp_hash),
+            ));
+        }
+        Err(error) => {
+            return Err(PoolError::new(
+                PoolErrorCode::InvalidProof,
+                format!("Invalid proof of space {}: {}", payload.sp_hash, error),
+            ));
+        }
+    };
+    if proof_of_space.pool_contract_puzzle_hash.as_ref() != Some(&farmer.p2_singleton_puzzle_hash) {
+        return Err(PoolError::new(
+            PoolErrorCode::InvalidP2SingletonPuzzleHash,
+            format!(
+                "Invalid pool contract puzzle hash {:?}",
+                proof_of_space.pool_contract_puzzle_hash
+            ),
+        ));
+    }
+    let required_iters = calculate_iterations_quality(
+        constants.difficulty_constant_factor,
+        &quality_string,
+        proof_of_space.size,
+        farmer.difficulty,
+        &payload.sp_hash,
+    );
+    let sp_interval_iters =
+        match calculate_sp_interval_iters(constants, constants.pool_sub_slot_iters) {
+            Ok(iters) => iters,
+            Err(error) => {
+                return Err(PoolError::new(
+                    PoolErrorCode::ServerException,
+                    error.to_string(),
+                ));
+            }
+        };
+    if required_iters >= sp_interval_iters {
+        return Err(PoolError::new(
+            PoolErrorCode::ProofNotGoodEnough,
+            format!(
+                "Proof of space has required iters {}, too high for difficulty {}",
+                required_iters, farmer.difficulty
+            ),
+        ));
+    }
+    let message = payload.hash();
+    if !aggregate_verify_signature(
+        &vec![
+            &proof_of_space.plot_public_key,
+            &farmer.authentication_public_key,
+        ],
+        &vec![message.as_slice(), message.as_slice()],
+        &partial.aggregate_signature,
+    ) {
+        return Err(PoolError::new(
+            PoolErrorCode::InvalidSignature,
+            format!(
+                "The aggregate signature is invalid {}",
+                partial.aggregate_signature
+            ),
+        ));
+    }
+    Ok(farmer.difficulty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_timeout() {
+        assert_eq!(
+            get_current_authentication_token(0),
+            get_current_authentication_token(1)
+        );
+        let token = get_current_authentication_token(0);
+        assert!(!validate_authentication_token(token, 0));
+        assert!(validate_authentication_token(token, 1));
+    }
+}
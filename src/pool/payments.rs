@@ -0,0 +1,322 @@
+use crate::blockchain::coin::Coin;
+use crate::blockchain::coin_spend::CoinSpend;
+use crate::blockchain::condition_opcode::ConditionOpcode;
+use crate::blockchain::constants::Constants;
+use crate::blockchain::pending_payment::PendingPayment;
+use crate::blockchain::sized_bytes::{Bytes32, Bytes48, Bytes96, SizedBytes};
+use crate::blockchain::spend_bundle::SpendBundle;
+use crate::clvm::bls_bindings::aggregate_signatures;
+use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::{
+    puzzle_for_conditions, puzzle_for_synthetic_public_key, solution_for_delegated_puzzle,
+};
+use crate::clvm::program::Program;
+use crate::clvm::utils::hash_256;
+use std::error::Error;
+
+pub const DEFAULT_MAX_OUTPUTS_PER_BUNDLE: usize = 100;
+
+pub struct PayoutTransactionBuilder {
+    pub coins: Vec<Coin>,
+    pub payments: Vec<PendingPayment>,
+    pub fee: u64,
+    pub max_outputs_per_bundle: usize,
+    pub synthetic_public_key: Bytes48,
+    pub change_puzzle_hash: Bytes32,
+    pub agg_sig_me_additional_data: Bytes32,
+}
+impl PayoutTransactionBuilder {
+    pub fn new(
+        constants: &Constants,
+        synthetic_public_key: Bytes48,
+        change_puzzle_hash: Bytes32,
+        coins: Vec<Coin>,
+        payments: Vec<PendingPayment>,
+        fee: u64,
+    ) -> Self {
+        PayoutTransactionBuilder {
+            coins,
+            payments,
+            fee,
+            max_outputs_per_bundle: DEFAULT_MAX_OUTPUTS_PER_BUNDLE,
+            synthetic_public_key,
+            change_puzzle_hash,
+            agg_sig_me_additional_data: constants.genesis_challenge.clone(),
+        }
+    }
+
+    pub fn build<F>(&self, mut sign: F) -> Result<Vec<SpendBundle>, Box<dyn Error>>
+    where
+        F: FnMut(&Bytes48, &[u8]) -> Result<Bytes96, Box<dyn Error>>,
+    {
+        if self.max_outputs_per_bundle == 0 {
+            return Err("max_outputs_per_bundle must be greater than 0".into());
+        }
+        let puzzle = puzzle_for_synthetic_public_key(&self.synthetic_public_key)?;
+        let puzzle_hash = puzzle.tree_hash();
+        let mut available: Vec<Coin> = Vec::new();
+        for coin in &self.coins {
+            if coin.puzzle_hash != puzzle_hash {
+                return Err(format!(
+                    "Coin {} does not belong to synthetic public key {}",
+                    coin.name(),
+                    self.synthetic_public_key
+                )
+                .into());
+            }
+            available.push(coin.clone());
+        }
+        available.sort_by(|a, b| b.amount.cmp(&a.amount));
+        let mut bundles: Vec<SpendBundle> = Vec::new();
+        for chunk in self.payments.chunks(self.max_outputs_per_bundle) {
+            let mut spend_amount: u64 = self.fee;
+            for payment in chunk {
+                spend_amount = match spend_amount.checked_add(payment.amount) {
+                    Some(amount) => amount,
+                    None => return Err("Payment amount overflow".into()),
+                };
+            }
+            let mut selected: Vec<Coin> = Vec::new();
+            let mut selected_amount: u64 = 0;
+            while selected_amount < spend_amount {
+                if available.is_empty() {
+                    return Err(format!(
+                        "Insufficient funds for payout: need {} have {}",
+                        spend_amount, selected_amount
+                    )
+                    .into());
+                }
+                let coin = available.remove(0);
+                selected_amount += coin.amount;
+                selected.push(coin);
+            }
+            let change = selected_amount - spend_amount;
+            bundles.push(self.make_spend_bundle(&puzzle, &selected, chunk, change, &mut sign)?);
+        }
+        Ok(bundles)
+    }
+
+    fn make_spend_bundle<F>(
+        &self,
+        puzzle: &Program,
+        coins: &Vec<Coin>,
+        payments: &[PendingPayment],
+        change: u64,
+        sign: &mut F,
+    ) -> Result<SpendBundle, Box<dyn Error>>
+    where
+        F: FnMut(&Bytes48, &[u8]) -> Result<Bytes96, Box<dyn Error>>,
+    {
+        let mut coin_names: Vec<u8> = Vec::new();
+        for coin in coins {
+            coin_names.extend(coin.name().to_bytes());
+        }
+        let announcement_message = hash_256(coin_names);
+        let primary_coin = &coins[0];
+        let mut announcement_id: Vec<u8> = Vec::new();
+        announcement_id.extend(primary_coin.name().to_bytes());
+        announcement_id.extend(&announcement_message);
+        let announcement_id = hash_256(announcement_id);
+        let mut coin_spends: Vec<CoinSpend> = Vec::new();
+        let mut signatures: Vec<Bytes96> = Vec::new();
+        for (index, coin) in coins.iter().enumerate() {
+            let mut conditions: Vec<Program> = Vec::new();
+            if index == 0 {
+                for payment in payments {
+                    conditions.push(create_coin_condition(&payment.puzzle_hash, payment.amount));
+                }
+                if change > 0 {
+                    conditions.push(create_coin_condition(&self.change_puzzle_hash, change));
+                }
+                if self.fee > 0 {
+                    conditions.push(
+                        vec![
+                            Program::from(ConditionOpcode::ReserveFee as u8),
+                            Program::from(self.fee),
+                        ]
+                        .into(),
+                    );
+                }
+                conditions.push(
+                    vec![
+                        Program::from(ConditionOpcode::CreateCoinAnnouncement as u8),
+                        Program::from(announcement_message.clone()),
+                    ]
+                    .into(),
+                );
+            } else {
+                conditions.push(
+                    vec![
+                        Program::from(ConditionOpcode::AssertCoinAnnouncement as u8),
+                        Program::from(announcement_id.clone()),
+                    ]
+                    .into(),
+                );
+            }
+            let delegated_puzzle = puzzle_for_conditions(conditions);
+            let solution = solution_for_delegated_puzzle(&delegated_puzzle, &Program::null());
+            let mut message: Vec<u8> = Vec::new();
+            message.extend(delegated_puzzle.tree_hash().to_bytes());
+            message.extend(coin.name().to_bytes());
+            message.extend(self.agg_sig_me_additional_data.to_bytes());
+            signatures.push(sign(&self.synthetic_public_key, message.as_slice())?);
+            coin_spends.push(CoinSpend {
+                coin: coin.clone(),
+                puzzle_reveal: puzzle.clone().into(),
+                solution: solution.into(),
+            });
+        }
+        Ok(SpendBundle {
+            coin_spends,
+            aggregated_signature: aggregate_signatures(&signatures)?,
+        })
+    }
+}
+
+fn create_coin_condition(puzzle_hash: &Bytes32, amount: u64) -> Program {
+    vec![
+        Program::from(ConditionOpcode::CreateCoin as u8),
+        Program::from(puzzle_hash),
+        Program::from(amount),
+    ]
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::constants::MAINNET;
+    use crate::clvm::bls_bindings::aggregate_verify_signature;
+    use crate::clvm::p2_delegated_puzzle_or_hidden_puzzle::puzzle_hash_for_synthetic_public_key;
+    use blst::min_pk::SecretKey;
+
+    const AUG_SCHEME_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+
+    fn coin(parent: u8, puzzle_hash: &Bytes32, amount: u64) -> Coin {
+        Coin {
+            parent_coin_info: Bytes32::new(vec![parent; 32]),
+            puzzle_hash: puzzle_hash.clone(),
+            amount,
+        }
+    }
+
+    fn payment(target: u8, amount: u64) -> PendingPayment {
+        PendingPayment {
+            puzzle_hash: Bytes32::new(vec![target; 32]),
+            amount,
+        }
+    }
+
+    fn builder(
+        coin_amounts: &[u64],
+        payments: Vec<PendingPayment>,
+        fee: u64,
+    ) -> (SecretKey, PayoutTransactionBuilder) {
+        let secret_key = SecretKey::key_gen(&[7u8; 32], &[]).unwrap();
+        let public_key = Bytes48::new(secret_key.sk_to_pk().compress().to_vec());
+        let puzzle_hash = puzzle_hash_for_synthetic_public_key(&public_key).unwrap();
+        let coins = coin_amounts
+            .iter()
+            .enumerate()
+            .map(|(index, amount)| coin(index as u8, &puzzle_hash, *amount))
+            .collect();
+        let change_puzzle_hash = Bytes32::new(vec![0xCC; 32]);
+        let mut builder = PayoutTransactionBuilder::new(
+            &MAINNET,
+            public_key,
+            change_puzzle_hash,
+            coins,
+            payments,
+            fee,
+        );
+        builder.max_outputs_per_bundle = 2;
+        (secret_key, builder)
+    }
+
+    fn create_coins(spend: &CoinSpend) -> Vec<(Bytes32, u64)> {
+        let solution = spend.solution.clone().to_program().unwrap();
+        let conditions = solution.at("rfr").unwrap().as_list().unwrap();
+        conditions
+            .iter()
+            .filter(|condition| {
+                condition.first().unwrap().as_u64().unwrap() == ConditionOpcode::CreateCoin as u64
+            })
+            .map(|condition| {
+                (
+                    condition.at("rf").unwrap().as_bytes32().unwrap(),
+                    condition.at("rrf").unwrap().as_u64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_chunks_payments_with_change() {
+        let payments = vec![payment(1, 40), payment(2, 30), payment(3, 20)];
+        let (secret_key, builder) = builder(&[50, 100, 30], payments, 5);
+        let bundles = builder
+            .build(|public_key, message| {
+                let signature = secret_key.sign(message, AUG_SCHEME_DST, &public_key.to_bytes());
+                Ok(Bytes96::new(signature.compress().to_vec()))
+            })
+            .unwrap();
+        assert_eq!(bundles.len(), 2);
+
+        //The largest coin pays for the first chunk of two payments plus the fee
+        assert_eq!(bundles[0].coin_spends.len(), 1);
+        assert_eq!(bundles[0].coin_spends[0].coin.amount, 100);
+        assert_eq!(
+            create_coins(&bundles[0].coin_spends[0]),
+            vec![
+                (Bytes32::new(vec![1; 32]), 40),
+                (Bytes32::new(vec![2; 32]), 30),
+                (Bytes32::new(vec![0xCC; 32]), 25),
+            ]
+        );
+        assert_eq!(bundles[1].coin_spends[0].coin.amount, 50);
+        assert_eq!(
+            create_coins(&bundles[1].coin_spends[0]),
+            vec![
+                (Bytes32::new(vec![3; 32]), 20),
+                (Bytes32::new(vec![0xCC; 32]), 25),
+            ]
+        );
+
+        //Each spend is signed with AGG_SIG_ME over the delegated puzzle hash
+        for bundle in &bundles {
+            let mut messages: Vec<Vec<u8>> = Vec::new();
+            for spend in &bundle.coin_spends {
+                let solution = spend.solution.clone().to_program().unwrap();
+                let mut message = solution.at("rf").unwrap().tree_hash().to_bytes();
+                message.extend(spend.coin.name().to_bytes());
+                message.extend(MAINNET.genesis_challenge.to_bytes());
+                messages.push(message);
+            }
+            let public_keys = vec![&builder.synthetic_public_key; messages.len()];
+            let messages: Vec<&[u8]> = messages.iter().map(|message| message.as_slice()).collect();
+            assert!(aggregate_verify_signature(
+                &public_keys,
+                &messages,
+                &bundle.aggregated_signature
+            ));
+        }
+    }
+
+    #[test]
+    fn test_build_rejects_insufficient_funds() {
+        let (secret_key, builder) = builder(&[10, 10], vec![payment(1, 30)], 0);
+        let result = builder.build(|public_key, message| {
+            let signature = secret_key.sign(message, AUG_SCHEME_DST, &public_key.to_bytes());
+            Ok(Bytes96::new(signature.compress().to_vec()))
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_foreign_coins() {
+        let (_, mut builder) = builder(&[100], vec![payment(1, 30)], 0);
+        builder
+            .coins
+            .push(coin(9, &Bytes32::new(vec![0xAB; 32]), 100));
+        assert!(builder.build(|_, _| Err("Should not sign".into())).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum PoolErrorCode {
+    RevertedSignagePoint = 1,
+    TooLate = 2,
+    NotFound = 3,
+    InvalidProof = 4,
+    ProofNotGoodEnough = 5,
+    InvalidDifficulty = 6,
+    InvalidSignature = 7,
+    ServerException = 8,
+    InvalidP2SingletonPuzzleHash = 9,
+    FarmerNotKnown = 10,
+    FarmerAlreadyKnown = 11,
+    InvalidAuthenticationToken = 12,
+    InvalidPayoutInstructions = 13,
+    InvalidSingleton = 14,
+    DelayTimeTooShort = 15,
+    RequestFailed = 16,
+}
+impl PoolErrorCode {
+    pub fn from_u16(code: u16) -> Result<Self, Box<dyn Error>> {
+        match code {
+            1u16 => Ok(PoolErrorCode::RevertedSignagePoint),
+            2u16 => Ok(PoolErrorCode::TooLate),
+            3u16 => Ok(PoolErrorCode::NotFound),
+            4u16 => Ok(PoolErrorCode::InvalidProof),
+            5u16 => Ok(PoolErrorCode::ProofNotGoodEnough),
+            6u16 => Ok(PoolErrorCode::InvalidDifficulty),
+            7u16 => Ok(PoolErrorCode::InvalidSignature),
+            8u16 => Ok(PoolErrorCode::ServerException),
+            9u16 => Ok(PoolErrorCode::InvalidP2SingletonPuzzleHash),
+            10u16 => Ok(PoolErrorCode::FarmerNotKnown),
+            11u16 => Ok(PoolErrorCode::FarmerAlreadyKnown),
+            12u16 => Ok(PoolErrorCode::InvalidAuthenticationToken),
+            13u16 => Ok(PoolErrorCode::InvalidPayoutInstructions),
+            14u16 => Ok(PoolErrorCode::InvalidSingleton),
+            15u16 => Ok(PoolErrorCode::DelayTimeTooShort),
+            16u16 => Ok(PoolErrorCode::RequestFailed),
+            _ => Err(format!("Not a Valid Pool Error Code: {}", code).into()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PoolError {
+    pub error_code: PoolErrorCode,
+    pub error_message: String,
+}
+impl PoolError {
+    pub fn new(error_code: PoolErrorCode, error_message: String) -> Self {
+        PoolError {
+            error_code,
+            error_message,
+        }
+    }
+}
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}({}): {}",
+            self.error_code, self.error_code as u16, self.error_message
+        )
+    }
+}
+impl Error for PoolError {}
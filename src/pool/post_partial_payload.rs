@@ -0,0 +1,29 @@
+use crate::blockchain::proof_of_space::ProofOfSpace;
+use crate::blockchain::sized_bytes::{Bytes32, SizedBytes};
+use crate::clvm::utils::hash_256;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PostPartialPayload {
+    pub launcher_id: Bytes32,
+    pub authentication_token: u64,
+    pub proof_of_space: ProofOfSpace,
+    pub sp_hash: Bytes32,
+    pub end_of_sub_slot: bool,
+    pub harvester_id: Bytes32,
+}
+impl PostPartialPayload {
+    pub fn name(&self) -> Bytes32 {
+        self.hash().into()
+    }
+    pub fn hash(&self) -> Vec<u8> {
+        let mut to_hash: Vec<u8> = Vec::new();
+        to_hash.extend(&self.launcher_id.to_bytes());
+        to_hash.extend(self.authentication_token.to_be_bytes());
+        to_hash.extend(self.proof_of_space.to_bytes());
+        to_hash.extend(&self.sp_hash.to_bytes());
+        to_hash.push(self.end_of_sub_slot as u8);
+        to_hash.extend(&self.harvester_id.to_bytes());
+        hash_256(to_hash)
+    }
+}
@@ -0,0 +1,9 @@
+use crate::blockchain::sized_bytes::Bytes96;
+use crate::pool::post_partial_payload::PostPartialPayload;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PostPartialRequest {
+    pub payload: PostPartialPayload,
+    pub aggregate_signature: Bytes96,
+}